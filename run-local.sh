@@ -3,12 +3,13 @@ set -euo pipefail
 
 root_dir="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 interval="${1:-500}"
+scenario="${2:-wave}"
 
 cd "$root_dir"
 
 cargo build --bin fake_tegrastats
 cargo build --bin fake_nvidia_smi
-cargo run --bin jmon -- \
+FAKE_TEGRASTATS_SCENARIO="$scenario" cargo run --bin jmon -- \
   --tegrastats "$root_dir/target/debug/fake_tegrastats" \
   --nvidia-smi "$root_dir/target/debug/fake_nvidia_smi" \
   --interval "$interval"
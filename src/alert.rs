@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::model::{PowerSummary, StatsSnapshot, TempRange};
+
+/// Metric name the power threshold is reported under in `JMON_METRIC`.
+pub const POWER_METRIC: &str = "power_mw";
+
+/// A threshold that a reading rose to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+/// Runs the `--on-alert` command when a reading rises to its threshold.
+/// A metric fires again only after dropping below it and once `cooldown`
+/// has passed since it last fired, so a flapping value runs it rarely; one
+/// that is still above when the cooldown ends fires then.
+pub struct AlertHook {
+    command: String,
+    /// Celsius limits per sensor; names match case-insensitively.
+    temps: Vec<(String, f32)>,
+    power_mw: Option<u64>,
+    power_summary: PowerSummary,
+    temp_range: TempRange,
+    cooldown: Duration,
+    /// Metrics that fired and have not dropped below their threshold since.
+    above: HashSet<String>,
+    fired_at: HashMap<String, Instant>,
+    /// Commands still running, reaped on later runs.
+    children: Vec<Child>,
+}
+
+impl AlertHook {
+    pub fn new(
+        command: String,
+        temps: Vec<(String, f32)>,
+        power_mw: Option<u64>,
+        power_summary: PowerSummary,
+        temp_range: TempRange,
+        cooldown: Duration,
+    ) -> Self {
+        Self {
+            command,
+            temps,
+            power_mw,
+            power_summary,
+            temp_range,
+            cooldown,
+            above: HashSet::new(),
+            fired_at: HashMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// The thresholds `snapshot` is at or above that have not fired since the
+    /// reading rose and are not cooling down, marking them fired at `now`.
+    pub fn crossings(&mut self, snapshot: &StatsSnapshot, now: Instant) -> Vec<Alert> {
+        let mut readings = Vec::new();
+        for temp in &snapshot.temps {
+            if !self.temp_range.contains(temp.value_c) {
+                continue;
+            }
+            if let Some((_, limit)) = self
+                .temps
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&temp.name))
+            {
+                readings.push((temp.name.clone(), temp.value_c as f64, *limit as f64));
+            }
+        }
+        if let Some(limit) = self.power_mw
+            && let Some(total) = snapshot.total_power_mw(&self.power_summary)
+        {
+            readings.push((POWER_METRIC.to_string(), total as f64, limit as f64));
+        }
+
+        // A sensor that vanished or went out of range has to rise again.
+        self.above.retain(|metric| readings.iter().any(|(name, _, _)| name == metric));
+        let mut alerts = Vec::new();
+        for (metric, value, threshold) in readings {
+            if value < threshold {
+                self.above.remove(&metric);
+                continue;
+            }
+            let cooling = self
+                .fired_at
+                .get(&metric)
+                .is_some_and(|at| now.saturating_duration_since(*at) < self.cooldown);
+            if self.above.contains(&metric) || cooling {
+                continue;
+            }
+            self.above.insert(metric.clone());
+            self.fired_at.insert(metric.clone(), now);
+            alerts.push(Alert {
+                metric,
+                value,
+                threshold,
+            });
+        }
+        alerts
+    }
+
+    /// Runs the command through `sh -c` with `JMON_METRIC`, `JMON_VALUE` and
+    /// `JMON_THRESHOLD` set, without waiting for it.
+    pub fn fire(&mut self, alert: &Alert) -> Result<()> {
+        self.children.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("JMON_METRIC", &alert.metric)
+            .env("JMON_VALUE", format_value(alert.value))
+            .env("JMON_THRESHOLD", format_value(alert.threshold))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to run alert command `{}`", self.command))?;
+        self.children.push(child);
+        Ok(())
+    }
+}
+
+/// Whole numbers without a fraction, others with one decimal.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Alert, AlertHook, POWER_METRIC};
+    use crate::model::{PowerRail, PowerSummary, StatsSnapshot, TempRange, TempReading};
+
+    fn reading(tj: f32, power_mw: u64) -> StatsSnapshot {
+        StatsSnapshot {
+            temps: vec![TempReading {
+                name: "tj".to_string(),
+                value_c: tj,
+            }],
+            power_rails: vec![PowerRail {
+                name: "VDD_GPU".to_string(),
+                current_mw: power_mw,
+                average_mw: power_mw,
+            }],
+            ..StatsSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn fires_on_rising_edges_outside_the_cooldown() {
+        let mut hook = AlertHook::new(
+            "true".to_string(),
+            vec![("TJ".to_string(), 90.0)],
+            Some(10_000),
+            PowerSummary::default(),
+            TempRange::default(),
+            Duration::from_secs(60),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(hook.crossings(&reading(80.0, 5_000), at(0)).is_empty());
+        assert_eq!(
+            hook.crossings(&reading(91.5, 12_000), at(1)),
+            vec![
+                Alert {
+                    metric: "tj".to_string(),
+                    value: 91.5,
+                    threshold: 90.0,
+                },
+                Alert {
+                    metric: POWER_METRIC.to_string(),
+                    value: 12_000.0,
+                    threshold: 10_000.0,
+                },
+            ]
+        );
+        // Staying above does not fire again, and neither does flapping.
+        assert!(hook.crossings(&reading(92.0, 12_000), at(2)).is_empty());
+        assert!(hook.crossings(&reading(85.0, 5_000), at(3)).is_empty());
+        assert!(hook.crossings(&reading(91.0, 5_000), at(4)).is_empty());
+        assert!(hook.crossings(&reading(85.0, 5_000), at(70)).is_empty());
+        assert_eq!(hook.crossings(&reading(95.0, 5_000), at(71)).len(), 1);
+        // A reading outside the plausible range is ignored, and the sensor
+        // has to rise again once it is back.
+        assert!(hook.crossings(&reading(511.0, 5_000), at(200)).is_empty());
+        assert_eq!(hook.crossings(&reading(95.0, 5_000), at(201)).len(), 1);
+    }
+
+    #[test]
+    fn fires_once_the_cooldown_ends_if_still_above() {
+        let mut hook = AlertHook::new(
+            "true".to_string(),
+            vec![("tj".to_string(), 90.0)],
+            None,
+            PowerSummary::default(),
+            TempRange::default(),
+            Duration::from_secs(60),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(hook.crossings(&reading(91.0, 0), at(1)).len(), 1);
+        assert!(hook.crossings(&reading(85.0, 0), at(3)).is_empty());
+        let mut fired = 0;
+        for secs in 4..=70 {
+            fired += hook.crossings(&reading(92.0, 0), at(secs)).len();
+        }
+        assert_eq!(fired, 1);
+    }
+}
@@ -1,18 +1,133 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-fn main() {
-    let util = current_utilization();
-    println!("{}", util);
+const MEMORY_TOTAL_MB: u64 = 8192;
+
+/// `--query-gpu` columns this fake can answer, as nvidia-smi names them.
+const FIELDS: [&str; 6] = [
+    "index",
+    "utilization.gpu",
+    "memory.used",
+    "memory.total",
+    "temperature.gpu",
+    "power.draw",
+];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Without `--query-gpu` the fake keeps its original single-column output.
+    let fields = query_fields(&args).unwrap_or_else(|| vec!["utilization.gpu"]);
+    if let Some(unknown) = fields.iter().find(|field| !FIELDS.contains(field)) {
+        // Matches nvidia-smi, which rejects the whole query.
+        eprintln!("Field \"{}\" is not a valid field to query.", unknown);
+        return ExitCode::from(2);
+    }
+
+    let Some(period) = loop_period(args.iter().cloned()) else {
+        println!("{}", reading_row(&fields, now_secs()));
+        return ExitCode::SUCCESS;
+    };
+
+    let mut stdout = io::stdout();
+    loop {
+        if writeln!(stdout, "{}", reading_row(&fields, now_secs())).is_err()
+            || stdout.flush().is_err()
+        {
+            return ExitCode::SUCCESS;
+        }
+        thread::sleep(period);
+    }
+}
+
+/// The comma-separated names after `--query-gpu=` (or `--query-gpu <list>`).
+fn query_fields(args: &[String]) -> Option<Vec<&str>> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let list = match arg.strip_prefix("--query-gpu") {
+            Some("") => args.next()?.as_str(),
+            Some(rest) => rest.strip_prefix('=')?,
+            None => continue,
+        };
+        return Some(list.split(',').map(str::trim).collect());
+    }
+    None
+}
+
+/// Mirrors nvidia-smi's `-l <sec>` / `-lms <ms>` loop flags.
+fn loop_period(mut args: impl Iterator<Item = String>) -> Option<Duration> {
+    while let Some(arg) = args.next() {
+        let to_duration: fn(u64) -> Duration = match arg.as_str() {
+            "-l" => Duration::from_secs,
+            "-lms" => Duration::from_millis,
+            _ => continue,
+        };
+        return args.next()?.parse().ok().map(to_duration);
+    }
+    None
 }
 
-fn current_utilization() -> u64 {
-    let now = SystemTime::now()
+fn now_secs() -> f64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let t = now.as_secs_f64();
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// One `--format=csv,noheader,nounits` row with the requested columns.
+/// Memory, temperature and power follow utilization so they move together.
+fn reading_row(fields: &[&str], t: f64) -> String {
+    let util = utilization(t);
+    let load = util as f64 / 100.0;
+    fields
+        .iter()
+        .map(|field| match *field {
+            // A single fake GPU.
+            "index" => "0".to_string(),
+            "utilization.gpu" => util.to_string(),
+            "memory.used" => ((MEMORY_TOTAL_MB as f64 * (0.1 + 0.6 * load)).round() as u64)
+                .to_string(),
+            "memory.total" => MEMORY_TOTAL_MB.to_string(),
+            "temperature.gpu" => ((38.0 + 40.0 * load).round() as u64).to_string(),
+            "power.draw" => format!("{:.2}", 18.0 + 160.0 * load),
+            _ => "[N/A]".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn utilization(t: f64) -> u64 {
     let wave = (t * 0.7).sin() * 0.5 + 0.5;
     let jitter = ((t * 13.37).sin() * 0.5 + 0.5) * 6.0;
     let value = wave * 90.0 + 5.0 + jitter;
 
     value.round().clamp(0.0, 100.0) as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{query_fields, reading_row, utilization};
+
+    #[test]
+    fn answers_exactly_the_requested_columns() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let full = args(&[
+            "--query-gpu=utilization.gpu,memory.used,memory.total,temperature.gpu,power.draw",
+            "--format=csv,noheader,nounits",
+        ]);
+        let fields = query_fields(&full).expect("query");
+        let row = reading_row(&fields, 0.0);
+        let columns: Vec<&str> = row.split(", ").collect();
+
+        assert_eq!(columns.len(), 5);
+        assert_eq!(columns[0], utilization(0.0).to_string());
+        assert_eq!(columns[2], "8192");
+        assert!(columns[4].parse::<f64>().is_ok() && columns[4].contains('.'));
+        assert_eq!(
+            query_fields(&args(&["--query-gpu", "utilization.gpu"])),
+            Some(vec!["utilization.gpu"])
+        );
+        assert_eq!(query_fields(&args(&["-lms", "500"])), None);
+    }
+}
@@ -3,15 +3,21 @@ use std::io::{self, Write};
 use std::thread;
 use std::time::Duration;
 
+use chrono::{NaiveDate, NaiveDateTime};
+
 const RAM_TOTAL_MB: u64 = 125_772;
 const SWAP_TOTAL_MB: u64 = 8192;
-const CPU_CORES: usize = 14;
 const LFB_BLOCKS: u64 = 79;
 const LFB_SIZE_MB: u64 = 4;
+const SCENARIO_ENV: &str = "FAKE_TEGRASTATS_SCENARIO";
+const PROFILE_ENV: &str = "FAKE_TEGRASTATS_PROFILE";
 
 fn main() {
-    let interval_ms = parse_interval_ms().unwrap_or(1000).clamp(100, 5000);
-    let mut state = FakeState::new();
+    let Some((interval_ms, scenario, profile)) = parse_args() else {
+        return;
+    };
+    let interval_ms = interval_ms.unwrap_or(1000).clamp(100, 5000);
+    let mut state = FakeState::new(scenario, profile);
 
     loop {
         let line = state.next_line(interval_ms);
@@ -21,9 +27,19 @@ fn main() {
     }
 }
 
-fn parse_interval_ms() -> Option<u64> {
+/// Returns `None` when the process should exit (help or a bad scenario or profile).
+fn parse_args() -> Option<(Option<u64>, Scenario, Profile)> {
     let mut args = env::args().skip(1);
     let mut interval = None;
+    // jmon only passes --interval, so the scenario can also come from the environment.
+    let mut scenario = env::var(SCENARIO_ENV)
+        .ok()
+        .and_then(|name| Scenario::parse(&name))
+        .unwrap_or(Scenario::Wave);
+    let mut profile = env::var(PROFILE_ENV)
+        .ok()
+        .and_then(|name| Profile::parse(&name))
+        .unwrap_or(Profile::Orin);
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -32,56 +48,256 @@ fn parse_interval_ms() -> Option<u64> {
                     interval = value.parse::<u64>().ok();
                 }
             }
+            "--scenario" => {
+                let value = args.next().unwrap_or_default();
+                match Scenario::parse(&value) {
+                    Some(parsed) => scenario = parsed,
+                    None => {
+                        eprintln!(
+                            "unknown scenario `{}` (expected one of: {})",
+                            value,
+                            Scenario::NAMES.join(", ")
+                        );
+                        return None;
+                    }
+                }
+            }
+            "--profile" => {
+                let value = args.next().unwrap_or_default();
+                match Profile::parse(&value) {
+                    Some(parsed) => profile = parsed,
+                    None => {
+                        eprintln!(
+                            "unknown profile `{}` (expected one of: {})",
+                            value,
+                            Profile::NAMES.join(", ")
+                        );
+                        return None;
+                    }
+                }
+            }
             "--help" | "-h" => {
-                println!("fake_tegrastats --interval <ms>");
+                println!(
+                    "fake_tegrastats --interval <ms> [--scenario <{}>] [--profile <{}>]",
+                    Scenario::NAMES.join("|"),
+                    Profile::NAMES.join("|")
+                );
                 return None;
             }
             _ => {}
         }
     }
 
-    interval
+    Some((interval, scenario, profile))
+}
+
+/// Named shapes for the synthetic data, so demos and screenshots are reproducible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Scenario {
+    /// The default: every metric drifts on its own slow wave.
+    Wave,
+    /// Nearly idle board with a little noise.
+    Idle,
+    /// GPU alternates between 20s-period bursts near 100% and idle.
+    GpuBurst,
+    /// Load and temperatures climb steadily past the crit threshold.
+    ThermalRamp,
+    /// RAM use grows every tick while the largest free block shrinks.
+    MemoryLeak,
+}
+
+impl Scenario {
+    const NAMES: [&'static str; 5] = ["wave", "idle", "gpu-burst", "thermal-ramp", "memory-leak"];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "wave" => Some(Self::Wave),
+            "idle" => Some(Self::Idle),
+            "gpu-burst" => Some(Self::GpuBurst),
+            "thermal-ramp" => Some(Self::ThermalRamp),
+            "memory-leak" => Some(Self::MemoryLeak),
+            _ => None,
+        }
+    }
+
+    fn cpu_base(self, t: f64, core: usize) -> f64 {
+        let phase = core as f64 * 0.35;
+        match self {
+            Self::Idle => wave(t, 0.3, phase, 1.0, 6.0),
+            Self::ThermalRamp => (10.0 + t * 1.5).min(98.0),
+            Self::Wave | Self::GpuBurst | Self::MemoryLeak => {
+                wave(t, 0.6 + core as f64 * 0.02, phase, 2.0, 92.0)
+            }
+        }
+    }
+
+    fn gpu_base(self, t: f64) -> f64 {
+        match self {
+            Self::Idle => wave(t, 0.2, 0.3, 0.0, 3.0),
+            Self::GpuBurst if t % 20.0 < 8.0 => 96.0,
+            Self::GpuBurst => 4.0,
+            Self::ThermalRamp => (5.0 + t * 1.5).min(99.0),
+            Self::Wave | Self::MemoryLeak => wave(t, 0.35, 0.3, 5.0, 95.0),
+        }
+    }
+
+    fn ram_used(self, t: f64) -> f64 {
+        match self {
+            Self::Idle => 6200.0,
+            Self::MemoryLeak => 17842.0 + t * 400.0,
+            Self::Wave | Self::GpuBurst | Self::ThermalRamp => {
+                17842.0 + wave(t, 0.05, 0.0, -1800.0, 1800.0)
+            }
+        }
+    }
+
+    /// Extra degrees on top of the load-driven temperature.
+    fn temp_offset(self, t: f64) -> f64 {
+        match self {
+            Self::ThermalRamp => (t * 0.5).min(40.0),
+            _ => 0.0,
+        }
+    }
+
+    fn lfb_blocks(self, t: f64) -> u64 {
+        match self {
+            Self::MemoryLeak => (LFB_BLOCKS as f64 - t * 0.5).max(1.0) as u64,
+            _ => LFB_BLOCKS,
+        }
+    }
+}
+
+/// Which board's tegrastats layout to imitate. Every line starts with the
+/// `MM-DD-YYYY HH:MM:SS` stamp; the rest differs per profile:
+///
+/// - `orin` (default):
+///   `RAM u/tMB (lfb NxSMB) SWAP u/tMB CPU [p%@f,...x14] cpu@tC tj@tC soc012@tC soc345@tC
+///   VDD_GPU cmW/amW VDD_CPU_SOC_MSS cmW/amW VIN_SYS_5V0 cmW/amW VIN cmW/amW GR3D_FREQ p%
+///   EMC_FREQ p%`
+/// - `xavier` (JetPack 4): clocks after the utilization, generic sensor and rail
+///   names, rails without a unit, no VIN:
+///   `RAM u/tMB (lfb NxSMB) SWAP u/tMB (cached 0MB) CPU [p%@f,...x8] EMC_FREQ p%@2133
+///   GR3D_FREQ p%@1377 APE 150 AO@tC GPU@tC Tdiode@tC AUX@tC CPU@tC thermal@tC Tboard@tC
+///   GPU c/a CPU c/a SOC c/a CV c/a VDDRQ c/a SYS5V c/a`
+/// - `nano` (JetPack 4): an IRAM field, `POM_5V_*` rails without a unit, and no
+///   EMC_FREQ or GR3D_FREQ at all:
+///   `RAM u/tMB (lfb NxSMB) SWAP u/tMB (cached 0MB) IRAM 0/252kB(lfb 252kB) CPU [p%@f,...x4]
+///   APE 25 PLL@tC CPU@tC GPU@tC AO@tC thermal@tC POM_5V_IN c/a POM_5V_GPU c/a POM_5V_CPU c/a`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Profile {
+    Orin,
+    Xavier,
+    Nano,
+}
+
+impl Profile {
+    pub(crate) const NAMES: [&'static str; 3] = ["orin", "xavier", "nano"];
+
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "orin" => Some(Self::Orin),
+            "xavier" => Some(Self::Xavier),
+            "nano" => Some(Self::Nano),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cpu_cores(self) -> usize {
+        match self {
+            Self::Orin => 14,
+            Self::Xavier => 8,
+            Self::Nano => 4,
+        }
+    }
+
+    /// Sensor names with their offset from the first (reference) sensor.
+    pub(crate) fn sensors(self) -> &'static [(&'static str, f64)] {
+        match self {
+            Self::Orin => &[("cpu", 0.0), ("tj", 1.0), ("soc012", -0.3), ("soc345", 0.4)],
+            Self::Xavier => &[
+                ("AO", 0.0),
+                ("GPU", 1.5),
+                ("Tdiode", 3.0),
+                ("AUX", 0.5),
+                ("CPU", 2.0),
+                ("thermal", 1.4),
+                ("Tboard", -1.0),
+            ],
+            Self::Nano => &[
+                ("PLL", 0.0),
+                ("CPU", 2.5),
+                ("GPU", 1.5),
+                ("AO", 7.5),
+                ("thermal", 2.0),
+            ],
+        }
+    }
+
+    /// Rail names and readings in mW, given the modelled GPU, CPU, 5V system
+    /// and remaining board draw.
+    pub(crate) fn rails(self, gpu: f64, cpu: f64, sys: f64, rest: f64) -> Vec<(&'static str, f64)> {
+        match self {
+            Self::Orin => vec![
+                ("VDD_GPU", gpu),
+                ("VDD_CPU_SOC_MSS", cpu),
+                ("VIN_SYS_5V0", sys),
+                ("VIN", gpu + cpu + sys + rest),
+            ],
+            Self::Xavier => vec![
+                ("GPU", gpu * 0.6),
+                ("CPU", cpu * 0.3),
+                ("SOC", sys * 0.4),
+                ("CV", 0.0),
+                ("VDDRQ", rest * 0.05),
+                ("SYS5V", sys * 0.5),
+            ],
+            Self::Nano => vec![
+                ("POM_5V_IN", (gpu + cpu + sys) * 0.2),
+                ("POM_5V_GPU", gpu * 0.2),
+                ("POM_5V_CPU", cpu * 0.1),
+            ],
+        }
+    }
 }
 
-struct FakeState {
+pub(crate) struct FakeState {
+    scenario: Scenario,
+    profile: Profile,
     tick: u64,
     carry_ms: u64,
     seed: u64,
     clock: FakeClock,
-    avg_vdd_gpu: f64,
-    avg_vdd_cpu: f64,
-    avg_vin_sys: f64,
-    avg_vin: f64,
+    /// Smoothed average per rail, in the profile's rail order.
+    rail_avgs: Vec<f64>,
 }
 
 impl FakeState {
-    fn new() -> Self {
+    pub(crate) fn new(scenario: Scenario, profile: Profile) -> Self {
         Self {
+            scenario,
+            profile,
             tick: 0,
             carry_ms: 0,
             seed: 0x5eeda5,
             clock: FakeClock::new(2026, 1, 20, 22, 46, 22),
-            avg_vdd_gpu: 0.0,
-            avg_vdd_cpu: 0.0,
-            avg_vin_sys: 0.0,
-            avg_vin: 0.0,
+            rail_avgs: Vec::new(),
         }
     }
 
-    fn next_line(&mut self, interval_ms: u64) -> String {
+    pub(crate) fn next_line(&mut self, interval_ms: u64) -> String {
         let t = self.tick as f64 * interval_ms as f64 / 1000.0;
 
-        let mut cpu_utils = Vec::with_capacity(CPU_CORES);
-        for core in 0..CPU_CORES {
-            let phase = core as f64 * 0.35;
-            let base = wave(t, 0.6 + core as f64 * 0.02, phase, 2.0, 92.0);
+        let cores = self.profile.cpu_cores();
+        let mut cpu_utils = Vec::with_capacity(cores);
+        for core in 0..cores {
+            let base = self.scenario.cpu_base(t, core);
             let util = (base + self.jitter(6.0)).clamp(0.0, 100.0);
             cpu_utils.push(util);
         }
 
-        let cpu_total = cpu_utils.iter().sum::<f64>() / CPU_CORES as f64;
+        let cpu_total = cpu_utils.iter().sum::<f64>() / cores as f64;
 
-        let ram_used = (17842.0 + wave(t, 0.05, 0.0, -1800.0, 1800.0) + self.jitter(120.0))
+        let ram_used = (self.scenario.ram_used(t) + self.jitter(120.0))
             .clamp(8000.0, (RAM_TOTAL_MB - 1000) as f64)
             .round() as u64;
 
@@ -89,27 +305,43 @@ impl FakeState {
             .clamp(0.0, SWAP_TOTAL_MB as f64)
             .round() as u64;
 
-        let gpu_util = (wave(t, 0.35, 0.3, 5.0, 95.0) + self.jitter(4.0))
+        let gpu_util = (self.scenario.gpu_base(t) + self.jitter(4.0))
             .clamp(0.0, 100.0);
         let emc_util = (wave(t, 0.2, 1.1, 10.0, 90.0) + self.jitter(3.0))
             .clamp(0.0, 100.0);
 
-        let cpu_temp = 30.0 + cpu_total * 0.45 + self.jitter(0.4);
-        let tj_temp = cpu_temp + 1.0 + self.jitter(0.2);
-        let soc012_temp = cpu_temp - 0.3 + self.jitter(0.2);
-        let soc345_temp = cpu_temp + 0.4 + self.jitter(0.2);
+        let reference_temp =
+            30.0 + cpu_total * 0.45 + self.scenario.temp_offset(t) + self.jitter(0.4);
+        let mut temps = Vec::new();
+        for (idx, (name, offset)) in self.profile.sensors().iter().enumerate() {
+            let temp = if idx == 0 {
+                reference_temp
+            } else {
+                reference_temp + offset + self.jitter(0.2)
+            };
+            temps.push(format!("{}@{:.3}C", name, temp));
+        }
 
         let vdd_gpu = (200.0 + gpu_util * 25.0 + self.jitter(40.0)).max(0.0);
         let vdd_cpu = (4800.0 + cpu_total * 40.0 + self.jitter(120.0)).max(0.0);
         let vin_sys = (4800.0 + wave(t, 0.1, 0.7, -200.0, 200.0) + self.jitter(60.0))
             .max(0.0);
         let overhead = 6000.0 + wave(t, 0.08, 0.2, -250.0, 250.0) + self.jitter(50.0);
-        let vin = (vdd_gpu + vdd_cpu + vin_sys + overhead).max(0.0);
-
-        let vdd_gpu_avg = smooth(&mut self.avg_vdd_gpu, vdd_gpu);
-        let vdd_cpu_avg = smooth(&mut self.avg_vdd_cpu, vdd_cpu);
-        let vin_sys_avg = smooth(&mut self.avg_vin_sys, vin_sys);
-        let vin_avg = smooth(&mut self.avg_vin, vin);
+        let rails = self.profile.rails(vdd_gpu, vdd_cpu, vin_sys, overhead);
+        self.rail_avgs.resize(rails.len(), 0.0);
+        let rails = rails
+            .iter()
+            .zip(self.rail_avgs.iter_mut())
+            .map(|((name, current), avg)| {
+                let current = current.max(0.0);
+                let (current, avg) = (current.round() as u64, smooth(avg, current));
+                match self.profile {
+                    Profile::Orin => format!("{} {}mW/{}mW", name, current, avg),
+                    Profile::Xavier | Profile::Nano => format!("{} {}/{}", name, current, avg),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
 
         let cpu_list = cpu_utils
             .iter()
@@ -120,31 +352,32 @@ impl FakeState {
             .collect::<Vec<_>>()
             .join(",");
 
-        let line = format!(
-            "{} RAM {}/{}MB (lfb {}x{}MB) SWAP {}/{}MB CPU [{}] cpu@{:.3}C tj@{:.3}C soc012@{:.3}C soc345@{:.3}C VDD_GPU {}mW/{}mW VDD_CPU_SOC_MSS {}mW/{}mW VIN_SYS_5V0 {}mW/{}mW VIN {}mW/{}mW GR3D_FREQ {}% EMC_FREQ {}%",
-            self.clock.format(),
+        let memory = format!(
+            "RAM {}/{}MB (lfb {}x{}MB) SWAP {}/{}MB",
             ram_used,
             RAM_TOTAL_MB,
-            LFB_BLOCKS,
+            self.scenario.lfb_blocks(t),
             LFB_SIZE_MB,
             swap_used,
-            SWAP_TOTAL_MB,
-            cpu_list,
-            cpu_temp,
-            tj_temp,
-            soc012_temp,
-            soc345_temp,
-            vdd_gpu.round() as u64,
-            vdd_gpu_avg,
-            vdd_cpu.round() as u64,
-            vdd_cpu_avg,
-            vin_sys.round() as u64,
-            vin_sys_avg,
-            vin.round() as u64,
-            vin_avg,
-            gpu_util.round() as u64,
-            emc_util.round() as u64
+            SWAP_TOTAL_MB
         );
+        let (gpu_util, emc_util) = (gpu_util.round() as u64, emc_util.round() as u64);
+        let temps = temps.join(" ");
+        let body = match self.profile {
+            Profile::Orin => format!(
+                "{} CPU [{}] {} {} GR3D_FREQ {}% EMC_FREQ {}%",
+                memory, cpu_list, temps, rails, gpu_util, emc_util
+            ),
+            Profile::Xavier => format!(
+                "{} (cached 0MB) CPU [{}] EMC_FREQ {}%@2133 GR3D_FREQ {}%@1377 APE 150 {} {}",
+                memory, cpu_list, emc_util, gpu_util, temps, rails
+            ),
+            Profile::Nano => format!(
+                "{} (cached 0MB) IRAM 0/252kB(lfb 252kB) CPU [{}] APE 25 {} {}",
+                memory, cpu_list, temps, rails
+            ),
+        };
+        let line = format!("{} {}", self.clock.format(), body);
 
         self.tick += 1;
         self.advance_clock(interval_ms);
@@ -187,54 +420,99 @@ fn wave(t: f64, freq: f64, phase: f64, min: f64, max: f64) -> f64 {
 }
 
 struct FakeClock {
-    year: i32,
-    month: u32,
-    day: u32,
-    hour: u32,
-    minute: u32,
-    second: u32,
+    time: NaiveDateTime,
 }
 
 impl FakeClock {
     fn new(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Self {
-        Self {
-            year,
-            month,
-            day,
-            hour,
-            minute,
-            second,
-        }
+        let time = NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, second))
+            .expect("valid fake clock start");
+        Self { time }
     }
 
     fn tick(&mut self) {
-        self.second += 1;
-        if self.second >= 60 {
-            self.second = 0;
-            self.minute += 1;
-        }
-        if self.minute >= 60 {
-            self.minute = 0;
-            self.hour += 1;
-        }
-        if self.hour >= 24 {
-            self.hour = 0;
-            self.day += 1;
-        }
-        if self.day > 28 {
-            self.day = 1;
-            self.month += 1;
-        }
-        if self.month > 12 {
-            self.month = 1;
-            self.year += 1;
-        }
+        self.time += chrono::Duration::seconds(1);
     }
 
+    /// The `MM-DD-YYYY HH:MM:SS` prefix tegrastats stamps each line with.
     fn format(&self) -> String {
-        format!(
-            "{:02}-{:02}-{:04} {:02}:{:02}:{:02}",
-            self.month, self.day, self.year, self.hour, self.minute, self.second
-        )
+        self.time.format("%m-%d-%Y %H:%M:%S").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FakeClock, FakeState, Profile, Scenario};
+
+    fn field(line: &str, prefix: &str, end: char) -> u64 {
+        let (_, rest) = line.split_once(prefix).expect("field present");
+        rest.split(end).next().unwrap().parse().unwrap()
+    }
+
+    /// (RAM used MB, GPU %) per tick.
+    fn series(scenario: Scenario) -> Vec<(u64, u64)> {
+        let mut state = FakeState::new(scenario, Profile::Orin);
+        (0..60)
+            .map(|_| {
+                let line = state.next_line(1000);
+                (field(&line, "RAM ", '/'), field(&line, "GR3D_FREQ ", '%'))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scenarios_produce_distinct_series() {
+        let series: Vec<Vec<(u64, u64)>> = Scenario::NAMES
+            .iter()
+            .map(|name| series(Scenario::parse(name).expect("known scenario")))
+            .collect();
+        for (idx, a) in series.iter().enumerate() {
+            for b in &series[idx + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+
+        let idle = &series[1];
+        let burst = &series[2];
+        let leak = &series[4];
+        assert!(idle.iter().all(|(_, gpu)| *gpu <= 10));
+        assert!(burst.iter().any(|(_, gpu)| *gpu >= 90));
+        assert!(leak.last().unwrap().0 > leak[0].0 + 20_000);
+    }
+
+    #[test]
+    fn clock_rolls_over_months_and_leap_days() {
+        let mut clock = FakeClock::new(2026, 1, 31, 23, 59, 59);
+        clock.tick();
+        assert_eq!(clock.format(), "02-01-2026 00:00:00");
+
+        let mut clock = FakeClock::new(2026, 4, 30, 23, 59, 59);
+        clock.tick();
+        assert_eq!(clock.format(), "05-01-2026 00:00:00");
+
+        let mut clock = FakeClock::new(2028, 2, 28, 23, 59, 59);
+        clock.tick();
+        assert_eq!(clock.format(), "02-29-2028 00:00:00");
+    }
+
+    #[test]
+    fn profiles_switch_rails_and_clock_fields() {
+        let line = |profile| FakeState::new(Scenario::Wave, profile).next_line(1000);
+
+        let orin = line(Profile::Orin);
+        assert!(orin.contains(" VIN ") && orin.contains("GR3D_FREQ "));
+        let xavier = line(Profile::Xavier);
+        assert!(xavier.contains("EMC_FREQ ") && xavier.contains("%@1377"));
+        assert!(xavier.contains(" SYS5V ") && !xavier.contains(" VIN "));
+        let cores = |line: &str| {
+            let (_, rest) = line.split_once("CPU [").expect("CPU list");
+            rest.split(']').next().unwrap().split(',').count()
+        };
+        assert_eq!(cores(&xavier), 8);
+        let nano = line(Profile::Nano);
+        assert!(nano.contains(" POM_5V_IN ") && nano.contains("IRAM "));
+        assert!(!nano.contains("EMC_FREQ") && !nano.contains("GR3D_FREQ"));
+        assert_eq!(cores(&nano), 4);
     }
 }
@@ -0,0 +1,134 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+
+use crate::model::History;
+
+pub fn save_screen(buffer: &Buffer) -> Result<PathBuf> {
+    let path = PathBuf::from(format!(
+        "jmon-screen-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, buffer_to_ansi(buffer))
+        .with_context(|| format!("failed to write screen capture `{}`", path.display()))?;
+    Ok(path)
+}
+
+pub fn save_history_csv(history: &History, dir: &Path) -> Result<PathBuf> {
+    let path = dir.join(format!(
+        "jmon-history-{}.csv",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let file = File::create(&path)
+        .with_context(|| format!("failed to create history export `{}`", path.display()))?;
+    history
+        .to_csv(BufWriter::new(file), (Instant::now(), Local::now()))
+        .with_context(|| format!("failed to write history export `{}`", path.display()))?;
+    Ok(path)
+}
+
+fn buffer_to_ansi(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut out = String::new();
+
+    for y in area.top()..area.bottom() {
+        let mut current: Option<(Color, Color, Modifier)> = None;
+        for x in area.left()..area.right() {
+            let cell = buffer.get(x, y);
+            if cell.skip {
+                continue;
+            }
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if current != Some(style) {
+                out.push_str(&sgr(cell));
+                current = Some(style);
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+fn sgr(cell: &Cell) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if cell.modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    if let Some(code) = color_code(cell.fg, false) {
+        codes.push(code);
+    }
+    if let Some(code) = color_code(cell.bg, true) {
+        codes.push(code);
+    }
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let named = |code: u8| Some((code + offset).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => named(30),
+        Color::Red => named(31),
+        Color::Green => named(32),
+        Color::Yellow => named(33),
+        Color::Blue => named(34),
+        Color::Magenta => named(35),
+        Color::Cyan => named(36),
+        Color::Gray => named(37),
+        Color::DarkGray => named(90),
+        Color::LightRed => named(91),
+        Color::LightGreen => named(92),
+        Color::LightYellow => named(93),
+        Color::LightBlue => named(94),
+        Color::LightMagenta => named(95),
+        Color::LightCyan => named(96),
+        Color::White => named(97),
+        Color::Indexed(index) => Some(format!("{};5;{}", 38 + offset, index)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", 38 + offset, r, g, b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Modifier, Style};
+
+    use super::buffer_to_ansi;
+
+    #[test]
+    fn emits_sgr_codes_per_style_run() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 1));
+        buffer.set_string(
+            0,
+            0,
+            "ab",
+            Style::default()
+                .fg(Color::Rgb(1, 2, 3))
+                .add_modifier(Modifier::BOLD),
+        );
+        buffer.set_string(2, 0, "cd", Style::default().fg(Color::Red));
+
+        let ansi = buffer_to_ansi(&buffer);
+        assert_eq!(ansi, "\x1b[0;1;38;2;1;2;3mab\x1b[0;31mcd\x1b[0m\n");
+    }
+}
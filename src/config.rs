@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Defaults read from `config.toml`. Each key is named after the CLI option
+/// it seeds and takes the same value syntax; flags on the command line win.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub tegrastats: Option<String>,
+    pub nvidia_smi: Option<String>,
+    /// `flag`, `positional` or `none`, for tegrastats builds without `--interval`.
+    pub tegrastats_interval_style: Option<String>,
+    pub interval: Option<u64>,
+    pub history: Option<usize>,
+    pub palette: Option<String>,
+    pub temp_unit: Option<String>,
+    pub power_unit: Option<String>,
+    /// Panes to start hidden, e.g. `["swap", "power"]`.
+    pub hide: Option<Vec<String>>,
+    pub temp_range: Option<String>,
+    pub temp_heat: Option<String>,
+    pub percent_heat: Option<String>,
+    /// Celsius limits per sensor, e.g. `{ tj = 85, cpu = 90 }`.
+    pub throttle_threshold: Option<BTreeMap<String, f32>>,
+    /// Colors per power rail, e.g. `{ VDD_GPU_SOC = "#40c0ff" }`.
+    pub rail_color: Option<BTreeMap<String, String>>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/jmon/config.toml`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("jmon").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config `{}`", path.display()))?;
+        Self::parse(&text).with_context(|| format!("invalid config `{}`", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The config as `(arg id, value)` pairs, ready to be given to clap as
+    /// `--id=value` so values go through the same parsers as flags.
+    pub fn args(&self) -> Vec<(&'static str, String)> {
+        let mut args = Vec::new();
+        let mut push = |id: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push((id, value));
+            }
+        };
+        push("tegrastats", self.tegrastats.clone());
+        push("nvidia_smi", self.nvidia_smi.clone());
+        push("tegrastats_interval_style", self.tegrastats_interval_style.clone());
+        push("interval", self.interval.map(|ms| ms.to_string()));
+        push("history", self.history.map(|samples| samples.to_string()));
+        push("palette", self.palette.clone());
+        push("temp_unit", self.temp_unit.clone());
+        push("power_unit", self.power_unit.clone());
+        push("hide", self.hide.as_ref().map(|panes| panes.join(",")));
+        push("temp_range", self.temp_range.clone());
+        push("temp_heat", self.temp_heat.clone());
+        push("percent_heat", self.percent_heat.clone());
+        push(
+            "throttle_threshold",
+            self.throttle_threshold.as_ref().map(|limits| {
+                limits
+                    .iter()
+                    .map(|(sensor, limit)| format!("{}={}", sensor, limit))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        );
+        push(
+            "rail_color",
+            self.rail_color.as_ref().map(|colors| {
+                colors
+                    .iter()
+                    .map(|(rail, color)| format!("{}={}", rail, color))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        );
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn turns_keys_into_cli_args() {
+        let config = Config::parse(
+            "interval = 500\nhide = [\"swap\", \"power\"]\n\
+             [throttle-threshold]\ntj = 80\ncpu = 90.5\n\
+             [rail-color]\nVDD_GPU = \"#40c0ff\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.args(),
+            vec![
+                ("interval", "500".to_string()),
+                ("hide", "swap,power".to_string()),
+                ("throttle_threshold", "cpu=90.5,tj=80".to_string()),
+                ("rail_color", "VDD_GPU=#40c0ff".to_string()),
+            ]
+        );
+        assert!(Config::parse("intervall = 500").is_err());
+        assert!(Config::parse("interval = \"fast\"").is_err());
+    }
+}
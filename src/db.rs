@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use rusqlite::{params, Connection};
+
+use crate::model::{CpuAggregate, PowerSummary, StatsSnapshot};
+
+/// Snapshots written per transaction, so a soak test does not fsync every
+/// sample; at most this many are lost if jmon is killed.
+pub const BATCH_SIZE: usize = 10;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS samples (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        cpu_total REAL,
+        ram_used_mb INTEGER,
+        gpu_util REAL,
+        power_total_mw INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS cpu_cores (
+        sample_id INTEGER NOT NULL REFERENCES samples(id),
+        core INTEGER NOT NULL,
+        util REAL NOT NULL,
+        freq_mhz INTEGER,
+        PRIMARY KEY (sample_id, core)
+    );
+    CREATE TABLE IF NOT EXISTS power_rails (
+        sample_id INTEGER NOT NULL REFERENCES samples(id),
+        name TEXT NOT NULL,
+        current_mw INTEGER NOT NULL,
+        average_mw INTEGER NOT NULL,
+        PRIMARY KEY (sample_id, name)
+    );
+";
+
+/// Appends every snapshot to an SQLite file (`--db`): one `samples` row plus
+/// its `cpu_cores` and `power_rails` rows. Pending rows are committed on drop.
+pub struct SnapshotDb {
+    conn: Connection,
+    cpu_aggregate: CpuAggregate,
+    power_summary: PowerSummary,
+    pending: usize,
+}
+
+impl SnapshotDb {
+    pub fn open(
+        path: &Path,
+        cpu_aggregate: CpuAggregate,
+        power_summary: PowerSummary,
+    ) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open database `{}`", path.display()))?;
+        conn.execute_batch(SCHEMA)
+            .with_context(|| format!("failed to create tables in `{}`", path.display()))?;
+        Ok(Self {
+            conn,
+            cpu_aggregate,
+            power_summary,
+            pending: 0,
+        })
+    }
+
+    /// Queues `snapshot`, committing once [`BATCH_SIZE`] are pending. The
+    /// device timestamp is used when the line has one. A sample whose rows
+    /// fail to insert is rolled back whole; the rest of the batch is kept.
+    pub fn insert(&mut self, snapshot: &StatsSnapshot) -> Result<()> {
+        // A failed COMMIT can leave the batch open; it is then retried.
+        if self.conn.is_autocommit() {
+            self.conn.execute_batch("BEGIN")?;
+            self.pending = 0;
+        }
+        self.conn.execute_batch("SAVEPOINT sample")?;
+        if let Err(err) = self.insert_rows(snapshot) {
+            let _ = self.conn.execute_batch("ROLLBACK TO sample; RELEASE sample");
+            return Err(err);
+        }
+        self.conn.execute_batch("RELEASE sample")?;
+
+        self.pending += 1;
+        if self.pending >= BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn insert_rows(&self, snapshot: &StatsSnapshot) -> Result<()> {
+        let timestamp = snapshot
+            .timestamp
+            .unwrap_or_else(|| Local::now().naive_local())
+            .format("%Y-%m-%dT%H:%M:%S%.3f")
+            .to_string();
+        self.conn
+            .execute(
+                "INSERT INTO samples (timestamp, cpu_total, ram_used_mb, gpu_util, power_total_mw)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    timestamp,
+                    snapshot.cpu_total(self.cpu_aggregate),
+                    snapshot.ram_used_mb,
+                    snapshot.gpu_util,
+                    snapshot.total_power_mw(&self.power_summary),
+                ],
+            )
+            .context("failed to insert sample")?;
+        self.insert_children(self.conn.last_insert_rowid(), snapshot)
+    }
+
+    fn insert_children(&self, sample_id: i64, snapshot: &StatsSnapshot) -> Result<()> {
+        let mut core = self.conn.prepare_cached(
+            "INSERT INTO cpu_cores (sample_id, core, util, freq_mhz) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (index, util) in snapshot.cpu_cores.iter().enumerate() {
+            let freq = snapshot.cpu_freqs_mhz.get(index).copied().flatten();
+            core.execute(params![sample_id, index, util, freq])
+                .context("failed to insert per-core values")?;
+        }
+        let mut rail = self.conn.prepare_cached(
+            "INSERT INTO power_rails (sample_id, name, current_mw, average_mw)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for power in &snapshot.power_rails {
+            rail.execute(params![sample_id, power.name, power.current_mw, power.average_mw])
+                .context("failed to insert power rails")?;
+        }
+        Ok(())
+    }
+
+    /// Commits whatever is pending. If the commit fails (e.g. the file is
+    /// locked), the batch stays open and the next insert or flush retries it.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.conn.is_autocommit() {
+            self.pending = 0;
+            return Ok(());
+        }
+        self.conn.execute_batch("COMMIT").context("failed to commit samples")?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+impl Drop for SnapshotDb {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rusqlite::Connection;
+
+    use super::{SnapshotDb, BATCH_SIZE};
+    use crate::model::{CpuAggregate, PowerRail, PowerSummary, StatsSnapshot};
+
+    #[test]
+    fn writes_samples_with_cores_and_rails_in_batches() {
+        let path = std::env::temp_dir().join(format!("jmon-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let snapshot = StatsSnapshot {
+            cpu_cores: vec![20.0, 40.0],
+            cpu_freqs_mhz: vec![Some(1200), None],
+            ram_used_mb: Some(1024),
+            power_rails: vec![PowerRail {
+                name: "VDD_GPU".to_string(),
+                current_mw: 1200,
+                average_mw: 1100,
+            }],
+            ..StatsSnapshot::default()
+        };
+        let count = |table: &str| -> i64 {
+            let reader = Connection::open(&path).unwrap();
+            reader
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let mut db =
+            SnapshotDb::open(&path, CpuAggregate::Mean, PowerSummary::default()).unwrap();
+        for _ in 0..=BATCH_SIZE {
+            db.insert(&snapshot).unwrap();
+        }
+        // The last sample is still in the open transaction.
+        assert_eq!(count("samples"), BATCH_SIZE as i64);
+        drop(db);
+        assert_eq!(count("samples"), BATCH_SIZE as i64 + 1);
+        assert_eq!(count("cpu_cores"), 2 * (BATCH_SIZE as i64 + 1));
+
+        let reader = Connection::open(&path).unwrap();
+        let row: (f64, i64, i64) = reader
+            .query_row(
+                "SELECT s.cpu_total, s.power_total_mw, r.current_mw
+                 FROM samples s JOIN power_rails r ON r.sample_id = s.id LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(row, (30.0, 1200, 1200));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_rows_and_commits_do_not_wedge_later_samples() {
+        let path = std::env::temp_dir().join(format!("jmon-test-fail-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let rail = |name: &str| PowerRail {
+            name: name.to_string(),
+            current_mw: 1000,
+            average_mw: 1000,
+        };
+        let good = StatsSnapshot {
+            power_rails: vec![rail("VDD_GPU")],
+            ..StatsSnapshot::default()
+        };
+        // A repeated rail name breaks the `power_rails` key halfway through.
+        let bad = StatsSnapshot {
+            power_rails: vec![rail("VDD_GPU"), rail("VDD_GPU")],
+            ..StatsSnapshot::default()
+        };
+        let count = |conn: &Connection, table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let mut db =
+            SnapshotDb::open(&path, CpuAggregate::Mean, PowerSummary::default()).unwrap();
+        db.conn.busy_timeout(Duration::ZERO).unwrap();
+        db.insert(&good).unwrap();
+        assert!(db.insert(&bad).is_err());
+        db.insert(&good).unwrap();
+
+        // A reader holding the file makes the commit fail; the batch survives it.
+        let reader = Connection::open(&path).unwrap();
+        reader.execute_batch("BEGIN").unwrap();
+        assert_eq!(count(&reader, "samples"), 0);
+        assert!(db.flush().is_err());
+        reader.execute_batch("COMMIT").unwrap();
+
+        db.insert(&good).unwrap();
+        db.flush().unwrap();
+        assert_eq!(count(&reader, "samples"), 3);
+        assert_eq!(count(&reader, "power_rails"), 3);
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
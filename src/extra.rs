@@ -0,0 +1,83 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use anyhow::{Context, Result};
+
+/// Runs a user-supplied command that prints `key=value` lines and forwards
+/// each parsed metric.
+pub struct ExtraSourceRunner {
+    rx: Receiver<(String, f64)>,
+    child: Child,
+}
+
+impl ExtraSourceRunner {
+    pub fn spawn(command: &str) -> Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to start extra source `{}`", command))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .context("extra source stdout was not available")?;
+
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let reader = BufReader::new(stdout);
+            for line in reader.lines().map_while(Result::ok) {
+                for metric in parse_kv_line(&line) {
+                    let _ = tx.send(metric);
+                }
+            }
+        });
+
+        Ok(Self { rx, child })
+    }
+
+    pub fn try_recv(&self) -> Option<(String, f64)> {
+        self.rx.try_recv().ok()
+    }
+
+    pub fn shutdown(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Parses whitespace-separated `key=value` pairs, skipping anything that
+/// lacks a key or a numeric value.
+pub fn parse_kv_line(line: &str) -> Vec<(String, f64)> {
+    line.split_whitespace()
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let value = value.parse::<f64>().ok().filter(|value| value.is_finite())?;
+            (!key.is_empty()).then(|| (key.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_kv_line;
+
+    #[test]
+    fn parses_pairs_and_skips_malformed_tokens() {
+        let parsed = parse_kv_line("fan=42 board_temp=38.5 junk =3 rpm=fast nan=NaN v=-1");
+        assert_eq!(
+            parsed,
+            vec![
+                ("fan".to_string(), 42.0),
+                ("board_temp".to_string(), 38.5),
+                ("v".to_string(), -1.0),
+            ]
+        );
+        assert!(parse_kv_line("no metrics here").is_empty());
+    }
+}
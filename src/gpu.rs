@@ -1,47 +1,127 @@
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Output, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::{mpsc, Arc};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 
+use crate::model::GpuStats;
+
+/// Consecutive timed-out queries before nvidia-smi is reported unresponsive.
+const UNRESPONSIVE_AFTER: u32 = 3;
+/// How often the streaming reader wakes up to check for shutdown.
+const STREAM_POLL: Duration = Duration::from_millis(100);
+
+/// Field lists to query, richest first; the first one nvidia-smi accepts is
+/// kept. Temperature and power are only reported by discrete GPUs. Each row
+/// leads with the GPU index, so a skipped row cannot shift later GPUs.
+const FIELD_SETS: [&str; 3] = [
+    "index,utilization.gpu,memory.used,memory.total,temperature.gpu,power.draw",
+    "index,utilization.gpu,memory.used,memory.total",
+    "index,utilization.gpu",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GpuUpdate {
+    /// One reading per GPU, in nvidia-smi index order; never empty.
+    Stats(Vec<GpuStats>),
+    Unresponsive,
+    /// nvidia-smi answered none of the field sets; the runner has stopped.
+    Failed(String),
+}
+
 pub struct GpuUtilRunner {
-    rx: mpsc::Receiver<f32>,
+    rx: mpsc::Receiver<GpuUpdate>,
     stop: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>,
 }
 
 impl GpuUtilRunner {
-    pub fn spawn(path: &str, interval_ms: u64) -> Result<Self> {
+    /// `extra_args` go before the query options on every nvidia-smi run.
+    /// The field probe and the wait for the loop's first reading run on the
+    /// runner's thread, so a hung nvidia-smi never blocks the caller; if no
+    /// field set is answered, [`GpuUpdate::Failed`] is sent instead.
+    pub fn spawn(path: &str, extra_args: &[String], interval_ms: u64, timeout: Duration) -> Self {
         let stop = Arc::new(AtomicBool::new(false));
         let stop_thread = Arc::clone(&stop);
         let (tx, rx) = mpsc::channel();
         let path = path.to_string();
-
-        query_gpu_util(&path).context("nvidia-smi not available")?;
+        let extra_args = extra_args.to_vec();
 
         let handle = thread::spawn(move || {
+            // Older or integrated-GPU builds may reject the extra fields outright.
+            let (fields, probe) = match probe_fields(&path, &extra_args, timeout, &stop_thread) {
+                Ok(found) => found,
+                Err(err) => {
+                    let _ = tx.send(GpuUpdate::Failed(format!("{:#}", err)));
+                    return;
+                }
+            };
+            let gpu_count = probe.len().max(1);
+            // The probe is a reading too, so callers need not wait for the loop.
+            if !probe.is_empty() {
+                let _ = tx.send(GpuUpdate::Stats(probe));
+            }
+
+            let mut stream_command = Command::new(&path);
+            stream_command
+                .args(&extra_args)
+                .arg(format!("--query-gpu={}", fields))
+                .arg("--format=csv,noheader,nounits")
+                .arg("-lms")
+                .arg(interval_ms.to_string());
+            let interval = Duration::from_millis(interval_ms);
+            let first_timeout = interval + timeout;
+            if let Some(stream) =
+                start_stream(&mut stream_command, gpu_count, first_timeout, &stop_thread)
+            {
+                let stall = (interval + timeout) * UNRESPONSIVE_AFTER;
+                stream_gpu_util(stream, &tx, &stop_thread, stall);
+            }
+
+            // One query per interval, used when nvidia-smi cannot loop itself
+            // or the stream ended.
+            let mut timeouts = 0;
             while !stop_thread.load(Ordering::Relaxed) {
-                if let Ok(Some(util)) = query_gpu_util(&path) {
-                    let _ = tx.send(util);
+                match query_gpu_stats(&path, &extra_args, fields, timeout, &stop_thread) {
+                    Ok(gpus) if !gpus.is_empty() => {
+                        timeouts = 0;
+                        let _ = tx.send(GpuUpdate::Stats(gpus));
+                    }
+                    Err(err) if err.is::<QueryTimeout>() => {
+                        timeouts += 1;
+                        if timeouts == UNRESPONSIVE_AFTER {
+                            let _ = tx.send(GpuUpdate::Unresponsive);
+                        }
+                    }
+                    _ => {}
                 }
-                thread::sleep(Duration::from_millis(interval_ms));
+                sleep_unless_stopped(interval, &stop_thread);
             }
         });
 
-        Ok(Self {
+        Self {
             rx,
             stop,
             handle: Some(handle),
-        })
+        }
     }
 
-    pub fn try_recv(&self) -> Option<f32> {
+    pub fn try_recv(&self) -> Option<GpuUpdate> {
         self.rx.try_recv().ok()
     }
 
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<GpuUpdate> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// Stops the thread and any nvidia-smi it runs; every wait on the thread
+    /// checks for this, so it returns within a poll.
     pub fn shutdown(&mut self) {
         self.stop.store(true, Ordering::Relaxed);
         if let Some(handle) = self.handle.take() {
@@ -50,12 +130,62 @@ impl GpuUtilRunner {
     }
 }
 
-fn query_gpu_util(path: &str) -> Result<Option<f32>> {
-    let output = Command::new(path)
-        .arg("--query-gpu=utilization.gpu")
-        .arg("--format=csv,noheader,nounits")
-        .output()
-        .context("failed to run nvidia-smi")?;
+#[derive(Debug)]
+struct QueryTimeout(Duration);
+
+impl std::fmt::Display for QueryTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "nvidia-smi did not respond within {}ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for QueryTimeout {}
+
+/// Sleeps for `duration`, waking early once `stop` is set.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        thread::sleep(left.min(STREAM_POLL));
+    }
+}
+
+/// Finds the richest entry of [`FIELD_SETS`] this nvidia-smi answers.
+fn probe_fields(
+    path: &str,
+    extra_args: &[String],
+    timeout: Duration,
+    stop: &AtomicBool,
+) -> Result<(&'static str, Vec<GpuStats>)> {
+    let (last, richer) = FIELD_SETS.split_last().expect("field sets");
+    for fields in richer {
+        if let Ok(gpus) = query_gpu_stats(path, extra_args, fields, timeout, stop)
+            && !gpus.is_empty()
+        {
+            return Ok((fields, gpus));
+        }
+    }
+    let gpus = query_gpu_stats(path, extra_args, last, timeout, stop)
+        .context("nvidia-smi not available")?;
+    Ok((last, gpus))
+}
+
+fn query_gpu_stats(
+    path: &str,
+    extra_args: &[String],
+    fields: &str,
+    timeout: Duration,
+    stop: &AtomicBool,
+) -> Result<Vec<GpuStats>> {
+    let mut command = Command::new(path);
+    command
+        .args(extra_args)
+        .arg(format!("--query-gpu={}", fields))
+        .arg("--format=csv,noheader,nounits");
+    let output = output_with_timeout(&mut command, timeout, stop)?;
 
     if !output.status.success() {
         return Err(anyhow::anyhow!(
@@ -64,16 +194,309 @@ fn query_gpu_util(path: &str) -> Result<Option<f32>> {
         ));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let values: Vec<f32> = stdout
-        .lines()
-        .filter_map(|line| line.trim().parse::<f32>().ok())
-        .collect();
+    Ok(parse_stats_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses the per-GPU `index, util[, mem used, mem total[, temp, power]]`
+/// rows of a query into index order. Rows without an index and a numeric
+/// utilization (warnings) are skipped; other columns reading `[N/A]` are left
+/// `None`. Power is reported in watts.
+fn parse_stats_output(stdout: &str) -> Vec<GpuStats> {
+    let rows: BTreeMap<usize, GpuStats> = stdout.lines().filter_map(parse_stats_row).collect();
+    rows.into_values().collect()
+}
+
+fn parse_stats_row(line: &str) -> Option<(usize, GpuStats)> {
+    let mut fields = line.split(',').map(str::trim);
+    let index = fields.next()?.parse().ok()?;
+    let stats = GpuStats {
+        util: fields.next()?.parse().ok()?,
+        mem_used_mb: fields.next().and_then(|value| value.parse().ok()),
+        mem_total_mb: fields.next().and_then(|value| value.parse().ok()),
+        temp_c: fields.next().and_then(|value| value.parse().ok()),
+        power_mw: fields
+            .next()
+            .and_then(|value| value.parse::<f64>().ok())
+            .map(|watts| (watts * 1000.0).round() as u64),
+    };
+    Some((index, stats))
+}
+
+/// A long-running nvidia-smi printing one reading per loop.
+struct Stream {
+    child: Child,
+    /// The reading that proved the stream works.
+    first: Vec<GpuStats>,
+    readings: mpsc::Receiver<Vec<GpuStats>>,
+}
+
+/// Starts `command` and forwards its output in groups of `gpu_count` rows,
+/// one group per loop, in index order. A loop missing a GPU's row (e.g. an
+/// unparseable one) is dropped once an index repeats. Returns `None` if no
+/// full group arrives within `first_timeout`, e.g. because this nvidia-smi
+/// has no `-lms`, or once `stop` is set.
+fn start_stream(
+    command: &mut Command,
+    gpu_count: usize,
+    first_timeout: Duration,
+    stop: &AtomicBool,
+) -> Option<Stream> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let stdout = child.stdout.take()?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut group = BTreeMap::new();
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Some((index, row)) = parse_stats_row(&line) else {
+                continue;
+            };
+            if group.contains_key(&index) {
+                group.clear();
+            }
+            group.insert(index, row);
+            if group.len() == gpu_count {
+                let full = std::mem::take(&mut group).into_values().collect();
+                if tx.send(full).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let deadline = Instant::now() + first_timeout;
+    while !stop.load(Ordering::Relaxed) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left.min(STREAM_POLL)) {
+            Ok(first) => {
+                return Some(Stream {
+                    child,
+                    first,
+                    readings: rx,
+                });
+            }
+            Err(RecvTimeoutError::Timeout) if !left.is_zero() => {}
+            Err(_) => break,
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    None
+}
+
+/// Forwards streamed readings until shutdown or the stream ends, reporting
+/// nvidia-smi unresponsive once no reading has arrived for `stall`.
+fn stream_gpu_util(
+    stream: Stream,
+    tx: &mpsc::Sender<GpuUpdate>,
+    stop: &AtomicBool,
+    stall: Duration,
+) {
+    let Stream {
+        mut child,
+        first,
+        readings,
+    } = stream;
+    let _ = tx.send(GpuUpdate::Stats(first));
+    let mut last_reading = Instant::now();
+    let mut reported = false;
+    while !stop.load(Ordering::Relaxed) {
+        match readings.recv_timeout(STREAM_POLL) {
+            Ok(gpus) => {
+                last_reading = Instant::now();
+                reported = false;
+                let _ = tx.send(GpuUpdate::Stats(gpus));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if !reported && last_reading.elapsed() >= stall {
+                    reported = true;
+                    let _ = tx.send(GpuUpdate::Unresponsive);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Like `Command::output`, but kills the child if it runs longer than `timeout`
+/// or `stop` is set.
+fn output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+    stop: &AtomicBool,
+) -> Result<Output> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("failed to run nvidia-smi")?;
+    let started = Instant::now();
+
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed to wait for nvidia-smi")? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(QueryTimeout(timeout).into());
+        }
+        if stop.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("nvidia-smi query cancelled");
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let mut stdout = Vec::new();
+    if let Some(mut pipe) = child.stdout.take() {
+        pipe.read_to_end(&mut stdout)
+            .context("failed to read nvidia-smi output")?;
+    }
+
+    Ok(Output {
+        status,
+        stdout,
+        stderr: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+    use std::sync::atomic::AtomicBool;
+    use std::time::{Duration, Instant};
+
+    use super::{
+        output_with_timeout, parse_stats_output, start_stream, GpuUpdate, GpuUtilRunner,
+        QueryTimeout,
+    };
+    use crate::model::GpuStats;
+
+    static RUNNING: AtomicBool = AtomicBool::new(false);
+
+    #[cfg(unix)]
+    #[test]
+    fn kills_command_after_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let err =
+            output_with_timeout(&mut command, Duration::from_millis(50), &RUNNING).unwrap_err();
+        assert!(err.is::<QueryTimeout>());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawning_does_not_wait_for_nvidia_smi() {
+        // `sh -c` takes the query options as positional arguments.
+        let hung = ["-c".to_string(), "sleep 5".to_string()];
+        let started = Instant::now();
+        let mut runner = GpuUtilRunner::spawn("sh", &hung, 1000, Duration::from_secs(2));
+        assert!(runner.try_recv().is_none());
+        runner.shutdown();
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let missing = ["-c".to_string(), "exit 1".to_string()];
+        let runner = GpuUtilRunner::spawn("sh", &missing, 1000, Duration::from_secs(2));
+        assert!(matches!(
+            runner.recv_timeout(Duration::from_secs(2)),
+            Some(GpuUpdate::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn parses_one_row_per_gpu_and_skips_noise() {
+        assert_eq!(
+            parse_stats_output(
+                "1, 60, 2048, 8192\nWarning: persistence mode off\n0, 40, 1024, 8192\n"
+            ),
+            vec![
+                GpuStats {
+                    util: 40.0,
+                    mem_used_mb: Some(1024),
+                    mem_total_mb: Some(8192),
+                    ..GpuStats::default()
+                },
+                GpuStats {
+                    util: 60.0,
+                    mem_used_mb: Some(2048),
+                    mem_total_mb: Some(8192),
+                    ..GpuStats::default()
+                },
+            ]
+        );
+        assert_eq!(
+            parse_stats_output("0, 12, [N/A], [N/A]\n"),
+            vec![GpuStats {
+                util: 12.0,
+                mem_used_mb: None,
+                mem_total_mb: None,
+                ..GpuStats::default()
+            }]
+        );
+        assert_eq!(
+            parse_stats_output("0, 71, 3000, 8192, 54, 112.34
+1, 5, 10, 8192, [N/A], [N/A]
+"),
+            vec![
+                GpuStats {
+                    util: 71.0,
+                    mem_used_mb: Some(3000),
+                    mem_total_mb: Some(8192),
+                    temp_c: Some(54.0),
+                    power_mw: Some(112_340),
+                },
+                GpuStats {
+                    util: 5.0,
+                    mem_used_mb: Some(10),
+                    mem_total_mb: Some(8192),
+                    ..GpuStats::default()
+                },
+            ]
+        );
+        assert!(parse_stats_output("Unknown option -lms\n").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streams_lines_or_falls_back() {
+        let mut looping = Command::new("sh");
+        looping.arg("-c").arg("while true; do echo 0, 42; echo 1, 7; sleep 0.05; done");
+        let mut stream =
+            start_stream(&mut looping, 2, Duration::from_secs(2), &RUNNING).expect("stream");
+        let utils = |gpus: &[GpuStats]| gpus.iter().map(|gpu| gpu.util).collect::<Vec<_>>();
+        assert_eq!(utils(&stream.first), [42.0, 7.0]);
+        let next = stream.readings.recv_timeout(Duration::from_secs(1)).expect("second reading");
+        assert_eq!(utils(&next), [42.0, 7.0]);
+        stream.child.kill().unwrap();
+        stream.child.wait().unwrap();
+
+        let mut unsupported = Command::new("sh");
+        unsupported.arg("-c").arg("echo 'Invalid combination of input arguments' >&2; exit 1");
+        assert!(start_stream(&mut unsupported, 1, Duration::from_millis(500), &RUNNING).is_none());
+    }
 
-    if values.is_empty() {
-        Ok(None)
-    } else {
-        let avg = values.iter().sum::<f32>() / values.len() as f32;
-        Ok(Some(avg))
+    #[cfg(unix)]
+    #[test]
+    fn a_malformed_row_does_not_shift_gpu_indices() {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(
+            "echo 0, 42; echo 1, 7; echo 0, 43; echo '1, [N/A]'; \
+             echo 0, 44; echo 1, 8; sleep 5",
+        );
+        let mut stream =
+            start_stream(&mut command, 2, Duration::from_secs(2), &RUNNING).expect("stream");
+        let utils = |gpus: &[GpuStats]| gpus.iter().map(|gpu| gpu.util).collect::<Vec<_>>();
+        assert_eq!(utils(&stream.first), [42.0, 7.0]);
+        let next = stream.readings.recv_timeout(Duration::from_secs(1)).expect("second reading");
+        assert_eq!(utils(&next), [44.0, 8.0]);
+        stream.child.kill().unwrap();
+        stream.child.wait().unwrap();
     }
 }
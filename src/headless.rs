@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::alert::AlertHook;
+use crate::extra::ExtraSourceRunner;
+use crate::gpu::{GpuUpdate, GpuUtilRunner};
+use crate::metrics::MetricsServer;
+use crate::model::{
+    CpuAggregate, HeatBounds, PowerSummary, PowerUnit, StatsSnapshot, TempRange, TempUnit,
+};
+use crate::socket::SnapshotSocket;
+use crate::tegrastats::TegrastatsRunner;
+
+/// How often the stream wakes up to check for Ctrl-C while waiting on tegrastats.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+pub enum StreamEnd {
+    Interrupted,
+    Elapsed { crit_breached: bool },
+    /// stdout was closed, e.g. the reader of a pipe exited.
+    Closed,
+}
+
+/// The runners a headless mode merges into each snapshot.
+pub struct Sources<'a> {
+    pub tegrastats: &'a TegrastatsRunner,
+    pub gpu: Option<&'a GpuUtilRunner>,
+    /// `--extra-source`, whose latest values fill `extra`.
+    pub extra: Option<&'a ExtraSourceRunner>,
+}
+
+impl Sources<'_> {
+    /// Adds the metrics the extra source printed since the last call.
+    fn drain_extra(&self, extra: &mut HashMap<String, f64>) {
+        if let Some(runner) = self.extra {
+            extra.extend(iter::from_fn(|| runner.try_recv()));
+        }
+    }
+}
+
+/// Where [`stream_json`] hands each snapshot besides stdout.
+pub struct StreamSinks<'a> {
+    pub metrics: Option<&'a MetricsServer>,
+    pub socket: Option<&'a SnapshotSocket>,
+    pub alert_hook: Option<&'a mut AlertHook>,
+    /// Keep failing alert commands off stderr (`--silent`).
+    pub silent: bool,
+}
+
+/// Prints every snapshot as one line of JSON until interrupted, `duration`
+/// elapses, or stdout goes away.
+pub fn stream_json(
+    sources: &Sources,
+    duration: Option<Duration>,
+    temp_range: TempRange,
+    sinks: StreamSinks,
+) -> Result<StreamEnd> {
+    let StreamSinks {
+        metrics,
+        socket,
+        mut alert_hook,
+        silent,
+    } = sinks;
+    install_interrupt_handler();
+    let started_at = Instant::now();
+    let mut crit_breached = false;
+    let mut gpus = Vec::new();
+    let mut extra = HashMap::new();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return Ok(StreamEnd::Interrupted);
+        }
+        if duration.is_some_and(|duration| started_at.elapsed() >= duration) {
+            return Ok(StreamEnd::Elapsed { crit_breached });
+        }
+
+        if let Some(gpu_runner) = sources.gpu {
+            while let Some(update) = gpu_runner.try_recv() {
+                gpus = match update {
+                    GpuUpdate::Stats(stats) => stats,
+                    GpuUpdate::Unresponsive | GpuUpdate::Failed(_) => Vec::new(),
+                };
+            }
+        }
+        sources.drain_extra(&mut extra);
+
+        let Some(mut snapshot) = sources.tegrastats.recv_timeout(POLL_INTERVAL) else {
+            continue;
+        };
+        snapshot.set_gpus(&gpus);
+        snapshot.extra = extra.clone();
+        if let Some(metrics) = metrics {
+            metrics.publish(&snapshot);
+        }
+        if let Some(socket) = socket {
+            socket.publish(&snapshot);
+        }
+        crit_breached |= snapshot.crit_breached(temp_range);
+        if let Some(hook) = alert_hook.as_deref_mut() {
+            for alert in hook.crossings(&snapshot, Instant::now()) {
+                // stdout carries the JSON stream, so failures go to stderr.
+                if let Err(err) = hook.fire(&alert)
+                    && !silent
+                {
+                    eprintln!("warning: {:#}", err);
+                }
+            }
+        }
+
+        let line = snapshot_json(&snapshot)?;
+        let written = writeln!(stdout, "{}", line).and_then(|_| stdout.flush());
+        if let Err(err) = written {
+            if err.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(StreamEnd::Closed);
+            }
+            return Err(err).context("failed to write snapshot");
+        }
+    }
+}
+
+/// Waits up to three intervals for the first snapshot and prints it, as JSON
+/// or as a compact summary line.
+pub fn print_once(
+    sources: &Sources,
+    interval_ms: u64,
+    cpu_aggregate: CpuAggregate,
+    power_summary: &PowerSummary,
+    temp_range: TempRange,
+    json: bool,
+) -> Result<()> {
+    let snapshot = first_snapshot(sources, interval_ms)?;
+    let line = if json {
+        snapshot_json(&snapshot)?
+    } else {
+        summary_line(&snapshot, cpu_aggregate, power_summary, temp_range)
+    };
+    println!("{}", line);
+    Ok(())
+}
+
+/// How `--line` formats its reading.
+pub struct LineStyle<'a> {
+    /// Template with `{cpu}`, `{ram}`, `{gpu}`, `{temp}`, `{power}` and
+    /// `{extra.<key>}` for `--extra-source` metrics.
+    pub format: &'a str,
+    pub cpu_aggregate: CpuAggregate,
+    pub power_summary: &'a PowerSummary,
+    pub temp_unit: TempUnit,
+    pub power_unit: PowerUnit,
+    pub temp_range: TempRange,
+    pub temp_heat: HeatBounds,
+    pub percent_heat: HeatBounds,
+    /// Color values green/yellow/red with ANSI escapes.
+    pub ansi: bool,
+}
+
+/// Waits for the first snapshot like [`print_once`] and prints it through
+/// the `--line-format` template, for status bars.
+pub fn print_line(sources: &Sources, interval_ms: u64, style: &LineStyle) -> Result<()> {
+    let snapshot = first_snapshot(sources, interval_ms)?;
+    println!("{}", status_line(&snapshot, style));
+    Ok(())
+}
+
+/// Waits up to three intervals for tegrastats and one more for nvidia-smi.
+/// The extra source is not waited for; it gets whatever it printed by then.
+fn first_snapshot(sources: &Sources, interval_ms: u64) -> Result<StatsSnapshot> {
+    let timeout = Duration::from_millis(interval_ms.saturating_mul(3));
+    let mut snapshot = sources
+        .tegrastats
+        .recv_timeout(timeout)
+        .with_context(|| format!("no tegrastats reading within {}ms", timeout.as_millis()))?;
+    // nvidia-smi polls on its own thread; give it one interval to report.
+    let gpus = sources
+        .gpu
+        .and_then(|gpu_runner| gpu_runner.recv_timeout(Duration::from_millis(interval_ms)))
+        .and_then(|update| match update {
+            GpuUpdate::Stats(stats) => Some(stats),
+            GpuUpdate::Unresponsive | GpuUpdate::Failed(_) => None,
+        })
+        .unwrap_or_default();
+    snapshot.set_gpus(&gpus);
+    sources.drain_extra(&mut snapshot.extra);
+    Ok(snapshot)
+}
+
+/// Fills the template; readings the snapshot lacks read `N/A`, and
+/// unknown placeholders are left as typed.
+fn status_line(snapshot: &StatsSnapshot, style: &LineStyle) -> String {
+    let paint = |text: String, value: f64, bounds: HeatBounds| {
+        if !style.ansi {
+            return text;
+        }
+        let code = if value >= bounds.high {
+            31
+        } else if value >= bounds.mid {
+            33
+        } else {
+            32
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    };
+    let percent = |value: Option<f32>| match value {
+        Some(value) => paint(format!("{:.0}%", value), f64::from(value), style.percent_heat),
+        None => "N/A".to_string(),
+    };
+    let hottest = snapshot
+        .temps
+        .iter()
+        .map(|temp| temp.value_c)
+        .filter(|value| style.temp_range.contains(*value))
+        .max_by(f32::total_cmp);
+    let temp = match hottest {
+        Some(value_c) => {
+            paint(style.temp_unit.format(value_c), f64::from(value_c), style.temp_heat)
+        }
+        None => "N/A".to_string(),
+    };
+    let power = match snapshot.total_power_mw(style.power_summary) {
+        Some(total) => style.power_unit.format(total),
+        None => "N/A".to_string(),
+    };
+
+    let line = style
+        .format
+        .replace("{cpu}", &percent(snapshot.cpu_total(style.cpu_aggregate)))
+        .replace("{ram}", &percent(snapshot.ram_percent()))
+        .replace("{gpu}", &percent(snapshot.gpu_util))
+        .replace("{temp}", &temp)
+        .replace("{power}", &power);
+    fill_extra(&line, &snapshot.extra)
+}
+
+/// Replaces each `{extra.<key>}` with that extra metric, or `N/A` when the
+/// source has not printed it.
+fn fill_extra(template: &str, extra: &HashMap<String, f64>) -> String {
+    const PREFIX: &str = "{extra.";
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(PREFIX) {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let key = &rest[start + PREFIX.len()..start + len];
+        filled.push_str(&rest[..start]);
+        match extra.get(key) {
+            Some(value) => filled.push_str(&format!("{:.1}", value)),
+            None => filled.push_str("N/A"),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// One-line human summary, e.g. `CPU 42% RAM 17842/125772MB GPU 55% ...`.
+fn summary_line(
+    snapshot: &StatsSnapshot,
+    cpu_aggregate: CpuAggregate,
+    power_summary: &PowerSummary,
+    temp_range: TempRange,
+) -> String {
+    let percent = |value: Option<f32>| {
+        value
+            .map(|value| format!("{:.0}%", value))
+            .unwrap_or_else(|| "N/A".to_string())
+    };
+    let mut parts = vec![format!("CPU {}", percent(snapshot.cpu_total(cpu_aggregate)))];
+    if let (Some(used), Some(total)) = (snapshot.ram_used_mb, snapshot.ram_total_mb) {
+        parts.push(format!("RAM {}/{}MB", used, total));
+    }
+    parts.push(format!("GPU {}", percent(snapshot.gpu_util)));
+    if snapshot.emc_util.is_some() {
+        parts.push(format!("EMC {}", percent(snapshot.emc_util)));
+    }
+    if let Some(hottest) = snapshot
+        .temps
+        .iter()
+        .filter(|temp| temp_range.contains(temp.value_c))
+        .max_by(|a, b| a.value_c.total_cmp(&b.value_c))
+    {
+        parts.push(format!("{} {:.1}C", hottest.name, hottest.value_c));
+    }
+    if let Some(total) = snapshot.total_power_mw(power_summary) {
+        parts.push(format!("power {}mW", total));
+    }
+    let mut extra: Vec<_> = snapshot.extra.iter().collect();
+    extra.sort_by(|a, b| a.0.cmp(b.0));
+    parts.extend(extra.into_iter().map(|(key, value)| format!("{} {:.1}", key, value)));
+    parts.join(" ")
+}
+
+/// The one stderr line `--silent` prints for a failed run: the outermost
+/// message without the chain of causes under it.
+pub fn error_line(err: &anyhow::Error) -> String {
+    format!("jmon: {}", err)
+}
+
+pub fn snapshot_json(snapshot: &StatsSnapshot) -> Result<String> {
+    serde_json::to_string(snapshot).context("failed to serialize snapshot")
+}
+
+/// Turns Ctrl-C into a flag so the caller can stop tegrastats before exiting.
+#[cfg(unix)]
+fn install_interrupt_handler() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+    }
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as *const () as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn install_interrupt_handler() {}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::{error_line, snapshot_json, status_line, summary_line, LineStyle};
+    use crate::model::{
+        CpuAggregate, HeatBounds, PowerRail, PowerSummary, PowerUnit, StatsSnapshot, TempRange,
+        TempReading, TempUnit,
+    };
+
+    fn sample() -> StatsSnapshot {
+        StatsSnapshot {
+            cpu_cores: vec![12.0, 0.0],
+            ram_used_mb: Some(1024),
+            ram_total_mb: Some(4096),
+            gpu_util: Some(55.0),
+            temps: vec![
+                TempReading {
+                    name: "cpu".to_string(),
+                    value_c: 41.5,
+                },
+                TempReading {
+                    name: "tj".to_string(),
+                    value_c: 43.0,
+                },
+            ],
+            power_rails: vec![PowerRail {
+                name: "VIN".to_string(),
+                current_mw: 5000,
+                average_mw: 4800,
+            }],
+            ..StatsSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_serializes_to_one_line() {
+        let snapshot = sample();
+        let line = snapshot_json(&snapshot).unwrap();
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["cpu_cores"], serde_json::json!([12.0, 0.0]));
+        assert_eq!(value["ram_used_mb"], 1024);
+        assert_eq!(value["swap_used_mb"], serde_json::Value::Null);
+        assert_eq!(value["temps"][0]["name"], "cpu");
+        assert_eq!(value["power_rails"][0]["current_mw"], 5000);
+    }
+
+    #[test]
+    fn silent_errors_are_one_line_without_causes() {
+        let err = std::fs::File::open("/nonexistent/tegrastats")
+            .context("failed to start tegrastats as `/nonexistent/tegrastats`")
+            .context("failed to start tegrastats (ensure it is installed)")
+            .unwrap_err();
+        assert_eq!(error_line(&err), "jmon: failed to start tegrastats (ensure it is installed)");
+    }
+
+    #[test]
+    fn status_line_fills_the_template() {
+        let power_summary = PowerSummary::default();
+        let mut style = LineStyle {
+            format: "CPU {cpu} RAM {ram} GPU {gpu} {temp} {power} {swap}",
+            cpu_aggregate: CpuAggregate::Mean,
+            power_summary: &power_summary,
+            temp_unit: TempUnit::Celsius,
+            power_unit: PowerUnit::Watts,
+            temp_range: TempRange::default(),
+            temp_heat: HeatBounds::TEMP,
+            percent_heat: HeatBounds::PERCENT,
+            ansi: false,
+        };
+        let mut snapshot = sample();
+        snapshot.gpu_util = None;
+
+        assert_eq!(
+            status_line(&snapshot, &style),
+            "CPU 6% RAM 25% GPU N/A 43.0C 5.0W {swap}"
+        );
+        style.format = "fan {extra.fan} {extra.rpm} {extra.fan";
+        snapshot.extra.insert("fan".to_string(), 42.0);
+        assert_eq!(status_line(&snapshot, &style), "fan 42.0 N/A {extra.fan");
+        style.format = "{cpu}";
+        style.ansi = true;
+        assert_eq!(status_line(&snapshot, &style), "\x1b[32m6%\x1b[0m");
+    }
+
+    #[test]
+    fn summary_line_is_compact() {
+        assert_eq!(
+            summary_line(
+                &sample(),
+                CpuAggregate::Mean,
+                &PowerSummary::default(),
+                TempRange::default(),
+            ),
+            "CPU 6% RAM 1024/4096MB GPU 55% tj 43.0C power 5000mW"
+        );
+
+        let mut snapshot = sample();
+        snapshot.extra.insert("fan".to_string(), 42.0);
+        snapshot.extra.insert("board".to_string(), 38.5);
+        let line = summary_line(
+            &snapshot,
+            CpuAggregate::Mean,
+            &PowerSummary::default(),
+            TempRange::default(),
+        );
+        assert!(line.ends_with("power 5000mW board 38.5 fan 42.0"), "{}", line);
+    }
+
+    #[test]
+    fn summary_line_skips_implausible_temps() {
+        let mut snapshot = sample();
+        snapshot.temps.push(TempReading {
+            name: "soc".to_string(),
+            value_c: 511.0,
+        });
+        let line = summary_line(
+            &snapshot,
+            CpuAggregate::Mean,
+            &PowerSummary::default(),
+            TempRange::default(),
+        );
+        assert!(line.contains("tj 43.0C"), "{}", line);
+        assert!(!line.contains("511"), "{}", line);
+    }
+}
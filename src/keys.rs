@@ -0,0 +1,196 @@
+use crossterm::event::KeyCode;
+
+use crate::model::Pane;
+
+/// What a dashboard key does; the event loop matches on this, so the help
+/// overlay and the handler share one table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    TogglePane(Pane),
+    ResetHistory,
+    ToggleRamDetail,
+    EditSources,
+    DismissNotice,
+    CaptureScreen,
+    ExportHistory,
+    ToggleHeatmap,
+    ToggleTable,
+    ScrollUp,
+    ScrollDown,
+    ToggleRamFree,
+    ToggleLfb,
+    ToggleTempUnit,
+    TogglePowerUnit,
+    CyclePowerSummary,
+    TogglePowerScale,
+    CycleSmoothing,
+    TogglePercentiles,
+    CycleTempSort,
+    EditTempFilter,
+    EditInterval,
+    ToggleZoom,
+    SelectNext,
+    SelectPrev,
+    TogglePause,
+    ScrollBack,
+    ScrollForward,
+    ShrinkHistory,
+    GrowHistory,
+    IntervalUp,
+    IntervalDown,
+}
+
+/// One help row and the keys it covers.
+pub struct KeyBinding {
+    pub label: &'static str,
+    pub description: &'static str,
+    pub keys: &'static [(KeyCode, Action)],
+}
+
+const fn binding(
+    label: &'static str,
+    description: &'static str,
+    keys: &'static [(KeyCode, Action)],
+) -> KeyBinding {
+    KeyBinding {
+        label,
+        description,
+        keys,
+    }
+}
+
+/// Every dashboard key, in help order. Modal input (the sources editor and
+/// the interval prompt) and Ctrl+C are handled before this table.
+pub const KEY_BINDINGS: &[KeyBinding] = &[
+    binding(
+        "q / Esc",
+        "quit",
+        &[(KeyCode::Char('q'), Action::Quit), (KeyCode::Esc, Action::Quit)],
+    ),
+    binding("h", "toggle help", &[(KeyCode::Char('h'), Action::ToggleHelp)]),
+    binding(
+        "1-6",
+        "toggle CPU/RAM/GPU/Temps/Power/Swap panes",
+        &[
+            (KeyCode::Char('1'), Action::TogglePane(Pane::Cpu)),
+            (KeyCode::Char('2'), Action::TogglePane(Pane::Ram)),
+            (KeyCode::Char('3'), Action::TogglePane(Pane::Gpu)),
+            (KeyCode::Char('4'), Action::TogglePane(Pane::Temps)),
+            (KeyCode::Char('5'), Action::TogglePane(Pane::Power)),
+            (KeyCode::Char('6'), Action::TogglePane(Pane::Swap)),
+        ],
+    ),
+    binding("r", "reset history", &[(KeyCode::Char('r'), Action::ResetHistory)]),
+    binding("m", "toggle RAM detail view", &[(KeyCode::Char('m'), Action::ToggleRamDetail)]),
+    binding(
+        "p",
+        "edit tegrastats/nvidia-smi paths",
+        &[(KeyCode::Char('p'), Action::EditSources)],
+    ),
+    binding("x", "dismiss header notice", &[(KeyCode::Char('x'), Action::DismissNotice)]),
+    binding("c", "save screen as ANSI text", &[(KeyCode::Char('c'), Action::CaptureScreen)]),
+    binding("s", "export history as CSV", &[(KeyCode::Char('s'), Action::ExportHistory)]),
+    binding("k", "toggle per-core CPU heatmap", &[(KeyCode::Char('k'), Action::ToggleHeatmap)]),
+    binding("v", "toggle table view", &[(KeyCode::Char('v'), Action::ToggleTable)]),
+    binding(
+        "Up/Down",
+        "scroll this help, the table view, or the selected Temps/Power list",
+        &[(KeyCode::Up, Action::ScrollUp), (KeyCode::Down, Action::ScrollDown)],
+    ),
+    binding("f", "toggle RAM used/free", &[(KeyCode::Char('f'), Action::ToggleRamFree)]),
+    binding("l", "toggle largest-free-block sparkline", &[(KeyCode::Char('l'), Action::ToggleLfb)]),
+    binding("t", "toggle Celsius/Fahrenheit", &[(KeyCode::Char('t'), Action::ToggleTempUnit)]),
+    binding("w", "toggle power in mW/W", &[(KeyCode::Char('w'), Action::TogglePowerUnit)]),
+    binding(
+        "W",
+        "cycle power total: sum / VIN / rail",
+        &[(KeyCode::Char('W'), Action::CyclePowerSummary)],
+    ),
+    binding(
+        "g",
+        "toggle power graph between linear and log scale",
+        &[(KeyCode::Char('g'), Action::TogglePowerScale)],
+    ),
+    binding(
+        "a",
+        "cycle graph smoothing: off / 3 / 5 sample average",
+        &[(KeyCode::Char('a'), Action::CycleSmoothing)],
+    ),
+    binding(
+        "u",
+        "CPU/GPU footers: min/avg/max or p50/p95/p99",
+        &[(KeyCode::Char('u'), Action::TogglePercentiles)],
+    ),
+    binding(
+        "o",
+        "sort temps: reported / by name / hottest first",
+        &[(KeyCode::Char('o'), Action::CycleTempSort)],
+    ),
+    binding(
+        "/",
+        "filter temps: text, !text or >value (Enter sets)",
+        &[(KeyCode::Char('/'), Action::EditTempFilter)],
+    ),
+    binding(
+        "i",
+        "type an interval in ms (Enter sets, Esc cancels)",
+        &[(KeyCode::Char('i'), Action::EditInterval)],
+    ),
+    binding(
+        "Tab / S-Tab",
+        "select the next / previous panel (a click also selects)",
+        &[(KeyCode::Tab, Action::SelectNext), (KeyCode::BackTab, Action::SelectPrev)],
+    ),
+    binding(
+        "z",
+        "zoom the selected panel to the full body",
+        &[(KeyCode::Char('z'), Action::ToggleZoom)],
+    ),
+    binding("space", "pause/resume the display", &[(KeyCode::Char(' '), Action::TogglePause)]),
+    binding(
+        "<-/->",
+        "scroll history while paused",
+        &[(KeyCode::Left, Action::ScrollBack), (KeyCode::Right, Action::ScrollForward)],
+    ),
+    binding(
+        "[ / ]",
+        "halve / double history length",
+        &[(KeyCode::Char('['), Action::ShrinkHistory), (KeyCode::Char(']'), Action::GrowHistory)],
+    ),
+    binding(
+        "+/-",
+        "change tegrastats interval",
+        &[(KeyCode::Char('+'), Action::IntervalUp), (KeyCode::Char('-'), Action::IntervalDown)],
+    ),
+];
+
+/// Mouse hints listed after the keys in the help overlay.
+pub const MOUSE_HINTS: &[(&str, &str)] = &[
+    ("click", "[-] / [+] in the header change the interval"),
+    ("hover", "highlights the [-] / [+] button under the pointer"),
+];
+
+pub fn action_for(code: KeyCode) -> Option<Action> {
+    KEY_BINDINGS
+        .iter()
+        .flat_map(|binding| binding.keys)
+        .find(|(key, _)| *key == code)
+        .map(|(_, action)| *action)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::KEY_BINDINGS;
+
+    #[test]
+    fn every_key_is_bound_once() {
+        let mut seen = HashSet::new();
+        for (key, _) in KEY_BINDINGS.iter().flat_map(|binding| binding.keys) {
+            assert!(seen.insert(*key), "{:?} is bound twice", key);
+        }
+    }
+}
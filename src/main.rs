@@ -1,63 +1,585 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
 use std::io::{self, Stdout};
-use std::time::{Duration, Instant};
+use std::iter;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use crossterm::event::{
     self, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
 };
-use crossterm::execute;
+use crossterm::{cursor, execute};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
+mod alert;
+mod capture;
+mod config;
+mod db;
+mod extra;
 mod model;
+mod state;
 mod gpu;
+mod headless;
+mod keys;
+mod metrics;
+mod socket;
 mod tegrastats;
 mod ui;
 
-use crate::gpu::GpuUtilRunner;
-use crate::model::AppState;
-use crate::tegrastats::TegrastatsRunner;
+use crate::alert::AlertHook;
+use crate::config::Config;
+use crate::db::SnapshotDb;
+use crate::extra::ExtraSourceRunner;
+use crate::gpu::{GpuUpdate, GpuUtilRunner};
+use crate::headless::{StreamEnd, StreamSinks};
+use crate::keys::Action;
+use crate::metrics::MetricsServer;
+use crate::model::{
+    parse_heat_bounds, parse_rail_color, parse_rgb, parse_sensor_alias, parse_temp_range,
+    parse_throttle_threshold, AppState, ColorDepth, ColorScheme, CpuAggregate, GpuStats,
+    HeatBounds, IntervalStyle, MAX_HISTORY_CAPACITY, MIN_HISTORY_CAPACITY, Pane, PowerSummary,
+    PowerUnit, Rgb, SourcesEditor, SparkMetric, StabilityDetector, StatsSnapshot, TempRange,
+    TempUnit, Theme,
+};
+use crate::socket::SnapshotSocket;
+use crate::state::{seed_defaults, SavedState};
+use crate::tegrastats::{SessionRecorder, SourceStatus, TegrastatsRunner};
 
 #[derive(Parser, Debug)]
 #[command(name = "jmon", about = "Jetson monitor TUI using tegrastats")]
 struct Args {
+    /// Config file of default options (default: `~/.config/jmon/config.toml` if it exists).
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Start from the defaults instead of the panes, interval, units and palette of the last run.
+    #[arg(long)]
+    no_restore: bool,
     #[arg(short, long, default_value = "tegrastats")]
     tegrastats: String,
     #[arg(long, default_value = "nvidia-smi")]
     nvidia_smi: String,
+    /// Extra argument passed to tegrastats before `--interval` (repeatable).
+    #[arg(long = "tegrastats-arg", value_name = "ARG", allow_hyphen_values = true)]
+    tegrastats_args: Vec<String>,
+    /// How the interval is passed: `--interval <ms>`, a bare `<ms>`, or not at all.
+    #[arg(long, value_enum, default_value_t = IntervalStyle::Flag)]
+    tegrastats_interval_style: IntervalStyle,
+    /// Extra argument passed to nvidia-smi before the query options (repeatable).
+    #[arg(long = "nvidia-smi-arg", value_name = "ARG", allow_hyphen_values = true)]
+    nvidia_smi_args: Vec<String>,
     #[arg(short, long, default_value_t = 1000)]
     interval: u64,
+    /// UI ticks per second (1-30): blink speed and how often the clock is checked.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..=30))]
+    fps: u64,
+    /// Exit after this many seconds (exit code 2 if a crit threshold was breached).
+    #[arg(long)]
+    duration: Option<u64>,
+    /// Hide errors from the footer after this many seconds (default: keep them).
+    #[arg(long)]
+    error_timeout: Option<u64>,
+    /// Sparkline/bar base color that low values blend from (`#RRGGBB` or `r,g,b`);
+    /// overrides the one `--theme` picks.
+    #[arg(long, value_parser = parse_rgb)]
+    spark_base: Option<Rgb>,
+    /// Terminal background: `light` blends from near-black instead of white;
+    /// `auto` checks COLORFGBG.
+    #[arg(long, value_enum, default_value_t = Theme::Auto)]
+    theme: Theme,
+    /// Do not show the notice about running jmon as root.
+    #[arg(long)]
+    allow_root: bool,
+    /// How per-core utilization is combined into the CPU total.
+    #[arg(long, value_enum, default_value_t = CpuAggregate::Mean)]
+    cpu_aggregate: CpuAggregate,
+    /// Terminal color support; `auto` checks COLORTERM and TERM.
+    #[arg(long, value_enum, default_value_t = ColorDepth::Auto)]
+    color_depth: ColorDepth,
+    /// Colors for metrics and the heat scale; `deuteranopia` avoids red-green pairs.
+    #[arg(long, value_enum, default_value_t = ColorScheme::Default)]
+    palette: ColorScheme,
+    /// Unit temperatures start in; `t` toggles it.
+    #[arg(long, value_enum, default_value_t = TempUnit::Celsius)]
+    temp_unit: TempUnit,
+    /// Unit power starts in; `w` toggles it.
+    #[arg(long, value_enum, default_value_t = PowerUnit::Milliwatts)]
+    power_unit: PowerUnit,
+    /// Panes to start hidden, e.g. `swap,power`; `1`-`6` toggle them.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    hide: Vec<Pane>,
+    /// Friendly sensor/rail labels, e.g. `soc012=SOC0-2,tj=Junction`.
+    #[arg(long, value_delimiter = ',', value_parser = parse_sensor_alias)]
+    sensor_alias: Vec<(String, String)>,
+    /// Power rail label colors, e.g. `VDD_GPU=#40c0ff`; others get a color from their name.
+    #[arg(long, value_delimiter = ',', value_parser = parse_rail_color)]
+    rail_color: Vec<(String, Rgb)>,
+    /// Per-sensor Celsius limits that flag throttling, e.g. `tj=85,cpu=90`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_throttle_threshold,
+        default_value = "tj=85"
+    )]
+    throttle_threshold: Vec<(String, f32)>,
+    /// Align all sparklines to a shared time axis, leaving gaps for missing samples.
+    #[arg(long)]
+    align_history: bool,
+    /// Check that tegrastats (and nvidia-smi) produce data, print a report, and exit.
+    #[arg(long)]
+    probe_only: bool,
+    /// How fast the power graph's auto-scale follows the observed max (0-1, 1 snaps).
+    #[arg(long, default_value_t = 0.2)]
+    scale_smoothing: f64,
+    /// Pack this many compact core bars onto each CPU panel row.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=16))]
+    cpu_bars_per_row: u16,
+    /// Kill an nvidia-smi query that takes longer than this many milliseconds.
+    #[arg(long, default_value_t = 2000)]
+    nvidia_smi_timeout: u64,
+    /// Start in the numeric table view instead of the graphical dashboard.
+    #[arg(long)]
+    table: bool,
+    /// Color these sparklines green/yellow/red by warn/crit range instead of intensity.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    threshold_spark: Vec<SparkMetric>,
+    /// Shell command that prints `key=value` lines, shown as bars in an Extra pane.
+    #[arg(long)]
+    extra_source: Option<String>,
+    /// Plausible temperature range in Celsius (`min:max`); readings outside it show as `?`.
+    #[arg(long, value_parser = parse_temp_range, default_value = "-20:150")]
+    temp_range: TempRange,
+    /// Celsius where temperature colors start warming, turn warm and peak (`low:mid:high`).
+    #[arg(long, value_parser = parse_heat_bounds, default_value = "30:60:85")]
+    temp_heat: HeatBounds,
+    /// The same heat scale for utilization percents (CPU, GPU, EMC).
+    #[arg(long, value_parser = parse_heat_bounds, default_value = "0:50:100")]
+    percent_heat: HeatBounds,
+    /// Samples the STABLE badge looks back over.
+    #[arg(long, default_value_t = 10)]
+    stable_window: usize,
+    /// Max variance (percent points squared) of CPU, GPU and power for the STABLE badge.
+    #[arg(long, default_value_t = 4.0)]
+    stable_threshold: f64,
+    /// Flag samples more than this many standard deviations from their rolling mean (0: off).
+    #[arg(long, default_value_t = 4.0)]
+    anomaly_k: f64,
+    /// Show each core's clock next to its utilization, e.g. `92% @1566MHz`.
+    #[arg(long)]
+    cpu_freq: bool,
+    /// Skip the TUI and print each snapshot as one line of JSON on stdout.
+    #[arg(long)]
+    json: bool,
+    /// Print the first reading (as JSON with `--json`) and exit, without the TUI.
+    #[arg(long)]
+    once: bool,
+    /// Print the first reading as one `--line-format` line for status bars and exit.
+    #[arg(long, conflicts_with = "json")]
+    line: bool,
+    /// In `--json`, `--once` and `--line`, print only the data, and on failure
+    /// a single `jmon: <error>` line on stderr.
+    #[arg(long)]
+    silent: bool,
+    /// Template for `--line`, with `{cpu}`, `{ram}`, `{gpu}`, `{temp}` and `{power}`.
+    #[arg(long, default_value = "CPU {cpu} RAM {ram} GPU {gpu} {temp} {power}")]
+    line_format: String,
+    /// Color `--line` values by the heat scales with ANSI escapes.
+    #[arg(long)]
+    line_color: bool,
+    /// Replay a captured tegrastats log, one line per interval and looping, instead of tegrastats.
+    #[arg(long)]
+    replay: Option<String>,
+    /// Save every raw tegrastats line, timestamped, to this file for `--replay`.
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Samples kept per graph; `[` and `]` halve or double it at runtime.
+    #[arg(long, default_value_t = 120, value_parser = parse_history_capacity)]
+    history: usize,
+    /// Fixed ceiling in mW for the power bar and sparkline, instead of auto-scaling.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    power_max_mw: Option<u64>,
+    /// What the power total tracks: `sum` of rails (minus VIN), `vin`, or `rail:<NAME>`.
+    #[arg(long, default_value = "sum")]
+    power_summary: PowerSummary,
+    /// Battery capacity in Wh; the Power panel then estimates time-to-empty from the VIN average.
+    #[arg(long)]
+    battery_wh: Option<f64>,
+    /// Draw sparklines, heatmaps and borders with ASCII only, for consoles without Unicode.
+    #[arg(long)]
+    ascii: bool,
+    /// Draw graph sparklines with Braille dots, two samples per column (blocks with `--ascii`).
+    #[arg(long)]
+    braille: bool,
+    /// Serve the latest reading in Prometheus format at `http://<host:port>/metrics`.
+    #[arg(long)]
+    metrics_addr: Option<String>,
+    /// Stream each reading as a line of JSON to clients of a Unix socket created at this path.
+    #[arg(long)]
+    socket: Option<PathBuf>,
+    /// Append every reading to an SQLite database at this path (dashboard only).
+    #[arg(long)]
+    db: Option<PathBuf>,
+    /// Shell command run when a reading rises to an `--alert-temp` or
+    /// `--alert-power-mw` threshold, with `JMON_METRIC`, `JMON_VALUE` and
+    /// `JMON_THRESHOLD` set (dashboard and `--json`).
+    #[arg(long, value_name = "CMD")]
+    on_alert: Option<String>,
+    /// Per-sensor Celsius thresholds for `--on-alert`, e.g. `tj=90,cpu=85`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_throttle_threshold,
+        requires = "on_alert"
+    )]
+    alert_temp: Vec<(String, f32)>,
+    /// Power total in mW (as `--power-summary` tracks it) that triggers `--on-alert`.
+    #[arg(long, requires = "on_alert")]
+    alert_power_mw: Option<u64>,
+    /// Seconds before the same metric can trigger `--on-alert` again.
+    #[arg(long, default_value_t = 60)]
+    alert_cooldown: u64,
+    /// Directory `s` writes history CSV exports to.
+    #[arg(long, default_value = ".")]
+    export_dir: PathBuf,
+}
+
+fn parse_history_capacity(value: &str) -> Result<usize, String> {
+    let capacity: usize = value.parse().map_err(|err| format!("{err}"))?;
+    if (MIN_HISTORY_CAPACITY..=MAX_HISTORY_CAPACITY).contains(&capacity) {
+        Ok(capacity)
+    } else {
+        Err(format!("must be between {MIN_HISTORY_CAPACITY} and {MAX_HISTORY_CAPACITY}"))
+    }
+}
+
+/// Bounds for `+`/`-` and a typed interval.
+const MIN_INTERVAL_MS: u64 = 250;
+const MAX_INTERVAL_MS: u64 = 5000;
+
+/// Shortest gap between two redraws, so bursts of input or readings are
+/// drawn as one frame. A faster `--fps` shortens it; a slower one does not,
+/// so keys still answer quickly.
+const MIN_FRAME_GAP: Duration = Duration::from_millis(50);
+
+/// Intervals without an nvidia-smi reading before the last one is dropped.
+const GPU_STALE_INTERVALS: u32 = 3;
+
+/// Exit code when `--duration` elapses after a crit threshold was breached.
+const EXIT_CRIT_BREACHED: i32 = 2;
+/// Exit code when the user interrupts with Ctrl-C.
+const EXIT_INTERRUPTED: i32 = 130;
+
+enum ExitReason {
+    Quit,
+    Interrupted,
+    DurationElapsed { crit_breached: bool },
+}
+
+impl ExitReason {
+    fn code(&self) -> i32 {
+        match self {
+            ExitReason::Quit => 0,
+            ExitReason::Interrupted => EXIT_INTERRUPTED,
+            ExitReason::DurationElapsed { crit_breached: true } => EXIT_CRIT_BREACHED,
+            ExitReason::DurationElapsed { crit_breached: false } => 0,
+        }
+    }
+}
+
+/// The nvidia-smi runner in use, and one started by a restart that only
+/// replaces it once it reports, so a bad path keeps the old readings going.
+struct GpuRunners {
+    live: Option<GpuUtilRunner>,
+    pending: Option<PendingGpu>,
+}
+
+struct PendingGpu {
+    runner: GpuUtilRunner,
+    path: String,
+}
+
+impl GpuRunners {
+    fn shutdown(&mut self) {
+        if let Some(live) = self.live.as_mut() {
+            live.shutdown();
+        }
+        if let Some(pending) = self.pending.as_mut() {
+            pending.runner.shutdown();
+        }
+    }
+}
+
+/// Parses the command line, filling options it leaves out from the config
+/// file (`--config`, or the default path when that file exists), then from
+/// the state saved by the last run. Saved state only applies to the
+/// dashboard, so `--line` and friends do not follow what was toggled in it.
+fn parse_args() -> Result<Args> {
+    let cli: Vec<OsString> = env::args_os().collect();
+    let matches = Args::command().get_matches_from(&cli);
+    let config_path = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Some(path.clone()),
+        None => Config::default_path().filter(|path| path.exists()),
+    };
+    let headless = ["json", "once", "line", "probe_only"]
+        .into_iter()
+        .any(|id| matches.get_flag(id));
+    let restored = SavedState::default_path()
+        .filter(|_| !matches.get_flag("no_restore") && !headless)
+        .and_then(|path| SavedState::load(&path));
+    if config_path.is_none() && restored.is_none() {
+        return Ok(Args::from_arg_matches(&matches)?);
+    }
+
+    let config = match config_path.as_deref() {
+        Some(path) => Config::load(path)?.args(),
+        None => Vec::new(),
+    };
+    let seeded = seed_defaults(config, restored.as_ref());
+
+    let mut argv: Vec<OsString> = cli.iter().take(1).cloned().collect();
+    for (id, value) in seeded {
+        if !value.is_empty() && matches.value_source(id) != Some(ValueSource::CommandLine) {
+            argv.push(format!("--{}={}", id.replace('_', "-"), value).into());
+        }
+    }
+    argv.extend(cli.iter().skip(1).cloned());
+    Args::try_parse_from(argv).context(match config_path {
+        Some(path) => format!("invalid option in config `{}`", path.display()),
+        None => "invalid option in the restored UI state (try --no-restore)".to_string(),
+    })
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    let mut runner = TegrastatsRunner::spawn(&args.tegrastats, args.interval).with_context(
-        || "failed to start tegrastats (ensure it is installed and accessible without sudo)",
-    )?;
-    let mut gpu_runner = GpuUtilRunner::spawn(&args.nvidia_smi, args.interval).ok();
-    let mut terminal = setup_terminal()?;
+    let args = parse_args()?;
+    if args.probe_only {
+        let ok = probe_sources(&args);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    if args.silent && (args.json || args.once || args.line) {
+        // Same exit code as an error returned from `main`, without the
+        // `Caused by:` chain.
+        if let Err(err) = run(&args) {
+            eprintln!("{}", headless::error_line(&err));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    run(&args)
+}
+
+fn run(args: &Args) -> Result<()> {
+    let recorder = args.record.as_deref().map(SessionRecorder::create).transpose()?;
+    let mut runner = match args.replay.as_deref() {
+        Some(log) => TegrastatsRunner::replay(log, args.interval)?,
+        None => TegrastatsRunner::spawn(
+            &args.tegrastats,
+            &args.tegrastats_args,
+            args.interval,
+            args.tegrastats_interval_style,
+            recorder,
+        )
+        .with_context(|| {
+            "failed to start tegrastats (ensure it is installed and accessible without sudo)"
+        })?,
+    };
+    let mut gpu_runners = GpuRunners {
+        live: Some(GpuUtilRunner::spawn(
+            &args.nvidia_smi,
+            &args.nvidia_smi_args,
+            args.interval,
+            Duration::from_millis(args.nvidia_smi_timeout),
+        )),
+        pending: None,
+    };
+    let mut extra_runner = args
+        .extra_source
+        .as_deref()
+        .map(ExtraSourceRunner::spawn)
+        .transpose()?;
+    // `--once` and `--line` never publish, so they leave the port and socket alone.
+    let metrics = match args.metrics_addr.as_deref() {
+        Some(addr) if !args.once && !args.line => {
+            Some(MetricsServer::spawn(addr, args.temp_range)?)
+        }
+        _ => None,
+    };
+    let socket = match args.socket.as_deref() {
+        Some(path) if !args.once && !args.line => Some(SnapshotSocket::spawn(path)?),
+        _ => None,
+    };
+    let sources = headless::Sources {
+        tegrastats: &runner,
+        gpu: gpu_runners.live.as_ref(),
+        extra: extra_runner.as_ref(),
+    };
+    let result = if args.line {
+        let style = headless::LineStyle {
+            format: &args.line_format,
+            cpu_aggregate: args.cpu_aggregate,
+            power_summary: &args.power_summary,
+            temp_unit: args.temp_unit,
+            power_unit: args.power_unit,
+            temp_range: args.temp_range,
+            temp_heat: args.temp_heat,
+            percent_heat: args.percent_heat,
+            ansi: args.line_color,
+        };
+        headless::print_line(&sources, args.interval, &style).map(|()| ExitReason::Quit)
+    } else if args.once {
+        headless::print_once(
+            &sources,
+            args.interval,
+            args.cpu_aggregate,
+            &args.power_summary,
+            args.temp_range,
+            args.json,
+        )
+        .map(|()| ExitReason::Quit)
+    } else if args.json {
+        headless::stream_json(
+            &sources,
+            args.duration.map(Duration::from_secs),
+            args.temp_range,
+            StreamSinks {
+                metrics: metrics.as_ref(),
+                socket: socket.as_ref(),
+                alert_hook: alert_hook(args).as_mut(),
+                silent: args.silent,
+            },
+        )
+        .map(|end| match end {
+            StreamEnd::Interrupted => ExitReason::Interrupted,
+            StreamEnd::Elapsed { crit_breached } => ExitReason::DurationElapsed { crit_breached },
+            StreamEnd::Closed => ExitReason::Quit,
+        })
+    } else {
+        let mut terminal = setup_terminal()?;
+        let result = run_app(
+            &mut terminal,
+            &mut runner,
+            &mut gpu_runners,
+            extra_runner.as_ref(),
+            metrics.as_ref(),
+            socket.as_ref(),
+            args,
+        );
+        restore_terminal(&mut terminal)?;
+        if let Ok((_, state)) = &result
+            && let Some(path) = SavedState::default_path()
+            && let Err(err) = state.save(&path)
+        {
+            eprintln!("warning: {:#}", err);
+        }
+        result.map(|(reason, _)| reason)
+    };
+
+    runner.shutdown();
+    gpu_runners.shutdown();
+    if let Some(extra_runner) = extra_runner.as_mut() {
+        extra_runner.shutdown();
+    }
+    // Removes the socket file; `process::exit` below would skip the drop.
+    drop(socket);
+
+    let code = result?.code();
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
 
-    let result = run_app(
-        &mut terminal,
-        &mut runner,
-        &mut gpu_runner,
+/// Spawns each source and waits a bounded time for real data. Only tegrastats is
+/// required; nvidia-smi is reported but does not affect the result.
+fn probe_sources(args: &Args) -> bool {
+    let timeout = Duration::from_millis(args.interval.saturating_mul(3).max(2000));
+
+    let tegrastats_ok = match TegrastatsRunner::spawn(
         &args.tegrastats,
+        &args.tegrastats_args,
+        args.interval,
+        args.tegrastats_interval_style,
+        None,
+    ) {
+        Ok(mut runner) => {
+            let snapshot = runner.recv_timeout(timeout);
+            runner.shutdown();
+            match snapshot {
+                Some(snapshot) => {
+                    let cores = if snapshot.cpu_unparseable {
+                        "unparseable".to_string()
+                    } else {
+                        snapshot.cpu_cores.len().to_string()
+                    };
+                    println!(
+                        "tegrastats ({}): OK ({} cores, {} temps, {} rails)",
+                        args.tegrastats,
+                        cores,
+                        snapshot.temps.len(),
+                        snapshot.power_rails.len()
+                    );
+                    true
+                }
+                None => {
+                    println!(
+                        "tegrastats ({}): FAIL (no data within {}ms)",
+                        args.tegrastats,
+                        timeout.as_millis()
+                    );
+                    false
+                }
+            }
+        }
+        Err(err) => {
+            println!("tegrastats ({}): FAIL ({:#})", args.tegrastats, err);
+            false
+        }
+    };
+
+    let nvidia_smi_timeout = Duration::from_millis(args.nvidia_smi_timeout);
+    let mut runner = GpuUtilRunner::spawn(
         &args.nvidia_smi,
+        &args.nvidia_smi_args,
         args.interval,
+        nvidia_smi_timeout,
     );
-
-    restore_terminal(&mut terminal)?;
-    runner.shutdown();
-    if let Some(gpu_runner) = gpu_runner.as_mut() {
-        gpu_runner.shutdown();
+    // The runner first probes up to three field sets, each with its own timeout.
+    let gpu_timeout = timeout + nvidia_smi_timeout * 3;
+    match runner.recv_timeout(gpu_timeout) {
+        Some(GpuUpdate::Stats(gpus)) => {
+            let mut snapshot = StatsSnapshot::default();
+            snapshot.set_gpus(&gpus);
+            println!(
+                "nvidia-smi ({}): OK ({:.0}% util, {} GPU{})",
+                args.nvidia_smi,
+                snapshot.gpu_util.unwrap_or_default(),
+                gpus.len(),
+                if gpus.len() == 1 { "" } else { "s" }
+            )
+        }
+        Some(GpuUpdate::Unresponsive) => {
+            println!("nvidia-smi ({}): FAIL (unresponsive)", args.nvidia_smi)
+        }
+        Some(GpuUpdate::Failed(err)) => {
+            println!("nvidia-smi ({}): FAIL ({})", args.nvidia_smi, err)
+        }
+        None => println!(
+            "nvidia-smi ({}): FAIL (no data within {}ms)",
+            args.nvidia_smi,
+            gpu_timeout.as_millis()
+        ),
     }
+    runner.shutdown();
 
-    result
+    tegrastats_ok
 }
 
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
+    install_panic_hook();
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, event::EnableMouseCapture)?;
@@ -66,6 +588,24 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     Ok(terminal)
 }
 
+/// Restores the terminal before the default panic message is printed, so a
+/// panic in `run_app` or `ui::draw` does not leave the shell in raw mode on the
+/// alternate screen. The `Terminal` is owned by `run_app`, so this works on
+/// stdout directly.
+fn install_panic_hook() {
+    let original = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            event::DisableMouseCapture,
+            cursor::Show
+        );
+        original(info);
+    }));
+}
+
 fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
     terminal::disable_raw_mode()?;
     execute!(
@@ -80,126 +620,372 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     runner: &mut TegrastatsRunner,
-    gpu_runner: &mut Option<GpuUtilRunner>,
-    tegrastats_path: &str,
-    nvidia_smi_path: &str,
-    interval_ms: u64,
-) -> Result<()> {
-    let mut app = AppState::new(interval_ms, 120);
-    let mut last_gpu_util: Option<f32> = None;
-    let tick_rate = Duration::from_millis(200);
+    gpu_runners: &mut GpuRunners,
+    extra_runner: Option<&ExtraSourceRunner>,
+    metrics: Option<&MetricsServer>,
+    socket: Option<&SnapshotSocket>,
+    args: &Args,
+) -> Result<(ExitReason, SavedState)> {
+    let mut app = AppState::new(
+        args.tegrastats.clone(),
+        args.nvidia_smi.clone(),
+        args.interval,
+        args.history,
+    );
+    app.tegrastats_args = args.tegrastats_args.clone();
+    app.nvidia_smi_args = args.nvidia_smi_args.clone();
+    app.interval_style = args.tegrastats_interval_style;
+    app.spark_base = args
+        .spark_base
+        .unwrap_or_else(|| args.theme.resolve().spark_base());
+    app.cpu_aggregate = args.cpu_aggregate;
+    app.color_depth = args.color_depth.resolve();
+    app.color_scheme = args.palette;
+    app.sensor_aliases = args.sensor_alias.iter().cloned().collect();
+    app.rail_colors = args.rail_color.iter().cloned().collect();
+    app.throttle_thresholds = args.throttle_threshold.iter().cloned().collect();
+    app.align_history = args.align_history;
+    app.scale_smoothing = args.scale_smoothing.clamp(0.0, 1.0);
+    app.power_max_fixed = args.power_max_mw;
+    app.history.power_summary = args.power_summary.clone();
+    app.battery_wh = args.battery_wh.filter(|capacity| *capacity > 0.0);
+    app.ascii = args.ascii;
+    app.braille = args.braille;
+    app.cpu_bars_per_row = args.cpu_bars_per_row as usize;
+    app.nvidia_smi_timeout = Duration::from_millis(args.nvidia_smi_timeout);
+    app.table_view = args.table;
+    app.threshold_sparks = args.threshold_spark.clone();
+    app.temp_range = args.temp_range;
+    app.history.temp_range = args.temp_range;
+    app.temp_heat = args.temp_heat;
+    app.temp_unit = args.temp_unit;
+    app.power_unit = args.power_unit;
+    for pane in &args.hide {
+        if app.panes.shows(*pane) {
+            toggle_pane(&mut app, *pane);
+        }
+    }
+    app.percent_heat = args.percent_heat;
+    app.error_timeout = args.error_timeout.map(Duration::from_secs);
+    app.stability = StabilityDetector::new(args.stable_window, args.stable_threshold);
+    app.anomalies.k = args.anomaly_k;
+    app.show_cpu_freq = args.cpu_freq;
+    app.replay_path = args.replay.clone();
+    if !args.allow_root && running_as_root() {
+        app.notice = Some(
+            "running as root is usually unnecessary for tegrastats (x: dismiss)".to_string(),
+        );
+    }
+    let mut db = args
+        .db
+        .as_deref()
+        .map(|path| SnapshotDb::open(path, args.cpu_aggregate, args.power_summary.clone()))
+        .transpose()?;
+    let mut alert_hook = alert_hook(args);
+    let duration = args.duration.map(Duration::from_secs);
+    let mut last_gpus = Vec::new();
+    let mut last_gpus_at = Instant::now();
+    let mut extra_metrics = HashMap::new();
+    let mut startup_failure: Option<String> = None;
+    let tick_rate = Duration::from_millis(1000 / args.fps.max(1));
+    let frame_gap = MIN_FRAME_GAP.min(tick_rate);
     let mut last_tick = Instant::now();
+    // Redraw only when something changed or the clock's second ticked over.
+    let mut dirty = true;
+    let mut drawn_second = None;
+    let mut last_draw: Option<Instant> = None;
+
+    let reason = loop {
+        if duration.is_some_and(|duration| app.started_at.elapsed() >= duration) {
+            break ExitReason::DurationElapsed {
+                crit_breached: app.any_crit_breached,
+            };
+        }
+
+        let mut sinks = SnapshotSinks {
+            metrics,
+            socket,
+            db: db.as_mut(),
+            alert_hook: alert_hook.as_mut(),
+        };
+        let readings = iter::from_fn(|| runner.try_recv());
+        if apply_snapshots(&mut app, readings, &last_gpus, &extra_metrics, &mut sinks) {
+            dirty = true;
+            startup_failure = None;
+        }
 
-    loop {
-        let mut latest = None;
-        while let Some(snapshot) = runner.try_recv() {
-            latest = Some(snapshot);
+        while let Some(status) = runner.try_status() {
+            dirty = true;
+            let error = match status {
+                SourceStatus::StartupFailed(message) => {
+                    startup_failure = Some(message);
+                    continue;
+                }
+                SourceStatus::Exited { attempt, max } => Some(match &startup_failure {
+                    Some(message) => format!("{} (retrying {}/{})", message, attempt, max),
+                    None => format!("tegrastats exited, retrying… ({}/{})", attempt, max),
+                }),
+                // Keep a startup failure visible until the new child proves itself.
+                SourceStatus::Restarted => app.error.clone().filter(|_| startup_failure.is_some()),
+                SourceStatus::GaveUp => Some(match &startup_failure {
+                    Some(message) => format!("{}; gave up restarting tegrastats", message),
+                    None => "tegrastats exited; gave up restarting".to_string(),
+                }),
+            };
+            app.set_error(error);
         }
-        if let Some(mut snapshot) = latest {
-            snapshot.gpu_util = last_gpu_util;
-            app.history.push(&snapshot);
-            app.latest = Some(snapshot);
+
+        if let Some(runner) = extra_runner {
+            let mut changed = false;
+            while let Some((key, value)) = runner.try_recv() {
+                extra_metrics.insert(key, value);
+                changed = true;
+            }
+            dirty |= changed;
+            if changed
+                && !app.is_paused()
+                && let Some(snapshot) = app.latest.as_mut()
+            {
+                snapshot.extra = extra_metrics.clone();
+            }
         }
 
-        if let Some(runner) = gpu_runner.as_ref() {
-            while let Some(util) = runner.try_recv() {
-                last_gpu_util = Some(util);
-                if let Some(snapshot) = app.latest.as_mut() {
-                    snapshot.gpu_util = Some(util);
+        let mut gpu_updates = Vec::new();
+        if let Some(pending) = gpu_runners.pending.take() {
+            match pending.runner.try_recv() {
+                None => gpu_runners.pending = Some(pending),
+                // Nothing is lost without nvidia-smi before or a new path,
+                // so only a failed replacement is reported.
+                Some(GpuUpdate::Failed(err)) => {
+                    if gpu_runners.live.is_some() || pending.path != app.nvidia_smi_path {
+                        app.set_error(Some(err));
+                    }
+                }
+                Some(update) => {
+                    if let Some(live) = gpu_runners.live.as_mut() {
+                        live.shutdown();
+                    }
+                    gpu_runners.live = Some(pending.runner);
+                    app.nvidia_smi_path = pending.path;
+                    gpu_updates.push(update);
+                }
+            }
+        }
+        let mut gpu_failed = false;
+        if let Some(runner) = gpu_runners.live.as_ref() {
+            gpu_updates.extend(iter::from_fn(|| runner.try_recv()));
+            for update in gpu_updates {
+                dirty = true;
+                app.gpu_unresponsive = update == GpuUpdate::Unresponsive;
+                last_gpus = match update {
+                    GpuUpdate::Stats(gpus) => gpus,
+                    GpuUpdate::Unresponsive => Vec::new(),
+                    // No nvidia-smi is the usual case on a Jetson, so it is
+                    // not reported; GR3D stands in.
+                    GpuUpdate::Failed(_) => {
+                        gpu_failed = true;
+                        Vec::new()
+                    }
+                };
+                last_gpus_at = Instant::now();
+                if !app.is_paused()
+                    && let Some(snapshot) = app.latest.as_mut()
+                {
+                    snapshot.set_gpus(&last_gpus);
                 }
             }
         }
+        if gpu_failed {
+            gpu_runners.live = None;
+        }
+
+        // A reading that stopped updating would look live, so after a few
+        // intervals fall back to tegrastats' GR3D load instead.
+        let stale_after = (Duration::from_millis(app.interval_ms) * GPU_STALE_INTERVALS)
+            .max(app.nvidia_smi_timeout);
+        if !last_gpus.is_empty() && last_gpus_at.elapsed() >= stale_after {
+            last_gpus.clear();
+            dirty = true;
+            if !app.is_paused()
+                && let Some(snapshot) = app.latest.as_mut()
+            {
+                snapshot.set_gpus(&last_gpus);
+            }
+        }
 
-        terminal.draw(|frame| ui::draw(frame, &mut app))?;
+        let second = wall_second();
+        let frame_due = last_draw.is_none_or(|at| at.elapsed() >= frame_gap);
+        if (dirty || drawn_second != Some(second)) && frame_due {
+            let completed = terminal.draw(|frame| ui::draw(frame, &mut app))?;
+            dirty = false;
+            drawn_second = Some(second);
+            last_draw = Some(Instant::now());
+            if app.capture_pending {
+                app.capture_pending = false;
+                match capture::save_screen(completed.buffer) {
+                    Ok(path) => app.show_toast(format!("saved {}", path.display())),
+                    Err(err) => app.set_error(Some(format!("{:#}", err))),
+                }
+                dirty = true;
+            }
+        }
 
-        let timeout = tick_rate
+        let mut timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_millis(0));
+        // A change held back by the frame cap is drawn as soon as the gap is up.
+        if dirty && let Some(at) = last_draw {
+            timeout = timeout.min(frame_gap.saturating_sub(at.elapsed()));
+        }
 
         if event::poll(timeout)? {
+            // Any input (keys, mouse, resizes) may change what is shown.
+            dirty = true;
             match event::read()? {
                 Event::Key(key) => {
                     if key.kind != KeyEventKind::Press {
                         continue;
                     }
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => break,
-                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            break
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        break ExitReason::Interrupted;
+                    }
+                    if app.sources_editor.is_some() {
+                        handle_sources_editor_key(key.code, runner, gpu_runners, &mut app);
+                        continue;
+                    }
+                    if app.interval_input.is_some() {
+                        handle_interval_input_key(key.code, runner, gpu_runners, &mut app);
+                        continue;
+                    }
+                    if app.temp_filter_input.is_some() {
+                        handle_temp_filter_key(key.code, &mut app);
+                        continue;
+                    }
+                    let Some(action) = keys::action_for(key.code) else {
+                        continue;
+                    };
+                    match action {
+                        Action::Quit => break ExitReason::Quit,
+                        Action::TogglePane(pane) => toggle_pane(&mut app, pane),
+                        Action::ToggleHelp => {
+                            app.show_help = !app.show_help;
+                            app.help_scroll = 0;
+                        }
+                        Action::ResetHistory => {
+                            app.history.reset();
+                            app.power_max.reset();
+                            app.stability.reset();
+                            app.anomalies.reset();
+                        }
+                        Action::ToggleRamDetail => app.ram_detail = !app.ram_detail,
+                        Action::DismissNotice => app.notice = None,
+                        Action::CaptureScreen => app.capture_pending = true,
+                        Action::ExportHistory => {
+                            match capture::save_history_csv(&app.history, &args.export_dir) {
+                                Ok(path) => app.show_toast(format!("saved {}", path.display())),
+                                Err(err) => app.set_error(Some(format!("{:#}", err))),
+                            }
+                        }
+                        Action::ToggleHeatmap => app.cpu_heatmap = !app.cpu_heatmap,
+                        Action::ToggleTable => app.table_view = !app.table_view,
+                        Action::ToggleRamFree => app.ram_free = !app.ram_free,
+                        Action::ToggleLfb => app.show_lfb = !app.show_lfb,
+                        Action::ToggleTempUnit => app.temp_unit = app.temp_unit.toggle(),
+                        Action::TogglePowerUnit => app.power_unit = app.power_unit.toggle(),
+                        Action::CyclePowerSummary => cycle_power_summary(&mut app),
+                        Action::TogglePowerScale => app.toggle_power_scale(),
+                        Action::CycleSmoothing => app.cycle_smoothing(),
+                        Action::TogglePercentiles => app.show_percentiles = !app.show_percentiles,
+                        Action::CycleTempSort => app.temp_sort = app.temp_sort.next(),
+                        Action::EditTempFilter => {
+                            app.temp_filter_input = Some(app.temp_filter.clone());
+                        }
+                        Action::EditInterval => app.interval_input = Some(String::new()),
+                        Action::ToggleZoom => app.toggle_zoom(),
+                        Action::SelectNext => app.select_next(true),
+                        Action::SelectPrev => app.select_next(false),
+                        Action::TogglePause => toggle_pause(&mut app),
+                        Action::ScrollBack if app.is_paused() => app.scroll_history(1),
+                        Action::ScrollForward if app.is_paused() => app.scroll_history(-1),
+                        // The help overlay is drawn on top, so it takes the scroll.
+                        Action::ScrollUp if app.show_help => {
+                            app.help_scroll = app.help_scroll.saturating_sub(1);
+                        }
+                        Action::ScrollDown if app.show_help => app.help_scroll += 1,
+                        Action::ScrollUp if app.table_view => {
+                            app.table_scroll = app.table_scroll.saturating_sub(1);
+                        }
+                        Action::ScrollDown if app.table_view => {
+                            let rows = ui::table_row_count(&app);
+                            app.table_scroll = (app.table_scroll + 1).min(rows);
+                        }
+                        Action::ScrollUp if app.focused_scroll().is_some() => {
+                            app.scroll_focused(-1);
+                        }
+                        Action::ScrollDown if app.focused_scroll().is_some() => {
+                            app.scroll_focused(1);
+                        }
+                        Action::EditSources => {
+                            app.sources_editor = Some(SourcesEditor::new(
+                                &app.tegrastats_path,
+                                &app.nvidia_smi_path,
+                            ));
+                        }
+                        Action::IntervalUp => {
+                            update_interval(runner, gpu_runners, 250, &mut app);
                         }
-                        KeyCode::Char('1') => toggle_pane(&mut app, PaneToggle::Cpu),
-                        KeyCode::Char('2') => toggle_pane(&mut app, PaneToggle::Ram),
-                        KeyCode::Char('3') => toggle_pane(&mut app, PaneToggle::Gpu),
-                        KeyCode::Char('4') => toggle_pane(&mut app, PaneToggle::Temps),
-                        KeyCode::Char('5') => toggle_pane(&mut app, PaneToggle::Power),
-                        KeyCode::Char('h') => app.show_help = !app.show_help,
-                        KeyCode::Char('r') => app.history.reset(),
-                        KeyCode::Char('+') => {
-                            update_interval(
-                                runner,
-                                gpu_runner,
-                                tegrastats_path,
-                                nvidia_smi_path,
-                                250,
-                                &mut app,
-                            );
+                        Action::IntervalDown => {
+                            update_interval(runner, gpu_runners, -250, &mut app);
                         }
-                        KeyCode::Char('-') => {
-                            update_interval(
-                                runner,
-                                gpu_runner,
-                                tegrastats_path,
-                                nvidia_smi_path,
-                                -250,
-                                &mut app,
-                            );
+                        Action::ShrinkHistory => {
+                            let capacity = app.history.capacity() / 2;
+                            resize_history(&mut app, capacity);
                         }
-                        _ => {}
+                        Action::GrowHistory => {
+                            let capacity = app.history.capacity().saturating_mul(2);
+                            resize_history(&mut app, capacity);
+                        }
+                        Action::ScrollBack
+                        | Action::ScrollForward
+                        | Action::ScrollUp
+                        | Action::ScrollDown => {}
                     }
                 }
                 Event::Mouse(mouse) => {
                     if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
                         let column = mouse.column;
                         let row = mouse.row;
-                        if let Some(button) = app.buttons.minus {
-                            if button.contains(column, row) {
-                                update_interval(
-                                    runner,
-                                    gpu_runner,
-                                    tegrastats_path,
-                                    nvidia_smi_path,
-                                    -250,
-                                    &mut app,
-                                );
-                                continue;
-                            }
+                        if let Some(button) = app.buttons.minus
+                            && button.contains(column, row)
+                        {
+                            update_interval(runner, gpu_runners, -250, &mut app);
+                            continue;
                         }
-                        if let Some(button) = app.buttons.plus {
-                            if button.contains(column, row) {
-                                update_interval(
-                                    runner,
-                                    gpu_runner,
-                                    tegrastats_path,
-                                    nvidia_smi_path,
-                                    250,
-                                    &mut app,
-                                );
-                            }
+                        if let Some(button) = app.buttons.plus
+                            && button.contains(column, row)
+                        {
+                            update_interval(runner, gpu_runners, 250, &mut app);
+                            continue;
+                        }
+                        if let Some(pane) = app.buttons.pane_at(column, row) {
+                            app.selected_pane = Some(pane);
                         }
                     } else if mouse.kind == MouseEventKind::Moved {
                         let column = mouse.column;
                         let row = mouse.row;
-                        if let Some(button) = app.buttons.minus {
-                            if button.contains(column, row) {
-                                app.hover = crate::model::HoverTarget::Minus;
-                                continue;
-                            }
+                        if let Some(button) = app.buttons.minus
+                            && button.contains(column, row)
+                        {
+                            app.hover = crate::model::HoverTarget::Minus;
+                            continue;
                         }
-                        if let Some(button) = app.buttons.plus {
-                            if button.contains(column, row) {
-                                app.hover = crate::model::HoverTarget::Plus;
-                                continue;
-                            }
+                        if let Some(button) = app.buttons.plus
+                            && button.contains(column, row)
+                        {
+                            app.hover = crate::model::HoverTarget::Plus;
+                            continue;
                         }
                         app.hover = crate::model::HoverTarget::None;
                     }
@@ -210,69 +996,396 @@ fn run_app(
 
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
+            app.blink_phase = app.blink_phase.wrapping_add(1);
+            dirty |= app.throttle_suspected();
         }
-    }
+    };
 
-    Ok(())
+    Ok((reason, SavedState::from_app(&app)))
+}
+
+/// Wall-clock seconds; the header clock changes when this does.
+fn wall_second() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
 
 fn restart_sources(
     runner: &mut TegrastatsRunner,
-    gpu_runner: &mut Option<GpuUtilRunner>,
+    gpu_runners: &mut GpuRunners,
     path: &str,
     nvidia_smi_path: &str,
     next_interval: u64,
     app: &mut AppState,
 ) -> Result<()> {
-    if next_interval == app.interval_ms {
-        return Ok(());
-    }
-    let new_runner = TegrastatsRunner::spawn(path, next_interval)?;
+    let new_runner = match app.replay_path.as_deref() {
+        Some(log) => TegrastatsRunner::replay(log, next_interval)?,
+        None => TegrastatsRunner::spawn(
+            path,
+            &app.tegrastats_args,
+            next_interval,
+            app.interval_style,
+            None,
+        )?,
+    };
+    // Recording moves over only once the new runner is up, so a failed
+    // restart leaves it on the old one.
+    new_runner.set_recorder(runner.take_recorder());
     runner.shutdown();
     *runner = new_runner;
-    if let Some(runner) = gpu_runner.as_mut() {
-        runner.shutdown();
+    // The running nvidia-smi keeps reporting until this one does.
+    if let Some(pending) = gpu_runners.pending.as_mut() {
+        pending.runner.shutdown();
     }
-    *gpu_runner = GpuUtilRunner::spawn(nvidia_smi_path, next_interval).ok();
+    gpu_runners.pending = Some(PendingGpu {
+        runner: GpuUtilRunner::spawn(
+            nvidia_smi_path,
+            &app.nvidia_smi_args,
+            next_interval,
+            app.nvidia_smi_timeout,
+        ),
+        path: nvidia_smi_path.to_string(),
+    });
+    app.tegrastats_path = path.to_string();
     app.interval_ms = next_interval;
-    app.error = None;
+    app.reset_sample_rate();
     Ok(())
 }
 
 fn update_interval(
     runner: &mut TegrastatsRunner,
-    gpu_runner: &mut Option<GpuUtilRunner>,
-    path: &str,
-    nvidia_smi_path: &str,
+    gpu_runners: &mut GpuRunners,
     delta: i64,
     app: &mut AppState,
 ) {
     let next = if delta.is_negative() {
-        let amount = delta.abs() as u64;
-        app.interval_ms.saturating_sub(amount).max(250)
+        let amount = delta.unsigned_abs();
+        app.interval_ms.saturating_sub(amount).max(MIN_INTERVAL_MS)
     } else {
-        (app.interval_ms + delta as u64).min(5000)
+        (app.interval_ms + delta as u64).min(MAX_INTERVAL_MS)
+    };
+    set_interval(runner, gpu_runners, next, app);
+}
+
+fn set_interval(
+    runner: &mut TegrastatsRunner,
+    gpu_runners: &mut GpuRunners,
+    next: u64,
+    app: &mut AppState,
+) {
+    if next == app.interval_ms {
+        return;
+    }
+
+    let path = app.tegrastats_path.clone();
+    let nvidia_smi_path = app.nvidia_smi_path.clone();
+    if let Err(err) = restart_sources(runner, gpu_runners, &path, &nvidia_smi_path, next, app) {
+        app.set_error(Some(err.to_string()));
+    }
+}
+
+fn handle_interval_input_key(
+    code: KeyCode,
+    runner: &mut TegrastatsRunner,
+    gpu_runners: &mut GpuRunners,
+    app: &mut AppState,
+) {
+    let Some(input) = app.interval_input.as_mut() else {
+        return;
+    };
+    match code {
+        KeyCode::Esc => app.interval_input = None,
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Char(c) if c.is_ascii_digit() && input.len() < 5 => input.push(c),
+        KeyCode::Enter => {
+            let typed = app.interval_input.take().unwrap_or_default();
+            match typed.parse::<u64>() {
+                Ok(interval) => {
+                    let next = interval.clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS);
+                    set_interval(runner, gpu_runners, next, app);
+                    if next != interval {
+                        app.show_toast(format!("interval clamped to {}ms", next));
+                    }
+                }
+                Err(_) => app.show_toast("interval unchanged: no number typed".to_string()),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys for the Temps filter prompt; Enter applies the text (empty clears).
+fn handle_temp_filter_key(code: KeyCode, app: &mut AppState) {
+    let Some(input) = app.temp_filter_input.as_mut() else {
+        return;
+    };
+    match code {
+        KeyCode::Esc => app.temp_filter_input = None,
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Char(c) if input.chars().count() < 24 => input.push(c),
+        KeyCode::Enter => {
+            let typed = app.temp_filter_input.take().unwrap_or_default();
+            let typed = typed.trim();
+            if let Some(threshold) = typed.strip_prefix('>')
+                && threshold.trim().parse::<f32>().is_err()
+            {
+                app.show_toast(format!("filter unchanged: `{}` is not a number", threshold));
+                return;
+            }
+            app.temp_filter = typed.to_string();
+        }
+        _ => {}
+    }
+}
+
+fn handle_sources_editor_key(
+    code: KeyCode,
+    runner: &mut TegrastatsRunner,
+    gpu_runners: &mut GpuRunners,
+    app: &mut AppState,
+) {
+    let Some(editor) = app.sources_editor.as_mut() else {
+        return;
     };
+    match code {
+        KeyCode::Esc => app.sources_editor = None,
+        KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => editor.next_field(),
+        KeyCode::Backspace => {
+            editor.active_mut().pop();
+        }
+        KeyCode::Char(c) => editor.active_mut().push(c),
+        KeyCode::Enter => {
+            let path = editor.tegrastats.trim().to_string();
+            let nvidia_smi_path = editor.nvidia_smi.trim().to_string();
+            let interval = app.interval_ms;
+            match restart_sources(runner, gpu_runners, &path, &nvidia_smi_path, interval, app) {
+                Ok(()) => app.sources_editor = None,
+                Err(err) => app.set_error(Some(format!("{:#}", err))),
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn running_as_root() -> bool {
+    false
+}
+
+/// The `--on-alert` hook, if one is configured.
+fn alert_hook(args: &Args) -> Option<AlertHook> {
+    let command = args.on_alert.clone()?;
+    Some(AlertHook::new(
+        command,
+        args.alert_temp.clone(),
+        args.alert_power_mw,
+        args.power_summary.clone(),
+        args.temp_range,
+        Duration::from_secs(args.alert_cooldown),
+    ))
+}
+
+/// Where each tegrastats reading goes besides the dashboard.
+struct SnapshotSinks<'a> {
+    metrics: Option<&'a MetricsServer>,
+    socket: Option<&'a SnapshotSocket>,
+    db: Option<&'a mut SnapshotDb>,
+    alert_hook: Option<&'a mut AlertHook>,
+}
+
+/// Publishes, stores and shows every reading that arrived since the last
+/// tick; only drawing is coalesced, so a frame rate below the sample rate
+/// loses none. Returns whether any arrived.
+fn apply_snapshots(
+    app: &mut AppState,
+    snapshots: impl IntoIterator<Item = StatsSnapshot>,
+    gpus: &[GpuStats],
+    extra: &HashMap<String, f64>,
+    sinks: &mut SnapshotSinks,
+) -> bool {
+    let mut any = false;
+    for mut snapshot in snapshots {
+        any = true;
+        let now = Instant::now();
+        snapshot.set_gpus(gpus);
+        snapshot.extra = extra.clone();
+        if let Some(metrics) = sinks.metrics {
+            metrics.publish(&snapshot);
+        }
+        if let Some(socket) = sinks.socket {
+            socket.publish(&snapshot);
+        }
+        // A failing database is reported but does not stop the dashboard.
+        if let Some(db) = sinks.db.as_mut()
+            && let Err(err) = db.insert(&snapshot)
+        {
+            app.set_error(Some(format!("{:#}", err)));
+        }
+        if snapshot.crit_breached(app.temp_range) {
+            app.any_crit_breached = true;
+        }
+        // Checked on every reading, so pausing the view does not pause alerts.
+        if let Some(hook) = sinks.alert_hook.as_mut() {
+            for alert in &hook.crossings(&snapshot, now) {
+                match hook.fire(alert) {
+                    Ok(()) => app.show_toast(format!(
+                        "alert: {} reached {}",
+                        alert.metric, alert.threshold
+                    )),
+                    Err(err) => app.set_error(Some(format!("{:#}", err))),
+                }
+            }
+        }
+        if app.is_paused() {
+            app.queue_paused(snapshot, now);
+        } else {
+            record_snapshot(app, snapshot, now);
+        }
+    }
+    any
+}
 
-    if let Err(err) = restart_sources(runner, gpu_runner, path, nvidia_smi_path, next, app) {
-        app.error = Some(err.to_string());
+fn record_snapshot(app: &mut AppState, snapshot: StatsSnapshot, at: Instant) {
+    app.history.push_at(&snapshot, app.cpu_aggregate, at);
+    app.stability.update(&app.history);
+    let anomalies = app.anomalies.observe_snapshot(
+        &snapshot,
+        app.cpu_aggregate,
+        &app.history.power_summary,
+        at,
+    );
+    // Every anomaly is in the detector's log; the toast names the first.
+    if let Some(anomaly) = anomalies.first() {
+        let more = match anomalies.len() {
+            1 => String::new(),
+            count => format!(", +{} more in help", count - 1),
+        };
+        app.show_toast(format!(
+            "anomaly: {:?} {:.0} (z={:.1}){}",
+            anomaly.metric, anomaly.value, anomaly.z_score, more
+        ));
+    }
+    if let Some(observed) = app.history.power_total.values.iter().copied().max() {
+        app.power_max.update(observed, app.scale_smoothing);
+    }
+    app.note_sample(at);
+    app.latest = Some(snapshot);
+}
+
+/// Freezes the display, or resumes it by recording everything that arrived
+/// while paused with its original timestamp.
+fn toggle_pause(app: &mut AppState) {
+    if app.paused_at.take().is_some() {
+        app.scroll_offset = 0;
+        while let Some((snapshot, at)) = app.paused_backlog.pop_front() {
+            record_snapshot(app, snapshot, at);
+        }
+    } else {
+        app.paused_at = Some(Instant::now());
     }
 }
 
-enum PaneToggle {
-    Cpu,
-    Ram,
-    Gpu,
-    Temps,
-    Power,
+fn resize_history(app: &mut AppState, capacity: usize) {
+    app.set_history_capacity(capacity);
+    let span_ms = app.history.capacity() as u64 * app.interval_ms;
+    app.show_toast(format!(
+        "history: {} samples (~{}s)",
+        app.history.capacity(),
+        span_ms / 1000
+    ));
 }
 
-fn toggle_pane(app: &mut AppState, pane: PaneToggle) {
+/// Switches what the power total tracks. Past samples measured something
+/// else, so the power history, scale and anomaly stats start over.
+fn cycle_power_summary(app: &mut AppState) {
+    let rails = app
+        .latest
+        .as_ref()
+        .map(|snapshot| snapshot.power_rails.as_slice())
+        .unwrap_or_default();
+    let next = app.history.power_summary.next(rails);
+    app.history.set_power_summary(next);
+    app.power_max.reset();
+    app.anomalies.forget(SparkMetric::Power);
+    app.show_toast(format!("power total: {}", app.history.power_summary.label()));
+}
+
+fn toggle_pane(app: &mut AppState, pane: Pane) {
     match pane {
-        PaneToggle::Cpu => app.panes.cpu = !app.panes.cpu,
-        PaneToggle::Ram => app.panes.ram = !app.panes.ram,
-        PaneToggle::Gpu => app.panes.gpu = !app.panes.gpu,
-        PaneToggle::Temps => app.panes.temps = !app.panes.temps,
-        PaneToggle::Power => app.panes.power = !app.panes.power,
+        Pane::Cpu => app.panes.cpu = !app.panes.cpu,
+        Pane::Ram => app.panes.ram = !app.panes.ram,
+        Pane::Gpu => app.panes.gpu = !app.panes.gpu,
+        Pane::Temps => app.panes.temps = !app.panes.temps,
+        Pane::Power => app.panes.power = !app.panes.power,
+        Pane::Swap => app.panes.swap = !app.panes.swap,
+    }
+    // Hiding the zoomed panel leaves the zoom; hiding the selected one
+    // drops the selection.
+    if app.focused_pane.is_some_and(|focused| !app.panes.shows(focused)) {
+        app.focused_pane = None;
+    }
+    if app.selected_pane.is_some_and(|selected| !app.panes.shows(selected)) {
+        app.selected_pane = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    use rusqlite::Connection;
+
+    use super::{apply_snapshots, SnapshotSinks};
+    use crate::db::SnapshotDb;
+    use crate::model::{AppState, CpuAggregate, PowerSummary, StatsSnapshot};
+
+    #[test]
+    fn applies_every_snapshot_queued_in_one_tick() {
+        let path = std::env::temp_dir().join(format!("jmon-main-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut db = SnapshotDb::open(&path, CpuAggregate::Mean, PowerSummary::default()).unwrap();
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let queued = |from: u64| {
+            (from..from + 3).map(|used| StatsSnapshot {
+                ram_used_mb: Some(used),
+                ..StatsSnapshot::default()
+            })
+        };
+
+        let mut sinks = SnapshotSinks {
+            metrics: None,
+            socket: None,
+            db: Some(&mut db),
+            alert_hook: None,
+        };
+        assert!(apply_snapshots(&mut app, queued(1), &[], &HashMap::new(), &mut sinks));
+        assert_eq!(app.history.ram_used.values.len(), 3);
+        assert_eq!(app.latest.as_ref().and_then(|latest| latest.ram_used_mb), Some(3));
+
+        app.paused_at = Some(Instant::now());
+        assert!(apply_snapshots(&mut app, queued(4), &[], &HashMap::new(), &mut sinks));
+        assert_eq!(app.paused_backlog.len(), 3);
+        assert!(!apply_snapshots(&mut app, Vec::new(), &[], &HashMap::new(), &mut sinks));
+
+        drop(db);
+        let samples: i64 = Connection::open(&path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM samples", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(samples, 6);
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -0,0 +1,247 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::model::{StatsSnapshot, TempRange};
+
+/// How long a scrape may take to send its request or read the response
+/// before it is dropped.
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Serves the latest snapshot at `/metrics` in the Prometheus text format
+/// from a background thread. Temperatures outside `temp_range` are left out.
+pub struct MetricsServer {
+    latest: Arc<Mutex<Option<StatsSnapshot>>>,
+}
+
+impl MetricsServer {
+    pub fn spawn(addr: &str, temp_range: TempRange) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .with_context(|| format!("failed to listen for metrics on `{}`", addr))?;
+        let latest = Arc::new(Mutex::new(None));
+        let shared = Arc::clone(&latest);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                // Each scrape gets its own thread, so a stalled one cannot hold
+                // up the others; it only loses its own response.
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || handle_connection(stream, &shared, temp_range));
+            }
+        });
+
+        Ok(Self { latest })
+    }
+
+    /// Replaces the snapshot scrapes see.
+    pub fn publish(&self, snapshot: &StatsSnapshot) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(snapshot.clone());
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    latest: &Mutex<Option<StatsSnapshot>>,
+    temp_range: TempRange,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(SCRAPE_TIMEOUT))?;
+    stream.set_write_timeout(Some(SCRAPE_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers; nothing in them changes the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = match latest.lock().ok().and_then(|latest| latest.clone()) {
+                Some(snapshot) => render_metrics(&snapshot, temp_range),
+                None => String::new(),
+            };
+            ("200 OK", body)
+        }
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Renders every reading the snapshot has as Prometheus gauges; missing
+/// readings and temperatures outside `temp_range` are left out rather than
+/// reported as 0 or a sentinel.
+pub fn render_metrics(snapshot: &StatsSnapshot, temp_range: TempRange) -> String {
+    let mut out = String::new();
+    let mb = |value: Option<u64>| value.map(|mb| mb as f64);
+    let percent = |value: Option<f32>| value.map(f64::from);
+
+    gauge(
+        &mut out,
+        "cpu_core_percent",
+        "Per-core CPU utilization.",
+        snapshot
+            .cpu_cores
+            .iter()
+            .enumerate()
+            .map(|(core, util)| (label("core", &core.to_string()), f64::from(*util))),
+    );
+    gauge(&mut out, "ram_used_mb", "RAM in use.", unlabeled(mb(snapshot.ram_used_mb)));
+    gauge(&mut out, "ram_total_mb", "Total RAM.", unlabeled(mb(snapshot.ram_total_mb)));
+    gauge(&mut out, "swap_used_mb", "Swap in use.", unlabeled(mb(snapshot.swap_used_mb)));
+    gauge(&mut out, "swap_total_mb", "Total swap.", unlabeled(mb(snapshot.swap_total_mb)));
+    gauge(
+        &mut out,
+        "gpu_util_percent",
+        "GPU utilization, averaged over GPUs.",
+        unlabeled(percent(snapshot.gpu_util)),
+    );
+    gauge(
+        &mut out,
+        "gpu_mem_used_mb",
+        "GPU memory in use, summed over GPUs.",
+        unlabeled(mb(snapshot.gpu_mem_used_mb)),
+    );
+    gauge(
+        &mut out,
+        "emc_util_percent",
+        "Memory controller utilization.",
+        unlabeled(percent(snapshot.emc_util)),
+    );
+    gauge(
+        &mut out,
+        "temperature_celsius",
+        "Temperature per sensor.",
+        snapshot
+            .temps
+            .iter()
+            .filter(|temp| temp_range.contains(temp.value_c))
+            .map(|temp| (label("sensor", &temp.name), f64::from(temp.value_c))),
+    );
+    gauge(
+        &mut out,
+        "power_rail_mw",
+        "Instantaneous power per rail.",
+        snapshot
+            .power_rails
+            .iter()
+            .map(|rail| (label("rail", &rail.name), rail.current_mw as f64)),
+    );
+    out
+}
+
+/// Appends one gauge family; families without samples are skipped entirely.
+fn gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    let mut samples = samples.into_iter().peekable();
+    if samples.peek().is_none() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP jmon_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE jmon_{} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "jmon_{}{} {}", name, labels, value);
+    }
+}
+
+fn unlabeled(value: Option<f64>) -> Option<(String, f64)> {
+    value.map(|value| (String::new(), value))
+}
+
+fn label(name: &str, value: &str) -> String {
+    format!("{{{}=\"{}\"}}", name, escape_label(value))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    use super::{MetricsServer, render_metrics};
+    use crate::model::{PowerRail, StatsSnapshot, TempRange, TempReading};
+
+    #[test]
+    fn a_stalled_scrape_does_not_block_the_next_one() {
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server = MetricsServer::spawn(&addr.to_string(), TempRange::default()).unwrap();
+        server.publish(&StatsSnapshot {
+            ram_used_mb: Some(1024),
+            ..StatsSnapshot::default()
+        });
+
+        // Connects but never sends its request.
+        let _stalled = TcpStream::connect(addr).unwrap();
+        let mut scrape = TcpStream::connect(addr).unwrap();
+        scrape.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        scrape
+            .set_read_timeout(Some(std::time::Duration::from_secs(1)))
+            .unwrap();
+        let mut response = String::new();
+        scrape.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("jmon_ram_used_mb 1024\n"));
+    }
+
+    #[test]
+    fn renders_labeled_gauges_and_skips_missing_readings() {
+        let snapshot = StatsSnapshot {
+            cpu_cores: vec![12.0, 0.5],
+            ram_used_mb: Some(1024),
+            temps: vec![
+                TempReading {
+                    name: "tj".to_string(),
+                    value_c: 43.5,
+                },
+                TempReading {
+                    name: "PMIC".to_string(),
+                    value_c: -256.0,
+                },
+            ],
+            power_rails: vec![PowerRail {
+                name: "VDD_GPU".to_string(),
+                current_mw: 5000,
+                average_mw: 4000,
+            }],
+            ..StatsSnapshot::default()
+        };
+        let text = render_metrics(&snapshot, TempRange::default());
+
+        assert!(text.contains("# TYPE jmon_cpu_core_percent gauge\n"));
+        assert!(text.contains("jmon_cpu_core_percent{core=\"0\"} 12\n"));
+        assert!(text.contains("jmon_cpu_core_percent{core=\"1\"} 0.5\n"));
+        assert!(text.contains("jmon_ram_used_mb 1024\n"));
+        assert!(text.contains("jmon_temperature_celsius{sensor=\"tj\"} 43.5\n"));
+        assert!(!text.contains("PMIC"));
+        assert!(text.contains("jmon_power_rail_mw{rail=\"VDD_GPU\"} 5000\n"));
+        assert!(!text.contains("jmon_gpu_util_percent"));
+    }
+}
@@ -1,24 +1,105 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
 
-#[derive(Clone, Debug, Default)]
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::Serialize;
+
+/// Temperature at or above which a sensor counts as a crit breach.
+pub const TEMP_CRIT_C: f32 = 85.0;
+
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// How far back the VIN rolling average (and the battery estimate) looks.
+pub const VIN_AVERAGE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Bounds for the number of samples each history series keeps.
+pub const MIN_HISTORY_CAPACITY: usize = 10;
+pub const MAX_HISTORY_CAPACITY: usize = 10_000;
+/// Gaps between accepted snapshots averaged into the effective interval.
+const SAMPLE_GAP_WINDOW: usize = 10;
+
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct StatsSnapshot {
+    /// Device time from the line's `MM-DD-YYYY HH:MM:SS` prefix, when present.
+    pub timestamp: Option<NaiveDateTime>,
     pub cpu_cores: Vec<f32>,
+    /// Per-core clock from the `util%@MHz` entries, parallel to `cpu_cores`.
+    pub cpu_freqs_mhz: Vec<Option<u32>>,
+    /// Whether each core is online; offline (`off`) cores read as 0% in `cpu_cores`.
+    pub cpu_online: Vec<bool>,
+    /// The CPU field was present but no core utilization could be parsed.
+    pub cpu_unparseable: bool,
     pub ram_used_mb: Option<u64>,
     pub ram_total_mb: Option<u64>,
     pub swap_used_mb: Option<u64>,
     pub swap_total_mb: Option<u64>,
+    pub lfb_blocks: Option<u64>,
+    pub lfb_block_mb: Option<u64>,
+    /// Mean utilization over `gpus`, or tegrastats' `gr3d_util` without them.
     pub gpu_util: Option<f32>,
+    /// Integrated GPU load from tegrastats' `GR3D_FREQ`.
+    pub gr3d_util: Option<f32>,
+    /// GPU memory summed over `gpus`; `None` unless every GPU reports it,
+    /// e.g. on integrated GPUs that share RAM.
+    pub gpu_mem_used_mb: Option<u64>,
+    pub gpu_mem_total_mb: Option<u64>,
+    /// Per-GPU readings from nvidia-smi, in index order.
+    pub gpus: Vec<GpuStats>,
     pub emc_util: Option<f32>,
     pub temps: Vec<TempReading>,
     pub power_rails: Vec<PowerRail>,
+    /// Fan speed and/or duty cycle, on boards whose tegrastats reports them.
+    pub fan: Option<FanReading>,
+    /// Metrics from `--extra-source`, keyed by the name the script printed.
+    pub extra: HashMap<String, f64>,
 }
 
 impl StatsSnapshot {
-    pub fn cpu_total(&self) -> Option<f32> {
-        if self.cpu_cores.is_empty() {
-            None
-        } else {
-            Some(self.cpu_cores.iter().sum::<f32>() / self.cpu_cores.len() as f32)
+    /// Stores the latest nvidia-smi readings, or clears them when `gpus` is
+    /// empty, and updates the summary fields. Without nvidia-smi readings the
+    /// utilization falls back to tegrastats' GR3D load.
+    pub fn set_gpus(&mut self, gpus: &[GpuStats]) {
+        self.gpus = gpus.to_vec();
+        if gpus.is_empty() {
+            self.gpu_util = self.gr3d_util;
+            self.gpu_mem_used_mb = None;
+            self.gpu_mem_total_mb = None;
+            return;
+        }
+        self.gpu_util = Some(gpus.iter().map(|gpu| gpu.util).sum::<f32>() / gpus.len() as f32);
+        self.gpu_mem_used_mb = gpus.iter().map(|gpu| gpu.mem_used_mb).sum();
+        self.gpu_mem_total_mb = gpus.iter().map(|gpu| gpu.mem_total_mb).sum();
+    }
+
+    /// Cores without a recorded state count as online.
+    pub fn core_online(&self, index: usize) -> bool {
+        self.cpu_online.get(index).copied().unwrap_or(true)
+    }
+
+    /// Combines the online cores only; offline cores are not idle cores.
+    pub fn cpu_total(&self, method: CpuAggregate) -> Option<f32> {
+        let online: Vec<f32> = self
+            .cpu_cores
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| self.core_online(*idx))
+            .map(|(_, util)| *util)
+            .collect();
+        if online.is_empty() {
+            return None;
+        }
+
+        match method {
+            CpuAggregate::Mean => Some(online.iter().sum::<f32>() / online.len() as f32),
+            CpuAggregate::Max => online.iter().copied().reduce(f32::max),
+            CpuAggregate::ActiveMean => {
+                let active: Vec<f32> = online.into_iter().filter(|util| *util > 0.0).collect();
+                if active.is_empty() {
+                    Some(0.0)
+                } else {
+                    Some(active.iter().sum::<f32>() / active.len() as f32)
+                }
+            }
         }
     }
 
@@ -31,11 +112,37 @@ impl StatsSnapshot {
         }
     }
 
-    pub fn total_power_mw(&self) -> Option<u64> {
+    pub fn lfb_mb(&self) -> Option<u64> {
+        match (self.lfb_blocks, self.lfb_block_mb) {
+            (Some(blocks), Some(size)) => Some(blocks * size),
+            _ => None,
+        }
+    }
+
+    /// Whether any plausible temperature reached [`TEMP_CRIT_C`].
+    pub fn crit_breached(&self, plausible: TempRange) -> bool {
+        self.temps
+            .iter()
+            .any(|temp| plausible.contains(temp.value_c) && temp.value_c >= TEMP_CRIT_C)
+    }
+
+    pub fn total_power_mw(&self, summary: &PowerSummary) -> Option<u64> {
         if self.power_rails.is_empty() {
             return None;
         }
 
+        let rail_mw = |name: &str| {
+            self.power_rails
+                .iter()
+                .find(|rail| rail.name == name)
+                .map(|rail| rail.current_mw)
+        };
+        match summary {
+            PowerSummary::SumComponents => {}
+            PowerSummary::InputRail => return rail_mw("VIN"),
+            PowerSummary::NamedRail(name) => return rail_mw(name),
+        }
+
         let mut sum_non_vin = 0;
         let mut has_non_vin = false;
 
@@ -55,123 +162,1558 @@ impl StatsSnapshot {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Which reading the power total (title, bar and sparkline) tracks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PowerSummary {
+    /// Sum of every rail except VIN, which already includes them.
+    #[default]
+    SumComponents,
+    /// The VIN input rail, i.e. wall or battery draw.
+    InputRail,
+    /// A single rail by its tegrastats name.
+    NamedRail(String),
+}
+
+impl PowerSummary {
+    /// Short label for the Power panel title, e.g. `sum` or `VIN`.
+    pub fn label(&self) -> &str {
+        match self {
+            Self::SumComponents => "sum",
+            Self::InputRail => "VIN",
+            Self::NamedRail(name) => name,
+        }
+    }
+
+    /// The strategy after this one: sum, then VIN, then each other reported
+    /// rail in order, wrapping back to sum.
+    pub fn next(&self, rails: &[PowerRail]) -> Self {
+        let named: Vec<&str> = rails
+            .iter()
+            .map(|rail| rail.name.as_str())
+            .filter(|name| *name != "VIN")
+            .collect();
+        let first_named = || {
+            named
+                .first()
+                .map_or(Self::SumComponents, |name| Self::NamedRail(name.to_string()))
+        };
+        match self {
+            Self::SumComponents => Self::InputRail,
+            Self::InputRail => first_named(),
+            Self::NamedRail(current) => match named.iter().position(|name| name == current) {
+                Some(index) if index + 1 < named.len() => {
+                    Self::NamedRail(named[index + 1].to_string())
+                }
+                Some(_) => Self::SumComponents,
+                None => first_named(),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for PowerSummary {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sum" => Ok(Self::SumComponents),
+            "vin" | "VIN" => Ok(Self::InputRail),
+            _ => match value.strip_prefix("rail:") {
+                Some(name) if !name.is_empty() => Ok(Self::NamedRail(name.to_string())),
+                _ => Err("expected `sum`, `vin` or `rail:<NAME>`".to_string()),
+            },
+        }
+    }
+}
+
+/// How per-core CPU utilization is folded into a single total.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CpuAggregate {
+    /// Mean of all cores.
+    #[default]
+    Mean,
+    /// The busiest core.
+    Max,
+    /// Mean of cores that are online and not idle.
+    ActiveMean,
+}
+
+/// Unit temperatures are displayed in; readings are always kept in Celsius.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Celsius => Self::Fahrenheit,
+            Self::Fahrenheit => Self::Celsius,
+        }
+    }
+
+    pub fn convert(self, value_c: f32) -> f32 {
+        match self {
+            Self::Celsius => value_c,
+            Self::Fahrenheit => value_c * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Celsius => "C",
+            Self::Fahrenheit => "F",
+        }
+    }
+
+    /// Formats a Celsius reading with one decimal and the unit suffix, e.g. `105.8F`.
+    pub fn format(self, value_c: f32) -> String {
+        format!("{:.1}{}", self.convert(value_c), self.suffix())
+    }
+}
+
+/// Order of the Temps list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TempSort {
+    /// The order tegrastats prints them in.
+    #[default]
+    Reported,
+    Name,
+    /// Hottest first; implausible readings go last.
+    Hottest,
+}
+
+impl TempSort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Reported => Self::Name,
+            Self::Name => Self::Hottest,
+            Self::Hottest => Self::Reported,
+        }
+    }
+
+    /// Shown in the Temps title when not the default order.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Reported => None,
+            Self::Name => Some("by name"),
+            Self::Hottest => Some("hottest"),
+        }
+    }
+}
+
+/// Whether the Temps `filter` keeps a sensor called `name`. `>N` keeps
+/// readings of at least N in the displayed `unit`, `!text` hides names
+/// containing `text`, and any other text keeps only names containing it.
+/// Matching ignores case; an empty filter keeps everything.
+pub fn temp_filter_keeps(filter: &str, name: &str, value_c: f32, unit: TempUnit) -> bool {
+    let filter = filter.trim();
+    let contains = |text: &str| name.to_lowercase().contains(&text.trim().to_lowercase());
+    if let Some(threshold) = filter.strip_prefix('>') {
+        return threshold
+            .trim()
+            .parse::<f32>()
+            .is_ok_and(|threshold| unit.convert(value_c) >= threshold);
+    }
+    match filter.strip_prefix('!') {
+        Some(excluded) => !contains(excluded),
+        None => contains(filter),
+    }
+}
+
+/// Unit power is displayed in; rails and history are always kept in mW.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PowerUnit {
+    #[default]
+    #[value(name = "mw")]
+    Milliwatts,
+    #[value(name = "w")]
+    Watts,
+}
+
+impl PowerUnit {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Milliwatts => Self::Watts,
+            Self::Watts => Self::Milliwatts,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Milliwatts => "mW",
+            Self::Watts => "W",
+        }
+    }
+
+    /// Formats a mW reading with its unit suffix, e.g. `16802mW` or `16.8W`.
+    pub fn format(self, milliwatts: u64) -> String {
+        match self {
+            Self::Milliwatts => format!("{}mW", milliwatts),
+            Self::Watts => format!("{:.1}W", milliwatts as f64 / 1000.0),
+        }
+    }
+}
+
+/// How a sparkline maps values to bar heights.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SparkScale {
+    #[default]
+    Linear,
+    /// Heights follow `log10`, so idle readings stay visible next to load spikes.
+    Log,
+}
+
+impl SparkScale {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Linear => Self::Log,
+            Self::Log => Self::Linear,
+        }
+    }
+}
+
+/// Moving-average windows the smoothing key cycles through; 1 is off.
+pub const SMOOTHING_WINDOWS: [usize; 3] = [1, 3, 5];
+
+/// Hue set for the per-metric colors and the cool/warm/hot heat scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorScheme {
+    /// Green/yellow/red heat with a distinct hue per metric.
+    #[default]
+    Default,
+    /// Blue/yellow/vermillion, safe for red-green color blindness.
+    Deuteranopia,
+    /// A single hue; only intensity varies.
+    Mono,
+}
+
+/// How many colors the terminal can display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorDepth {
+    /// Detect from `COLORTERM` and `TERM`.
+    #[default]
+    Auto,
+    /// 24-bit RGB.
+    #[value(name = "truecolor")]
+    TrueColor,
+    /// The xterm 256-color palette.
+    #[value(name = "256")]
+    Ansi256,
+    /// The basic 16 ANSI colors.
+    #[value(name = "16")]
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Resolves `Auto` using the terminal environment variables.
+    pub fn resolve(self) -> Self {
+        if self != ColorDepth::Auto {
+            return self;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self::detect(&colorterm, &term)
+    }
+
+    fn detect(colorterm: &str, term: &str) -> Self {
+        let colorterm = colorterm.to_ascii_lowercase();
+        if colorterm.contains("truecolor") || colorterm.contains("24bit") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// How the interval is given to tegrastats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IntervalStyle {
+    /// `tegrastats --interval <ms>`.
+    #[default]
+    Flag,
+    /// `tegrastats <ms>`, as some distro builds expect.
+    Positional,
+    /// No interval; the binary's own pace is used.
+    None,
+}
+
+/// Terminal background the sparkline base color is picked for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Theme {
+    /// Detect from `COLORFGBG`, falling back to dark.
+    #[default]
+    Auto,
+    /// Low values blend from white.
+    Dark,
+    /// Low values blend from near-black, which stays visible on white.
+    Light,
+}
+
+impl Theme {
+    /// Resolves `Auto` using the `COLORFGBG` variable some terminals export.
+    pub fn resolve(self) -> Self {
+        if self != Theme::Auto {
+            return self;
+        }
+        Self::detect(&std::env::var("COLORFGBG").unwrap_or_default())
+    }
+
+    /// `COLORFGBG` is `fg;bg` (or `fg;default;bg`) in ANSI color numbers;
+    /// 7 and 9-15 are the light backgrounds.
+    fn detect(colorfgbg: &str) -> Self {
+        match colorfgbg.rsplit(';').next().map(str::parse::<u8>) {
+            Some(Ok(7 | 9..=15)) => Theme::Light,
+            _ => Theme::Dark,
+        }
+    }
+
+    /// Base color low sparkline and bar values blend from.
+    pub fn spark_base(self) -> Rgb {
+        match self {
+            Theme::Light => Rgb::NEAR_BLACK,
+            Theme::Auto | Theme::Dark => Rgb::WHITE,
+        }
+    }
+}
+
+/// Metrics that have a history sparkline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum SparkMetric {
+    Cpu,
+    Ram,
+    Gpu,
+    Power,
+}
+
+/// One GPU's nvidia-smi reading. Memory, temperature and power are `None`
+/// where nvidia-smi reports `[N/A]` or cannot be asked for them, as on
+/// integrated Jetson GPUs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct GpuStats {
+    pub util: f32,
+    pub mem_used_mb: Option<u64>,
+    pub mem_total_mb: Option<u64>,
+    pub temp_c: Option<f32>,
+    pub power_mw: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct PowerRail {
     pub name: String,
     pub current_mw: u64,
     pub average_mw: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct TempReading {
     pub name: String,
     pub value_c: f32,
 }
 
+/// Either field may be missing depending on the board's tegrastats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct FanReading {
+    pub rpm: Option<u32>,
+    pub pwm_percent: Option<f32>,
+}
+
+/// Temperatures outside this range (e.g. -256C or 511C from a disconnected
+/// sensor) are shown as unknown instead of colored or counted as crit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for TempRange {
+    fn default() -> Self {
+        Self {
+            min: -20.0,
+            max: 150.0,
+        }
+    }
+}
+
+impl TempRange {
+    pub fn contains(&self, value_c: f32) -> bool {
+        (self.min..=self.max).contains(&value_c)
+    }
+}
+
+/// Where the cool/warm/hot heat scale starts, turns warm and peaks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatBounds {
+    pub low: f64,
+    pub mid: f64,
+    pub high: f64,
+}
+
+impl HeatBounds {
+    pub const TEMP: Self = Self {
+        low: 30.0,
+        mid: 60.0,
+        high: TEMP_CRIT_C as f64,
+    };
+    pub const PERCENT: Self = Self {
+        low: 0.0,
+        mid: 50.0,
+        high: 100.0,
+    };
+}
+
+/// Parses `low:mid:high` heat bounds, which must be increasing.
+pub fn parse_heat_bounds(value: &str) -> Result<HeatBounds, String> {
+    let invalid = || format!("invalid heat bounds `{}`: expected low:mid:high", value);
+    let parts = value
+        .split(':')
+        .map(|part| part.trim().parse::<f64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [low, mid, high] = parts[..] else {
+        return Err(invalid());
+    };
+    if !(low < mid && mid < high) {
+        return Err(invalid());
+    }
+    Ok(HeatBounds { low, mid, high })
+}
+
+/// Parses a `min:max` temperature range in Celsius.
+pub fn parse_temp_range(value: &str) -> Result<TempRange, String> {
+    let invalid = || format!("invalid temperature range `{}`: expected min:max", value);
+    let (min, max) = value.split_once(':').ok_or_else(invalid)?;
+    let min = min.trim().parse::<f32>().map_err(|_| invalid())?;
+    let max = max.trim().parse::<f32>().map_err(|_| invalid())?;
+    if min >= max {
+        return Err(invalid());
+    }
+    Ok(TempRange { min, max })
+}
+
+/// A bounded series of samples, each stamped with when it was recorded.
+#[derive(Debug, Default)]
+pub struct TimedSeries {
+    pub values: VecDeque<u64>,
+    pub times: VecDeque<Instant>,
+}
+
+impl TimedSeries {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            times: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, capacity: usize, value: u64, at: Instant) {
+        if self.values.len() >= capacity {
+            self.values.pop_front();
+            self.times.pop_front();
+        }
+        self.values.push_back(value);
+        self.times.push_back(at);
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.times.clear();
+    }
+
+    /// Drops the oldest samples until at most `capacity` remain.
+    fn truncate_front(&mut self, capacity: usize) {
+        let excess = self.values.len().saturating_sub(capacity);
+        self.values.drain(..excess);
+        self.times.drain(..excess);
+    }
+
+    /// The sample recorded exactly at `at`, if this series has one.
+    pub fn value_at(&self, at: Instant) -> Option<u64> {
+        self.iter_timed()
+            .find(|(time, _)| *time == at)
+            .map(|(_, value)| value)
+    }
+
+    pub fn iter_timed(&self) -> impl Iterator<Item = (Instant, u64)> + '_ {
+        self.times.iter().copied().zip(self.values.iter().copied())
+    }
+
+    /// Mean of the samples recorded within `window` of the newest one,
+    /// rounded; `None` when the series is empty.
+    pub fn recent_mean(&self, window: Duration) -> Option<u64> {
+        let newest = *self.times.back()?;
+        let recent: Vec<u64> = self
+            .iter_timed()
+            .filter(|(at, _)| newest.saturating_duration_since(*at) <= window)
+            .map(|(_, value)| value)
+            .collect();
+        let count = recent.len() as u64;
+        Some((recent.iter().sum::<u64>() + count / 2) / count)
+    }
+
+    /// A copy with each value replaced by the mean of it and up to
+    /// `window - 1` samples before it; times are kept.
+    pub fn smoothed(&self, window: usize) -> Self {
+        Self {
+            values: moving_average(&self.values, window),
+            times: self.times.clone(),
+        }
+    }
+}
+
+/// Trailing moving average of `values`, rounded; the first samples average
+/// over what is available.
+pub fn moving_average(values: &VecDeque<u64>, window: usize) -> VecDeque<u64> {
+    let window = window.max(1);
+    let mut sum = 0;
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            sum += value;
+            if index >= window {
+                sum -= values[index - window];
+            }
+            let count = (index + 1).min(window) as u64;
+            (sum + count / 2) / count
+        })
+        .collect()
+}
+
+/// The `p`th percentile (0-100) of `values`, interpolating between the two
+/// nearest samples and rounding; 0 when there are none.
+pub fn percentile(values: &VecDeque<u64>, p: f64) -> u64 {
+    let mut sorted: Vec<u64> = values.iter().copied().collect();
+    sorted.sort_unstable();
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return 0;
+    };
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let (below, above) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+    (below as f64 + (above as f64 - below as f64) * rank.fract()).round() as u64
+}
+
+/// How long a full `capacity_wh` battery lasts at a steady `draw_mw`;
+/// `None` when nothing is drawn.
+pub fn battery_runtime(capacity_wh: f64, draw_mw: u64) -> Option<Duration> {
+    if draw_mw == 0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(capacity_wh * 3_600_000.0 / draw_mw as f64).ok()
+}
+
 #[derive(Debug)]
 pub struct History {
     capacity: usize,
-    pub cpu_total: VecDeque<u64>,
-    pub ram_used: VecDeque<u64>,
-    pub gpu_util: VecDeque<u64>,
-    pub power_total: VecDeque<u64>,
+    pub cpu_total: TimedSeries,
+    pub ram_used: TimedSeries,
+    pub swap_used: TimedSeries,
+    pub gpu_util: TimedSeries,
+    pub emc_util: TimedSeries,
+    pub power_total: TimedSeries,
+    /// What `power_total` records.
+    pub power_summary: PowerSummary,
+    /// Largest free block in MB, only pushed when tegrastats reports `lfb`.
+    pub lfb_mb: TimedSeries,
+    /// The VIN input rail in mW, whatever the power summary tracks.
+    pub vin_mw: TimedSeries,
+    pub cpu_cores: Vec<TimedSeries>,
+    /// Whole degrees per sensor name; readings below 0C are stored as 0.
+    /// Sensors that stop reporting keep their series until `reset`.
+    pub temps: HashMap<String, TimedSeries>,
+    /// Temperatures outside it (e.g. -256C from a sensor that is off) are
+    /// left out of `temps`, leaving a gap.
+    pub temp_range: TempRange,
 }
 
 impl History {
     pub fn new(capacity: usize) -> Self {
         Self {
             capacity,
-            cpu_total: VecDeque::with_capacity(capacity),
-            ram_used: VecDeque::with_capacity(capacity),
-            gpu_util: VecDeque::with_capacity(capacity),
-            power_total: VecDeque::with_capacity(capacity),
+            cpu_total: TimedSeries::with_capacity(capacity),
+            ram_used: TimedSeries::with_capacity(capacity),
+            swap_used: TimedSeries::with_capacity(capacity),
+            gpu_util: TimedSeries::with_capacity(capacity),
+            emc_util: TimedSeries::with_capacity(capacity),
+            power_total: TimedSeries::with_capacity(capacity),
+            power_summary: PowerSummary::default(),
+            lfb_mb: TimedSeries::with_capacity(capacity),
+            vin_mw: TimedSeries::with_capacity(capacity),
+            cpu_cores: Vec::new(),
+            temps: HashMap::new(),
+            temp_range: TempRange::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes how many samples each series keeps, clamped to
+    /// [`MIN_HISTORY_CAPACITY`]..=[`MAX_HISTORY_CAPACITY`]. Shrinking drops the
+    /// oldest samples; growing keeps everything recorded so far.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.clamp(MIN_HISTORY_CAPACITY, MAX_HISTORY_CAPACITY);
+        let series = [
+            &mut self.cpu_total,
+            &mut self.ram_used,
+            &mut self.swap_used,
+            &mut self.gpu_util,
+            &mut self.emc_util,
+            &mut self.power_total,
+            &mut self.lfb_mb,
+            &mut self.vin_mw,
+        ];
+        let series = series
+            .into_iter()
+            .chain(self.cpu_cores.iter_mut())
+            .chain(self.temps.values_mut());
+        for series in series {
+            series.truncate_front(self.capacity);
+        }
+    }
+
+    /// Writes one CSV row per sample time, oldest first. Series that have no
+    /// sample at a given time (e.g. GPU before nvidia-smi answered) leave the
+    /// cell empty. `now` anchors the monotonic sample times to wall-clock.
+    /// The writer is flushed, so a buffered write error is reported too.
+    pub fn to_csv<W: Write>(
+        &self,
+        mut writer: W,
+        now: (Instant, DateTime<Local>),
+    ) -> io::Result<()> {
+        let mut columns: Vec<(String, &TimedSeries)> = vec![
+            ("cpu_total".to_string(), &self.cpu_total),
+            ("ram_used_mb".to_string(), &self.ram_used),
+            ("swap_used_mb".to_string(), &self.swap_used),
+            ("gpu_util".to_string(), &self.gpu_util),
+            ("emc_util".to_string(), &self.emc_util),
+            ("power_total_mw".to_string(), &self.power_total),
+            ("lfb_mb".to_string(), &self.lfb_mb),
+            ("vin_mw".to_string(), &self.vin_mw),
+        ];
+        for (idx, series) in self.cpu_cores.iter().enumerate() {
+            columns.push((format!("cpu{}", idx), series));
+        }
+        let mut sensors: Vec<_> = self.temps.iter().collect();
+        sensors.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, series) in sensors {
+            columns.push((format!("temp_{}_c", name), series));
+        }
+
+        let lookups: Vec<HashMap<Instant, u64>> = columns
+            .iter()
+            .map(|(_, series)| series.iter_timed().collect())
+            .collect();
+        let mut times: Vec<Instant> = columns
+            .iter()
+            .flat_map(|(_, series)| series.times.iter().copied())
+            .collect();
+        times.sort();
+        times.dedup();
+
+        let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+        writeln!(writer, "time,{}", names.join(","))?;
+        let (now, wall_now) = now;
+        for at in times {
+            let age = chrono::Duration::from_std(now.saturating_duration_since(at))
+                .unwrap_or_default();
+            let cells: Vec<String> = lookups
+                .iter()
+                .map(|lookup| lookup.get(&at).map(u64::to_string).unwrap_or_default())
+                .collect();
+            writeln!(
+                writer,
+                "{},{}",
+                (wall_now - age).format("%Y-%m-%dT%H:%M:%S%.3f"),
+                cells.join(",")
+            )?;
         }
+        writer.flush()
     }
 
     pub fn reset(&mut self) {
         self.cpu_total.clear();
         self.ram_used.clear();
+        self.swap_used.clear();
         self.gpu_util.clear();
+        self.emc_util.clear();
+        self.power_total.clear();
+        self.lfb_mb.clear();
+        self.vin_mw.clear();
+        self.cpu_cores.clear();
+        self.temps.clear();
+    }
+
+    /// Switches what `power_total` records, dropping the samples of the old total.
+    pub fn set_power_summary(&mut self, summary: PowerSummary) {
+        self.power_summary = summary;
         self.power_total.clear();
     }
 
-    pub fn push(&mut self, snapshot: &StatsSnapshot) {
+    pub fn push_at(&mut self, snapshot: &StatsSnapshot, cpu_aggregate: CpuAggregate, at: Instant) {
         let capacity = self.capacity;
-        if let Some(cpu_total) = snapshot.cpu_total() {
-            Self::push_value(
-                &mut self.cpu_total,
-                capacity,
-                cpu_total.round().clamp(0.0, 100.0) as u64,
-            );
+        if let Some(cpu_total) = snapshot.cpu_total(cpu_aggregate) {
+            self.cpu_total
+                .push(capacity, cpu_total.round().clamp(0.0, 100.0) as u64, at);
+        }
+        if !snapshot.cpu_cores.is_empty() {
+            if self.cpu_cores.len() != snapshot.cpu_cores.len() {
+                self.cpu_cores.resize_with(snapshot.cpu_cores.len(), || {
+                    TimedSeries::with_capacity(capacity)
+                });
+            }
+            for (series, util) in self.cpu_cores.iter_mut().zip(&snapshot.cpu_cores) {
+                series.push(capacity, util.round().clamp(0.0, 100.0) as u64, at);
+            }
         }
         if let Some(used) = snapshot.ram_used_mb {
-            Self::push_value(&mut self.ram_used, capacity, used);
+            self.ram_used.push(capacity, used, at);
+        }
+        if let Some(used) = snapshot.swap_used_mb {
+            self.swap_used.push(capacity, used, at);
         }
         if let Some(gpu_util) = snapshot.gpu_util {
-            Self::push_value(
-                &mut self.gpu_util,
-                capacity,
-                gpu_util.round().clamp(0.0, 100.0) as u64,
-            );
+            self.gpu_util
+                .push(capacity, gpu_util.round().clamp(0.0, 100.0) as u64, at);
         }
-        if let Some(power_total) = snapshot.total_power_mw() {
-            Self::push_value(&mut self.power_total, capacity, power_total);
+        if let Some(emc_util) = snapshot.emc_util {
+            self.emc_util
+                .push(capacity, emc_util.round().clamp(0.0, 100.0) as u64, at);
         }
-    }
-
-    fn push_value(deque: &mut VecDeque<u64>, capacity: usize, value: u64) {
-        if deque.len() >= capacity {
-            deque.pop_front();
+        if let Some(power_total) = snapshot.total_power_mw(&self.power_summary) {
+            self.power_total.push(capacity, power_total, at);
+        }
+        for temp in &snapshot.temps {
+            if !self.temp_range.contains(temp.value_c) {
+                continue;
+            }
+            self.temps
+                .entry(temp.name.clone())
+                .or_insert_with(|| TimedSeries::with_capacity(capacity))
+                .push(capacity, temp.value_c.round().max(0.0) as u64, at);
+        }
+        if let Some(lfb_mb) = snapshot.lfb_mb() {
+            self.lfb_mb.push(capacity, lfb_mb, at);
+        }
+        if let Some(vin_mw) = snapshot.total_power_mw(&PowerSummary::InputRail) {
+            self.vin_mw.push(capacity, vin_mw, at);
         }
-        deque.push_back(value);
     }
 }
 
-#[derive(Debug)]
-pub struct AppState {
-    pub latest: Option<StatsSnapshot>,
-    pub history: History,
-    pub interval_ms: u64,
-    pub show_help: bool,
-    pub error: Option<String>,
-    pub buttons: UiButtons,
-    pub hover: HoverTarget,
-    pub panes: PaneVisibility,
+/// An auto-scale ceiling that eases toward the observed max instead of snapping.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmoothedMax {
+    value: Option<f64>,
 }
 
-impl AppState {
-    pub fn new(interval_ms: u64, history_capacity: usize) -> Self {
-        Self {
-            latest: None,
-            history: History::new(history_capacity),
-            interval_ms,
-            show_help: false,
-            error: None,
-            buttons: UiButtons::default(),
-            hover: HoverTarget::None,
-            panes: PaneVisibility::default(),
-        }
+impl SmoothedMax {
+    /// Moves the ceiling a `rate` fraction of the way to `observed` (1.0 snaps).
+    pub fn update(&mut self, observed: u64, rate: f64) {
+        let observed = observed as f64;
+        let rate = rate.clamp(0.0, 1.0);
+        self.value = Some(match self.value {
+            Some(current) => current + (observed - current) * rate,
+            None => observed,
+        });
+    }
+
+    pub fn get(&self) -> Option<u64> {
+        self.value.map(|value| (value.round() as u64).max(1))
+    }
+
+    pub fn reset(&mut self) {
+        self.value = None;
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
-pub struct UiButton {
-    pub x: u16,
-    pub y: u16,
-    pub width: u16,
+/// Flags when CPU, GPU and power have settled: each series' variance over its
+/// last `window` samples is below `threshold`. Power is measured in percent of
+/// its window mean so one threshold fits all three.
+#[derive(Clone, Copy, Debug)]
+pub struct StabilityDetector {
+    pub window: usize,
+    pub threshold: f64,
+    stable: bool,
 }
 
-impl UiButton {
-    pub fn contains(&self, column: u16, row: u16) -> bool {
-        row == self.y && column >= self.x && column < self.x.saturating_add(self.width)
+impl Default for StabilityDetector {
+    fn default() -> Self {
+        Self::new(10, 4.0)
     }
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct UiButtons {
-    pub minus: Option<UiButton>,
-    pub plus: Option<UiButton>,
-}
+impl StabilityDetector {
+    pub fn new(window: usize, threshold: f64) -> Self {
+        Self {
+            window: window.max(2),
+            threshold,
+            stable: false,
+        }
+    }
+
+    pub fn update(&mut self, history: &History) {
+        let checks = [
+            self.window_variance(&history.cpu_total, false),
+            self.window_variance(&history.gpu_util, false),
+            self.window_variance(&history.power_total, true),
+        ];
+        let mut any = false;
+        self.stable = checks.into_iter().all(|check| match check {
+            WindowCheck::Empty => true,
+            WindowCheck::Filling => false,
+            WindowCheck::Variance(variance) => {
+                any = true;
+                variance < self.threshold
+            }
+        }) && any;
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    pub fn reset(&mut self) {
+        self.stable = false;
+    }
+
+    fn window_variance(&self, series: &TimedSeries, relative: bool) -> WindowCheck {
+        if series.values.is_empty() {
+            return WindowCheck::Empty;
+        }
+        if series.values.len() < self.window {
+            return WindowCheck::Filling;
+        }
+        let mut samples: Vec<f64> = series
+            .values
+            .iter()
+            .skip(series.values.len() - self.window)
+            .map(|value| *value as f64)
+            .collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        if relative && mean > 0.0 {
+            for sample in &mut samples {
+                *sample = *sample / mean * 100.0;
+            }
+        }
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let squared_error: f64 = samples.iter().map(|sample| (sample - mean).powi(2)).sum();
+        let variance = squared_error / samples.len() as f64;
+        WindowCheck::Variance(variance)
+    }
+}
+
+enum WindowCheck {
+    /// The metric has no samples at all (e.g. no nvidia-smi) and is ignored.
+    Empty,
+    /// Fewer than `window` samples so far.
+    Filling,
+    Variance(f64),
+}
+
+/// Mean and standard deviation over the last `window` values of one metric.
+#[derive(Debug, Default)]
+struct RollingStats {
+    values: VecDeque<f64>,
+}
+
+impl RollingStats {
+    /// Samples needed before anything is flagged.
+    const MIN_SAMPLES: usize = 5;
+
+    fn z_score(&self, value: f64) -> Option<f64> {
+        if self.values.len() < Self::MIN_SAMPLES {
+            return None;
+        }
+        let len = self.values.len() as f64;
+        let mean = self.values.iter().sum::<f64>() / len;
+        let variance = self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / len;
+        let deviation = value - mean;
+        match variance.sqrt() {
+            std if std > f64::EPSILON => Some(deviation / std),
+            _ if deviation.abs() > f64::EPSILON => Some(f64::INFINITY.copysign(deviation)),
+            _ => Some(0.0),
+        }
+    }
+
+    fn push(&mut self, window: usize, value: f64) {
+        if self.values.len() >= window {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+}
+
+/// A sample more than `k` standard deviations from its metric's rolling mean.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Anomaly {
+    pub metric: SparkMetric,
+    pub value: f64,
+    pub z_score: f64,
+    pub at: Instant,
+}
+
+/// How many anomalies the detector keeps for the help overlay.
+pub const ANOMALY_LOG_LEN: usize = 20;
+
+/// Flags outlier samples per sparkline metric. Alongside each metric's history
+/// it keeps a 0/1 series of flags pushed in lockstep, so the flags line up with
+/// the sparkline columns.
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    /// Z-score threshold; 0 disables detection.
+    pub k: f64,
+    window: usize,
+    capacity: usize,
+    stats: HashMap<SparkMetric, RollingStats>,
+    flags: HashMap<SparkMetric, TimedSeries>,
+    /// The latest anomalies of every metric, oldest first.
+    log: VecDeque<Anomaly>,
+}
+
+impl AnomalyDetector {
+    pub fn new(k: f64, window: usize, capacity: usize) -> Self {
+        Self {
+            k,
+            window: window.max(RollingStats::MIN_SAMPLES),
+            capacity,
+            stats: HashMap::new(),
+            flags: HashMap::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    /// Scores `value` against the metric's recent samples, then adds it to them.
+    /// A flagged sample is also added to the log.
+    pub fn observe(&mut self, metric: SparkMetric, value: f64, at: Instant) -> Option<Anomaly> {
+        let stats = self.stats.entry(metric).or_default();
+        let z_score = stats.z_score(value).filter(|z| self.k > 0.0 && z.abs() > self.k);
+        stats.push(self.window, value);
+        self.flags
+            .entry(metric)
+            .or_insert_with(|| TimedSeries::with_capacity(self.capacity))
+            .push(self.capacity, u64::from(z_score.is_some()), at);
+        let anomaly = z_score.map(|z_score| Anomaly {
+            metric,
+            value,
+            z_score,
+            at,
+        })?;
+        if self.log.len() >= ANOMALY_LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back(anomaly);
+        Some(anomaly)
+    }
+
+    /// Observes every sparkline metric the snapshot reports, mirroring what
+    /// [`History::push_at`] records.
+    pub fn observe_snapshot(
+        &mut self,
+        snapshot: &StatsSnapshot,
+        cpu_aggregate: CpuAggregate,
+        power_summary: &PowerSummary,
+        at: Instant,
+    ) -> Vec<Anomaly> {
+        let samples = [
+            (SparkMetric::Cpu, snapshot.cpu_total(cpu_aggregate).map(f64::from)),
+            (SparkMetric::Ram, snapshot.ram_used_mb.map(|used| used as f64)),
+            (SparkMetric::Gpu, snapshot.gpu_util.map(f64::from)),
+            (
+                SparkMetric::Power,
+                snapshot.total_power_mw(power_summary).map(|mw| mw as f64),
+            ),
+        ];
+        samples
+            .into_iter()
+            .filter_map(|(metric, value)| self.observe(metric, value?, at))
+            .collect()
+    }
+
+    /// Drops a metric's rolling stats and flags, e.g. when what it measures changes.
+    pub fn forget(&mut self, metric: SparkMetric) {
+        self.stats.remove(&metric);
+        self.flags.remove(&metric);
+    }
+
+    /// Keeps the flag series the same length as the history they overlay.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        for flags in self.flags.values_mut() {
+            flags.truncate_front(capacity);
+        }
+    }
+
+    pub fn flags(&self, metric: SparkMetric) -> Option<&TimedSeries> {
+        self.flags.get(&metric)
+    }
+
+    /// Up to [`ANOMALY_LOG_LEN`] recent anomalies, oldest first.
+    pub fn log(&self) -> &VecDeque<Anomaly> {
+        &self.log
+    }
+
+    pub fn reset(&mut self) {
+        self.stats.clear();
+        self.flags.clear();
+        self.log.clear();
+    }
+}
+
+#[derive(Debug)]
+pub struct AppState {
+    pub latest: Option<StatsSnapshot>,
+    pub history: History,
+    pub interval_ms: u64,
+    pub show_help: bool,
+    /// First help line shown when the overlay is taller than the terminal.
+    pub help_scroll: usize,
+    pub error: Option<String>,
+    /// When `error` last changed; shown next to it in the footer.
+    pub error_at: Option<DateTime<Local>>,
+    /// `--error-timeout`: errors older than this drop out of the footer.
+    pub error_timeout: Option<Duration>,
+    pub notice: Option<String>,
+    pub buttons: UiButtons,
+    pub hover: HoverTarget,
+    pub panes: PaneVisibility,
+    pub ram_detail: bool,
+    pub tegrastats_path: String,
+    pub nvidia_smi_path: String,
+    /// `--tegrastats-arg` / `--nvidia-smi-arg`, kept for restarts.
+    pub tegrastats_args: Vec<String>,
+    pub nvidia_smi_args: Vec<String>,
+    pub interval_style: IntervalStyle,
+    pub sources_editor: Option<SourcesEditor>,
+    /// Digits typed after `i`, shown in place of the interval until Enter or Esc.
+    pub interval_input: Option<String>,
+    pub temp_sort: TempSort,
+    /// Applied Temps filter, see [`temp_filter_keeps`]; empty shows all.
+    pub temp_filter: String,
+    /// Text typed into the Temps filter prompt (`/`) while it is open.
+    pub temp_filter_input: Option<String>,
+    /// Panel zoomed to fill the body with `z`; it follows the selection.
+    pub focused_pane: Option<Pane>,
+    /// Panel picked with Tab/Shift-Tab or a click; scrolling and `z` act on it.
+    pub selected_pane: Option<Pane>,
+    pub any_crit_breached: bool,
+    pub spark_base: Rgb,
+    pub cpu_aggregate: CpuAggregate,
+    pub color_depth: ColorDepth,
+    pub color_scheme: ColorScheme,
+    pub sensor_aliases: HashMap<String, String>,
+    /// `--rail-color` overrides of the hashed per-rail colors.
+    pub rail_colors: HashMap<String, Rgb>,
+    /// Celsius per sensor name past which the board is probably throttling.
+    pub throttle_thresholds: HashMap<String, f32>,
+    /// Advanced every draw tick; alarms alternate their style on it.
+    pub blink_phase: u64,
+    pub capture_pending: bool,
+    pub toast: Option<(String, Instant)>,
+    /// When the last snapshot was recorded, and the recent gaps between them.
+    pub last_sample_at: Option<Instant>,
+    pub sample_gaps: VecDeque<Duration>,
+    pub align_history: bool,
+    pub cpu_heatmap: bool,
+    pub power_max: SmoothedMax,
+    /// `--power-max-mw`: a fixed ceiling for the power bar and sparkline
+    /// instead of following the observed max.
+    pub power_max_fixed: Option<u64>,
+    /// `--battery-wh`: battery capacity used to estimate time-to-empty from
+    /// the VIN rolling average.
+    pub battery_wh: Option<f64>,
+    pub scale_smoothing: f64,
+    pub started_at: Instant,
+    pub cpu_bars_per_row: usize,
+    pub nvidia_smi_timeout: Duration,
+    pub gpu_unresponsive: bool,
+    pub table_view: bool,
+    pub table_scroll: usize,
+    /// First shown line of the Temps and Power lists when they overflow;
+    /// each draw stores them back clamped to the current content.
+    pub temps_scroll: usize,
+    pub power_scroll: usize,
+    pub threshold_sparks: Vec<SparkMetric>,
+    pub temp_range: TempRange,
+    /// Heat scale for temperatures in Celsius, and for utilization percents.
+    pub temp_heat: HeatBounds,
+    pub percent_heat: HeatBounds,
+    pub stability: StabilityDetector,
+    pub ram_free: bool,
+    pub show_lfb: bool,
+    pub temp_unit: TempUnit,
+    pub power_unit: PowerUnit,
+    pub power_scale: SparkScale,
+    /// Moving-average window for graphs, one of [`SMOOTHING_WINDOWS`].
+    pub smoothing: usize,
+    /// The CPU and GPU footers show p50/p95/p99 instead of min/avg/max.
+    pub show_percentiles: bool,
+    /// Draw sparklines and borders with plain ASCII instead of block glyphs.
+    pub ascii: bool,
+    /// `--braille`: two samples per sparkline cell; ignored with `ascii`.
+    pub braille: bool,
+    /// When space froze the display; snapshots arriving meanwhile wait in
+    /// `paused_backlog` and are recorded on resume.
+    pub paused_at: Option<Instant>,
+    pub paused_backlog: VecDeque<(StatsSnapshot, Instant)>,
+    /// How many samples back from the newest the paused view ends, moved with
+    /// Left/Right.
+    pub scroll_offset: usize,
+    pub anomalies: AnomalyDetector,
+    pub show_cpu_freq: bool,
+    /// Set with `--replay`; source restarts reopen this log instead of tegrastats.
+    pub replay_path: Option<String>,
+}
+
+impl AppState {
+    pub fn new(
+        tegrastats_path: String,
+        nvidia_smi_path: String,
+        interval_ms: u64,
+        history_capacity: usize,
+    ) -> Self {
+        Self {
+            latest: None,
+            history: History::new(history_capacity),
+            interval_ms,
+            show_help: false,
+            help_scroll: 0,
+            error: None,
+            error_at: None,
+            error_timeout: None,
+            notice: None,
+            buttons: UiButtons::default(),
+            hover: HoverTarget::None,
+            panes: PaneVisibility::default(),
+            ram_detail: false,
+            tegrastats_path,
+            nvidia_smi_path,
+            tegrastats_args: Vec::new(),
+            nvidia_smi_args: Vec::new(),
+            interval_style: IntervalStyle::Flag,
+            sources_editor: None,
+            interval_input: None,
+            temp_sort: TempSort::default(),
+            temp_filter: String::new(),
+            temp_filter_input: None,
+            focused_pane: None,
+            selected_pane: None,
+            any_crit_breached: false,
+            spark_base: Rgb::WHITE,
+            cpu_aggregate: CpuAggregate::Mean,
+            color_depth: ColorDepth::TrueColor,
+            color_scheme: ColorScheme::Default,
+            sensor_aliases: HashMap::new(),
+            rail_colors: HashMap::new(),
+            throttle_thresholds: HashMap::from([("tj".to_string(), TEMP_CRIT_C)]),
+            blink_phase: 0,
+            capture_pending: false,
+            toast: None,
+            last_sample_at: None,
+            sample_gaps: VecDeque::new(),
+            align_history: false,
+            cpu_heatmap: false,
+            power_max: SmoothedMax::default(),
+            power_max_fixed: None,
+            battery_wh: None,
+            scale_smoothing: 0.2,
+            started_at: Instant::now(),
+            cpu_bars_per_row: 1,
+            nvidia_smi_timeout: Duration::from_secs(2),
+            gpu_unresponsive: false,
+            table_view: false,
+            table_scroll: 0,
+            temps_scroll: 0,
+            power_scroll: 0,
+            threshold_sparks: Vec::new(),
+            temp_range: TempRange::default(),
+            temp_heat: HeatBounds::TEMP,
+            percent_heat: HeatBounds::PERCENT,
+            stability: StabilityDetector::default(),
+            ram_free: false,
+            show_lfb: false,
+            temp_unit: TempUnit::default(),
+            power_unit: PowerUnit::default(),
+            power_scale: SparkScale::default(),
+            smoothing: 1,
+            show_percentiles: false,
+            ascii: false,
+            braille: false,
+            paused_at: None,
+            paused_backlog: VecDeque::new(),
+            scroll_offset: 0,
+            anomalies: AnomalyDetector::new(4.0, 30, history_capacity),
+            show_cpu_freq: false,
+            replay_path: None,
+        }
+    }
+
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history.set_capacity(capacity);
+        self.anomalies.set_capacity(self.history.capacity());
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// When the sample under the scrollback cursor was recorded. Only set
+    /// while paused; CPU history is the reference since every line has it.
+    pub fn cursor_time(&self) -> Option<Instant> {
+        self.paused_at?;
+        self.history
+            .cpu_total
+            .times
+            .iter()
+            .rev()
+            .nth(self.scroll_offset)
+            .copied()
+    }
+
+    /// Moves the scrollback cursor `delta` samples back in time (negative
+    /// moves forward), stopping at the oldest retained sample and the newest.
+    pub fn scroll_history(&mut self, delta: isize) {
+        let oldest = self.history.cpu_total.values.len().saturating_sub(1);
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(oldest);
+    }
+
+    /// Holds a snapshot that arrived while paused. Only the newest history's
+    /// worth is kept, since older ones would scroll out on resume anyway.
+    pub fn queue_paused(&mut self, snapshot: StatsSnapshot, at: Instant) {
+        if self.paused_backlog.len() >= self.history.capacity() {
+            self.paused_backlog.pop_front();
+        }
+        self.paused_backlog.push_back((snapshot, at));
+    }
+
+    pub fn note_sample(&mut self, at: Instant) {
+        if let Some(last) = self.last_sample_at {
+            if self.sample_gaps.len() >= SAMPLE_GAP_WINDOW {
+                self.sample_gaps.pop_front();
+            }
+            self.sample_gaps.push_back(at.saturating_duration_since(last));
+        }
+        self.last_sample_at = Some(at);
+    }
+
+    /// Forgets the measured gaps, e.g. after the sources restart at a new interval.
+    pub fn reset_sample_rate(&mut self) {
+        self.last_sample_at = None;
+        self.sample_gaps.clear();
+    }
+
+    /// Mean gap between recently recorded snapshots.
+    pub fn effective_interval(&self) -> Option<Duration> {
+        let count = u32::try_from(self.sample_gaps.len()).ok().filter(|count| *count > 0)?;
+        Some(self.sample_gaps.iter().sum::<Duration>() / count)
+    }
+
+    /// Whether the effective interval is more than 50% off the configured one.
+    pub fn sample_rate_lagging(&self) -> bool {
+        self.effective_interval().is_some_and(|effective| {
+            let configured = self.interval_ms as f64;
+            (effective.as_millis() as f64 - configured).abs() > configured * 0.5
+        })
+    }
+
+    /// Zooms the selected panel (the first visible one if none is), or
+    /// un-zooms.
+    pub fn toggle_zoom(&mut self) {
+        self.focused_pane = match self.focused_pane {
+            Some(_) => None,
+            None => self
+                .selected_pane
+                .filter(|pane| self.panes.shows(*pane))
+                .or_else(|| Pane::ALL.into_iter().find(|pane| self.panes.shows(*pane))),
+        };
+        if self.focused_pane.is_some() {
+            self.selected_pane = self.focused_pane;
+        }
+    }
+
+    /// Selects the next (or previous) visible panel, wrapping around; with
+    /// nothing selected yet it starts at the first (or last). A zoom moves
+    /// along with the selection.
+    pub fn select_next(&mut self, forward: bool) {
+        let len = Pane::ALL.len();
+        let start = match self.selected_pane {
+            Some(current) => Pane::ALL.iter().position(|pane| *pane == current).unwrap_or(0),
+            None if forward => len - 1,
+            None => 0,
+        };
+        self.selected_pane = (1..=len)
+            .map(|step| if forward { start + step } else { start + len * 2 - step })
+            .map(|index| Pane::ALL[index % len])
+            .find(|pane| self.panes.shows(*pane));
+        if self.focused_pane.is_some() {
+            self.focused_pane = self.selected_pane;
+        }
+    }
+
+    /// Replaces the current error, restamping it only when the message changes.
+    pub fn set_error(&mut self, error: Option<String>) {
+        if error != self.error {
+            self.error_at = error.as_ref().map(|_| Local::now());
+        }
+        self.error = error;
+    }
+
+    /// The current error and when it was raised, unless it outlived `error_timeout`.
+    pub fn visible_error(&self) -> Option<(&str, DateTime<Local>)> {
+        let at = self.error_at?;
+        let expired = self.error_timeout.is_some_and(|timeout| {
+            (Local::now() - at).to_std().is_ok_and(|age| age >= timeout)
+        });
+        self.error
+            .as_deref()
+            .filter(|_| !expired)
+            .map(|error| (error, at))
+    }
+
+    pub fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+
+    /// The toast message, if it was raised within the last few seconds.
+    pub fn active_toast(&self) -> Option<&str> {
+        self.toast
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Whether a plausible reading from a sensor with a throttle threshold
+    /// reached it. Sensor names match case-insensitively.
+    pub fn throttle_suspected(&self) -> bool {
+        let Some(snapshot) = self.latest.as_ref() else {
+            return false;
+        };
+        snapshot.temps.iter().any(|temp| {
+            self.temp_range.contains(temp.value_c)
+                && self.throttle_thresholds.iter().any(|(name, limit)| {
+                    name.eq_ignore_ascii_case(&temp.name) && temp.value_c >= *limit
+                })
+        })
+    }
+
+    /// The scroll offset of the zoomed (or else selected) pane, if it is one
+    /// with a list.
+    pub fn focused_scroll(&mut self) -> Option<&mut usize> {
+        match self.focused_pane.or(self.selected_pane)? {
+            Pane::Temps => Some(&mut self.temps_scroll),
+            Pane::Power => Some(&mut self.power_scroll),
+            _ => None,
+        }
+    }
+
+    /// Moves the zoomed or selected pane's list by `delta` lines; the next
+    /// draw clamps it.
+    pub fn scroll_focused(&mut self, delta: isize) {
+        if let Some(scroll) = self.focused_scroll() {
+            *scroll = scroll.saturating_add_signed(delta);
+        }
+    }
+
+    /// Toggles the power graph between linear and log. It is the only graph
+    /// with a log scale, so with another pane zoomed or selected a toast
+    /// says so instead.
+    pub fn toggle_power_scale(&mut self) {
+        match self.focused_pane.or(self.selected_pane) {
+            None | Some(Pane::Power) => self.power_scale = self.power_scale.toggle(),
+            Some(_) => self.show_toast("only the Power graph has a log scale".to_string()),
+        }
+    }
+
+    /// Steps to the next of [`SMOOTHING_WINDOWS`], wrapping back to off.
+    pub fn cycle_smoothing(&mut self) {
+        let index = SMOOTHING_WINDOWS.iter().position(|window| *window == self.smoothing);
+        let next = index.map_or(0, |index| (index + 1) % SMOOTHING_WINDOWS.len());
+        self.smoothing = SMOOTHING_WINDOWS[next];
+    }
+
+    /// The "on" half of a blinking alarm.
+    pub fn blink_on(&self) -> bool {
+        self.blink_phase.is_multiple_of(2)
+    }
+
+    /// Friendly label for a temp sensor or power rail, falling back to the raw name.
+    pub fn sensor_label<'a>(&'a self, name: &'a str) -> &'a str {
+        self.sensor_aliases
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(name)
+    }
+}
+
+/// Parses a `raw=label` sensor alias.
+pub fn parse_sensor_alias(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((raw, label)) if !raw.trim().is_empty() && !label.trim().is_empty() => {
+            Ok((raw.trim().to_string(), label.trim().to_string()))
+        }
+        _ => Err(format!("invalid sensor alias `{}`: expected name=label", value)),
+    }
+}
+
+/// Parses a `rail=#RRGGBB` power rail color.
+pub fn parse_rail_color(value: &str) -> Result<(String, Rgb), String> {
+    let invalid = || format!("invalid rail color `{}`: expected name=#RRGGBB", value);
+    let (name, color) = value.split_once('=').ok_or_else(invalid)?;
+    if name.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok((name.trim().to_string(), parse_rgb(color.trim())?))
+}
+
+/// Parses a `sensor=celsius` throttle threshold.
+pub fn parse_throttle_threshold(value: &str) -> Result<(String, f32), String> {
+    let invalid = || format!("invalid throttle threshold `{}`: expected name=celsius", value);
+    let (name, limit) = value.split_once('=').ok_or_else(invalid)?;
+    let limit: f32 = limit.trim().parse().map_err(|_| invalid())?;
+    if name.trim().is_empty() || !limit.is_finite() {
+        return Err(invalid());
+    }
+    Ok((name.trim().to_string(), limit))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceField {
+    Tegrastats,
+    NvidiaSmi,
+}
+
+#[derive(Clone, Debug)]
+pub struct SourcesEditor {
+    pub field: SourceField,
+    pub tegrastats: String,
+    pub nvidia_smi: String,
+}
+
+impl SourcesEditor {
+    pub fn new(tegrastats: &str, nvidia_smi: &str) -> Self {
+        Self {
+            field: SourceField::Tegrastats,
+            tegrastats: tegrastats.to_string(),
+            nvidia_smi: nvidia_smi.to_string(),
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = match self.field {
+            SourceField::Tegrastats => SourceField::NvidiaSmi,
+            SourceField::NvidiaSmi => SourceField::Tegrastats,
+        };
+    }
+
+    pub fn active_mut(&mut self) -> &mut String {
+        match self.field {
+            SourceField::Tegrastats => &mut self.tegrastats,
+            SourceField::NvidiaSmi => &mut self.nvidia_smi,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const WHITE: Self = Self {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+    pub const NEAR_BLACK: Self = Self {
+        r: 40,
+        g: 40,
+        b: 40,
+    };
+}
+
+/// Parses `#RRGGBB`, `RRGGBB`, or `r,g,b` into an [`Rgb`].
+pub fn parse_rgb(value: &str) -> Result<Rgb, String> {
+    let value = value.trim();
+    if value.contains(',') {
+        let parts: Vec<u8> = value
+            .split(',')
+            .map(|part| part.trim().parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|err| format!("invalid color `{}`: {}", value, err))?;
+        return match parts[..] {
+            [r, g, b] => Ok(Rgb { r, g, b }),
+            _ => Err(format!("invalid color `{}`: expected r,g,b", value)),
+        };
+    }
+
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("invalid color `{}`: expected #RRGGBB", value));
+    }
+    let channel = |range: std::ops::Range<usize>| {
+        u8::from_str_radix(&hex[range], 16)
+            .map_err(|err| format!("invalid color `{}`: {}", value, err))
+    };
+    Ok(Rgb {
+        r: channel(0..2)?,
+        g: channel(2..4)?,
+        b: channel(4..6)?,
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UiButton {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+}
+
+impl UiButton {
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        row == self.y && column >= self.x && column < self.x.saturating_add(self.width)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UiButtons {
+    pub minus: Option<UiButton>,
+    pub plus: Option<UiButton>,
+    /// Where each panel was drawn, so a click can select it.
+    pub panes: Vec<(Pane, UiArea)>,
+}
+
+impl UiButtons {
+    /// The panel drawn under a click, if any.
+    pub fn pane_at(&self, column: u16, row: u16) -> Option<Pane> {
+        self.panes
+            .iter()
+            .find(|(_, area)| area.contains(column, row))
+            .map(|(pane, _)| *pane)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UiArea {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl UiArea {
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        column >= self.x
+            && column < self.x.saturating_add(self.width)
+            && row >= self.y
+            && row < self.y.saturating_add(self.height)
+    }
+}
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum HoverTarget {
@@ -181,6 +1723,29 @@ pub enum HoverTarget {
     Plus,
 }
 
+/// One of the dashboard panels that `1`-`6` toggle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Pane {
+    Cpu,
+    Ram,
+    Swap,
+    Gpu,
+    Temps,
+    Power,
+}
+
+impl Pane {
+    /// Dashboard order: the left column, then the right one.
+    pub const ALL: [Pane; 6] = [
+        Pane::Cpu,
+        Pane::Ram,
+        Pane::Swap,
+        Pane::Gpu,
+        Pane::Temps,
+        Pane::Power,
+    ];
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct PaneVisibility {
     pub cpu: bool,
@@ -188,6 +1753,20 @@ pub struct PaneVisibility {
     pub gpu: bool,
     pub temps: bool,
     pub power: bool,
+    pub swap: bool,
+}
+
+impl PaneVisibility {
+    pub fn shows(&self, pane: Pane) -> bool {
+        match pane {
+            Pane::Cpu => self.cpu,
+            Pane::Ram => self.ram,
+            Pane::Swap => self.swap,
+            Pane::Gpu => self.gpu,
+            Pane::Temps => self.temps,
+            Pane::Power => self.power,
+        }
+    }
 }
 
 impl Default for PaneVisibility {
@@ -198,6 +1777,653 @@ impl Default for PaneVisibility {
             gpu: true,
             temps: true,
             power: true,
+            swap: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    use chrono::{Local, TimeZone};
+
+    use super::{
+        moving_average, parse_rail_color, parse_sensor_alias, parse_throttle_threshold, percentile,
+        AnomalyDetector, AppState, ColorDepth, CpuAggregate, GpuStats, History, Pane, PowerRail,
+        PowerSummary, PowerUnit, Rgb, SmoothedMax, SparkMetric, SparkScale, StabilityDetector,
+        StatsSnapshot, TempReading, TempUnit, Theme, ANOMALY_LOG_LEN,
+    };
+
+    fn mixed_cores() -> StatsSnapshot {
+        StatsSnapshot {
+            cpu_cores: vec![0.0, 20.0, 90.0, 0.0, 40.0],
+            ..StatsSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn cpu_total_mean() {
+        assert_eq!(mixed_cores().cpu_total(CpuAggregate::Mean), Some(30.0));
+    }
+
+    #[test]
+    fn cpu_total_max() {
+        assert_eq!(mixed_cores().cpu_total(CpuAggregate::Max), Some(90.0));
+    }
+
+    #[test]
+    fn cpu_total_active_mean() {
+        assert_eq!(mixed_cores().cpu_total(CpuAggregate::ActiveMean), Some(50.0));
+        let idle = StatsSnapshot {
+            cpu_cores: vec![0.0, 0.0],
+            ..StatsSnapshot::default()
+        };
+        assert_eq!(idle.cpu_total(CpuAggregate::ActiveMean), Some(0.0));
+        assert_eq!(StatsSnapshot::default().cpu_total(CpuAggregate::Max), None);
+    }
+
+    #[test]
+    fn cpu_total_skips_offline_cores() {
+        let parked = StatsSnapshot {
+            cpu_cores: vec![40.0, 0.0, 20.0, 0.0],
+            cpu_online: vec![true, false, true, false],
+            ..StatsSnapshot::default()
+        };
+        assert_eq!(parked.cpu_total(CpuAggregate::Mean), Some(30.0));
+        assert!(!parked.core_online(1));
+        assert!(parked.core_online(7));
+    }
+
+    #[test]
+    fn detects_color_depth() {
+        assert_eq!(ColorDepth::detect("truecolor", "xterm"), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::detect("", "xterm-256color"), ColorDepth::Ansi256);
+        assert_eq!(ColorDepth::detect("", "vt100"), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn detects_theme_from_colorfgbg() {
+        assert_eq!(Theme::detect("0;15"), Theme::Light);
+        assert_eq!(Theme::detect("0;default;7"), Theme::Light);
+        assert_eq!(Theme::detect("15;0"), Theme::Dark);
+        assert_eq!(Theme::detect(""), Theme::Dark);
+        assert_eq!(Theme::Light.resolve().spark_base(), Rgb::NEAR_BLACK);
+        assert_eq!(Theme::Dark.spark_base(), Rgb::WHITE);
+    }
+
+    #[test]
+    fn suspects_throttling_past_a_sensor_threshold() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let reading = |name: &str, value_c| TempReading {
+            name: name.to_string(),
+            value_c,
+        };
+        app.latest = Some(StatsSnapshot {
+            temps: vec![reading("cpu", 95.0), reading("TJ", 84.0)],
+            ..StatsSnapshot::default()
+        });
+        assert!(!app.throttle_suspected());
+
+        app.throttle_thresholds.insert("cpu".to_string(), 90.0);
+        assert!(app.throttle_suspected());
+        assert_eq!(parse_throttle_threshold(" gpu = 80.5"), Ok(("gpu".to_string(), 80.5)));
+        assert!(parse_throttle_threshold("gpu").is_err());
+    }
+
+    #[test]
+    fn log_scale_applies_only_to_the_power_pane() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        app.selected_pane = Some(Pane::Cpu);
+        app.toggle_power_scale();
+        assert_eq!(app.power_scale, SparkScale::Linear);
+        assert!(app.active_toast().is_some());
+
+        app.selected_pane = Some(Pane::Power);
+        app.toggle_power_scale();
+        assert_eq!(app.power_scale, SparkScale::Log);
+        app.selected_pane = None;
+        app.toggle_power_scale();
+        assert_eq!(app.power_scale, SparkScale::Linear);
+    }
+
+    #[test]
+    fn errors_keep_their_first_timestamp_and_expire() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        app.set_error(Some("tegrastats exited".to_string()));
+        let (_, raised_at) = app.visible_error().expect("error shown");
+        app.set_error(Some("tegrastats exited".to_string()));
+        assert_eq!(app.visible_error().map(|(_, at)| at), Some(raised_at));
+
+        app.error_timeout = Some(Duration::ZERO);
+        assert!(app.visible_error().is_none());
+        app.set_error(None);
+        assert!(app.error_at.is_none());
+    }
+
+    #[test]
+    fn applies_sensor_aliases() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        for alias in ["soc012=SOC0-2", "tj=Junction", "missing=Nope"] {
+            let (raw, label) = parse_sensor_alias(alias).unwrap();
+            app.sensor_aliases.insert(raw, label);
+        }
+        assert_eq!(app.sensor_label("tj"), "Junction");
+        assert_eq!(app.sensor_label("soc012"), "SOC0-2");
+        assert_eq!(app.sensor_label("cpu"), "cpu");
+        assert!(parse_sensor_alias("tj").is_err());
+        assert_eq!(
+            parse_rail_color("VDD_GPU=#ff8000"),
+            Ok(("VDD_GPU".to_string(), Rgb { r: 255, g: 128, b: 0 }))
+        );
+        assert!(parse_rail_color("=#ff8000").is_err());
+        assert!(parse_rail_color("VDD_GPU=orange").is_err());
+    }
+
+    #[test]
+    fn per_core_history_follows_core_count() {
+        let mut history = History::new(3);
+        let cores = |cpu_cores: Vec<f32>| StatsSnapshot {
+            cpu_cores,
+            ..StatsSnapshot::default()
+        };
+        let at = Instant::now();
+
+        history.push_at(&cores(vec![10.0, 20.0]), CpuAggregate::Mean, at);
+        history.push_at(&cores(vec![30.0, 40.0, 50.0, 60.0]), CpuAggregate::Mean, at);
+        assert_eq!(history.cpu_cores.len(), 4);
+        assert_eq!(history.cpu_cores[1].values, [20, 40]);
+        assert_eq!(history.cpu_cores[3].values, [60]);
+
+        history.push_at(&cores(vec![70.0]), CpuAggregate::Mean, at);
+        assert_eq!(history.cpu_cores.len(), 1);
+        assert_eq!(history.cpu_cores[0].values, [10, 30, 70]);
+    }
+
+    #[test]
+    fn push_at_records_the_given_sample_time() {
+        let mut history = History::new(3);
+        let start = Instant::now();
+        let snapshot = StatsSnapshot {
+            cpu_cores: vec![50.0],
+            temps: vec![TempReading {
+                name: "tj".to_string(),
+                value_c: 45.0,
+            }],
+            ..StatsSnapshot::default()
+        };
+
+        history.push_at(&snapshot, CpuAggregate::Mean, start);
+        history.push_at(&snapshot, CpuAggregate::Mean, start + Duration::from_secs(2));
+        let times = [start, start + Duration::from_secs(2)];
+        assert_eq!(history.cpu_total.times, times);
+        assert_eq!(history.cpu_cores[0].times, times);
+        assert_eq!(history.temps["tj"].times, times);
+    }
+
+    #[test]
+    fn smoothed_max_lags_step_increase() {
+        let mut max = SmoothedMax::default();
+        max.update(1000, 0.25);
+        assert_eq!(max.get(), Some(1000));
+
+        max.update(5000, 0.25);
+        assert_eq!(max.get(), Some(2000));
+        max.update(5000, 0.25);
+        assert_eq!(max.get(), Some(2750));
+        for _ in 0..40 {
+            max.update(5000, 0.25);
+        }
+        assert_eq!(max.get(), Some(5000));
+
+        max.update(100, 1.0);
+        assert_eq!(max.get(), Some(100));
+    }
+
+    #[test]
+    fn stability_waits_for_noise_to_settle() {
+        let mut history = History::new(60);
+        let mut detector = StabilityDetector::new(5, 4.0);
+        let start = Instant::now();
+        let noisy = [10.0, 80.0, 25.0, 95.0, 5.0, 70.0];
+        let flat = [50.0, 51.0, 49.0, 50.0, 50.0];
+
+        for (idx, util) in noisy.iter().chain(&flat).enumerate() {
+            let snapshot = StatsSnapshot {
+                cpu_cores: vec![*util],
+                ..StatsSnapshot::default()
+            };
+            history.push_at(&snapshot, CpuAggregate::Mean, start);
+            detector.update(&history);
+            let settled = idx + 1 >= noisy.len() + flat.len();
+            assert_eq!(detector.is_stable(), settled, "sample {}", idx);
+        }
+    }
+
+    #[test]
+    fn history_tracks_lfb_only_when_reported() {
+        let mut history = History::new(4);
+        let start = Instant::now();
+        let with_lfb = StatsSnapshot {
+            lfb_blocks: Some(79),
+            lfb_block_mb: Some(4),
+            ..StatsSnapshot::default()
+        };
+
+        history.push_at(&StatsSnapshot::default(), CpuAggregate::Mean, start);
+        assert!(history.lfb_mb.values.is_empty());
+
+        history.push_at(&with_lfb, CpuAggregate::Mean, start);
+        assert_eq!(history.lfb_mb.values, [316]);
+
+        history.reset();
+        assert!(history.lfb_mb.values.is_empty());
+    }
+
+    #[test]
+    fn history_clamps_emc_util() {
+        let mut history = History::new(4);
+        let start = Instant::now();
+        for emc in [12.4, 140.0] {
+            let snapshot = StatsSnapshot {
+                emc_util: Some(emc),
+                ..StatsSnapshot::default()
+            };
+            history.push_at(&snapshot, CpuAggregate::Mean, start);
+        }
+        assert_eq!(history.emc_util.values, [12, 100]);
+
+        history.reset();
+        assert!(history.emc_util.values.is_empty());
+    }
+
+    #[test]
+    fn history_tracks_temps_per_sensor() {
+        let mut history = History::new(2);
+        let start = Instant::now();
+        let reading = |name: &str, value_c| TempReading {
+            name: name.to_string(),
+            value_c,
+        };
+        let samples = [
+            vec![reading("CPU", 41.6), reading("GPU", 39.0)],
+            vec![reading("CPU", 43.0)],
+            vec![reading("CPU", 44.2), reading("SOC", -3.0)],
+        ];
+        for temps in samples {
+            let snapshot = StatsSnapshot {
+                temps,
+                ..StatsSnapshot::default()
+            };
+            history.push_at(&snapshot, CpuAggregate::Mean, start);
+        }
+
+        assert_eq!(history.temps["CPU"].values, [43, 44]);
+        assert_eq!(history.temps["GPU"].values, [39]);
+        assert_eq!(history.temps["SOC"].values, [0]);
+    }
+
+    #[test]
+    fn history_leaves_out_implausible_temps() {
+        let mut history = History::new(4);
+        let start = Instant::now();
+        for (secs, value_c) in [45.0, -256.0, 511.0, 46.4].into_iter().enumerate() {
+            let snapshot = StatsSnapshot {
+                temps: vec![TempReading {
+                    name: "tj".to_string(),
+                    value_c,
+                }],
+                ..StatsSnapshot::default()
+            };
+            let at = start + Duration::from_secs(secs as u64);
+            history.push_at(&snapshot, CpuAggregate::Mean, at);
+        }
+
+        assert_eq!(history.temps["tj"].values, [45, 46]);
+        let mut csv = Vec::new();
+        history.to_csv(&mut csv, (start, Local::now())).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let temps: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .filter_map(|row| row.rsplit(',').next())
+            .collect();
+        assert_eq!(temps, ["45", "46"]);
+    }
+
+    #[test]
+    fn csv_export_reports_errors_from_the_final_flush() {
+        /// Fails every write; behind a `BufWriter`, only the flush hits it.
+        struct FullDisk;
+
+        impl std::io::Write for FullDisk {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other(format!("{} bytes lost", buf.len())))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let history = History::new(4);
+        let writer = std::io::BufWriter::new(FullDisk);
+        assert!(history.to_csv(writer, (Instant::now(), Local::now())).is_err());
+    }
+
+    #[test]
+    fn history_capacity_change_keeps_newest_samples() {
+        let mut history = History::new(20);
+        let start = Instant::now();
+        for used in 0..20 {
+            let snapshot = StatsSnapshot {
+                ram_used_mb: Some(used),
+                ..StatsSnapshot::default()
+            };
+            history.push_at(&snapshot, CpuAggregate::Mean, start);
+        }
+
+        history.set_capacity(12);
+        assert_eq!(history.ram_used.values, (8..20).collect::<Vec<_>>());
+
+        history.set_capacity(1);
+        assert_eq!(history.capacity(), super::MIN_HISTORY_CAPACITY);
+        assert_eq!(history.ram_used.values.len(), super::MIN_HISTORY_CAPACITY);
+
+        history.set_capacity(usize::MAX);
+        assert_eq!(history.capacity(), super::MAX_HISTORY_CAPACITY);
+        assert_eq!(history.ram_used.values.len(), super::MIN_HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn temp_unit_converts_only_for_display() {
+        assert_eq!(TempUnit::Celsius.format(41.5), "41.5C");
+        assert_eq!(TempUnit::Fahrenheit.format(41.0), "105.8F");
+        assert_eq!(TempUnit::Fahrenheit.format(-40.0), "-40.0F");
+        assert_eq!(TempUnit::Celsius.toggle(), TempUnit::Fahrenheit);
+    }
+
+    #[test]
+    fn power_unit_formats_watts_with_one_decimal() {
+        assert_eq!(PowerUnit::Milliwatts.format(16802), "16802mW");
+        assert_eq!(PowerUnit::Watts.format(16802), "16.8W");
+        assert_eq!(PowerUnit::Watts.format(0), "0.0W");
+    }
+
+    #[test]
+    fn power_summary_picks_sum_vin_or_named_rail() {
+        let rail = |name: &str, current_mw| PowerRail {
+            name: name.to_string(),
+            current_mw,
+            average_mw: current_mw,
+        };
+        let snapshot = StatsSnapshot {
+            power_rails: vec![rail("VDD_GPU", 1000), rail("VDD_CPU", 2000), rail("VIN", 5000)],
+            ..StatsSnapshot::default()
+        };
+        let gpu = PowerSummary::NamedRail("VDD_GPU".to_string());
+        assert_eq!(snapshot.total_power_mw(&PowerSummary::SumComponents), Some(3000));
+        assert_eq!(snapshot.total_power_mw(&PowerSummary::InputRail), Some(5000));
+        assert_eq!(snapshot.total_power_mw(&gpu), Some(1000));
+        assert_eq!(snapshot.total_power_mw(&PowerSummary::NamedRail("X".into())), None);
+
+        let cycled = PowerSummary::SumComponents.next(&snapshot.power_rails);
+        assert_eq!(cycled, PowerSummary::InputRail);
+        assert_eq!(cycled.next(&snapshot.power_rails), gpu);
+        let cpu = gpu.next(&snapshot.power_rails);
+        assert_eq!(cpu, PowerSummary::NamedRail("VDD_CPU".to_string()));
+        assert_eq!(cpu.next(&snapshot.power_rails), PowerSummary::SumComponents);
+
+        assert_eq!("vin".parse(), Ok(PowerSummary::InputRail));
+        assert_eq!("rail:VDD_GPU".parse(), Ok(gpu));
+        assert!("rail:".parse::<PowerSummary>().is_err());
+    }
+
+    #[test]
+    fn effective_interval_averages_recent_gaps() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let start = Instant::now();
+        app.note_sample(start);
+        assert_eq!(app.effective_interval(), None);
+
+        app.note_sample(start + Duration::from_millis(900));
+        app.note_sample(start + Duration::from_millis(2000));
+        assert_eq!(app.effective_interval(), Some(Duration::from_millis(1000)));
+        assert!(!app.sample_rate_lagging());
+
+        app.note_sample(start + Duration::from_millis(8000));
+        assert!(app.sample_rate_lagging());
+
+        app.reset_sample_rate();
+        assert_eq!(app.effective_interval(), None);
+    }
+
+    #[test]
+    fn zoom_cycles_through_visible_panes() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        app.panes.cpu = false;
+        app.panes.swap = false;
+        app.toggle_zoom();
+        assert_eq!(app.focused_pane, Some(Pane::Ram));
+        app.select_next(true);
+        assert_eq!(app.focused_pane, Some(Pane::Gpu));
+        app.select_next(true);
+        app.select_next(true);
+        app.select_next(true);
+        assert_eq!(app.focused_pane, Some(Pane::Ram));
+        app.toggle_zoom();
+        assert_eq!(app.focused_pane, None);
+    }
+
+    #[test]
+    fn selection_cycles_both_ways_and_picks_what_to_zoom() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        app.panes.swap = false;
+        app.select_next(false);
+        assert_eq!(app.selected_pane, Some(Pane::Power));
+        app.select_next(true);
+        assert_eq!(app.selected_pane, Some(Pane::Cpu));
+        app.select_next(false);
+        app.select_next(false);
+        assert_eq!(app.selected_pane, Some(Pane::Temps));
+        assert!(app.focused_scroll().is_some());
+
+        app.toggle_zoom();
+        assert_eq!(app.focused_pane, Some(Pane::Temps));
+        app.toggle_zoom();
+        assert_eq!((app.focused_pane, app.selected_pane), (None, Some(Pane::Temps)));
+    }
+
+    #[test]
+    fn paused_backlog_keeps_newest_history_worth() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let at = Instant::now();
+        for used in 0..15 {
+            let snapshot = StatsSnapshot {
+                ram_used_mb: Some(used),
+                ..StatsSnapshot::default()
+            };
+            app.queue_paused(snapshot, at);
+        }
+
+        assert_eq!(app.paused_backlog.len(), 10);
+        assert_eq!(app.paused_backlog[0].0.ram_used_mb, Some(5));
+    }
+
+    #[test]
+    fn scrollback_clamps_to_retained_samples() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let start = Instant::now();
+        for secs in 0..4 {
+            let snapshot = StatsSnapshot {
+                cpu_cores: vec![10.0 * secs as f32],
+                ..StatsSnapshot::default()
+            };
+            let at = start + Duration::from_secs(secs);
+            app.history.push_at(&snapshot, CpuAggregate::Mean, at);
+        }
+        assert_eq!(app.cursor_time(), None);
+
+        app.paused_at = Some(start + Duration::from_secs(5));
+        app.scroll_history(2);
+        assert_eq!(app.cursor_time(), Some(start + Duration::from_secs(1)));
+        assert_eq!(app.history.cpu_total.value_at(start + Duration::from_secs(1)), Some(10));
+
+        app.scroll_history(10);
+        assert_eq!(app.scroll_offset, 3);
+        app.scroll_history(-10);
+        assert_eq!(app.scroll_offset, 0);
+    }
+
+    #[test]
+    fn history_csv_leaves_missing_samples_empty() {
+        let mut history = History::new(4);
+        let start = Instant::now();
+        let first = StatsSnapshot {
+            cpu_cores: vec![20.0, 40.0],
+            ram_used_mb: Some(1024),
+            ..StatsSnapshot::default()
+        };
+        let second = StatsSnapshot {
+            gpu_util: Some(55.0),
+            temps: vec![TempReading {
+                name: "CPU".to_string(),
+                value_c: 41.0,
+            }],
+            ..first.clone()
+        };
+        history.push_at(&first, CpuAggregate::Mean, start);
+        history.push_at(&second, CpuAggregate::Mean, start + Duration::from_secs(1));
+
+        let wall = Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut csv = Vec::new();
+        history
+            .to_csv(&mut csv, (start + Duration::from_secs(1), wall))
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "time,cpu_total,ram_used_mb,swap_used_mb,gpu_util,emc_util,power_total_mw,lfb_mb,\
+             vin_mw,cpu0,cpu1,temp_CPU_c"
+        );
+        assert_eq!(lines[1], "2024-05-01T11:59:59.000,30,1024,,,,,,,20,40,");
+        assert_eq!(lines[2], "2024-05-01T12:00:00.000,30,1024,,55,,,,,20,40,41");
+    }
+
+    #[test]
+    fn percentile_interpolates_and_handles_empty_history() {
+        let values = VecDeque::from([40, 10, 30, 20, 100]);
+        assert_eq!(percentile(&values, 50.0), 30);
+        assert_eq!(percentile(&values, 95.0), 88);
+        assert_eq!(percentile(&values, 99.0), 98);
+        assert_eq!(percentile(&values, 0.0), 10);
+        assert_eq!(percentile(&VecDeque::from([7]), 99.0), 7);
+        assert_eq!(percentile(&VecDeque::new(), 50.0), 0);
+    }
+
+    #[test]
+    fn moving_average_trails_and_cycles_windows() {
+        let values = VecDeque::from([10, 20, 30, 0, 60]);
+        assert_eq!(moving_average(&values, 1), values);
+        assert_eq!(moving_average(&values, 3), VecDeque::from([10, 15, 20, 17, 30]));
+
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let windows: Vec<_> = (0..4)
+            .map(|_| {
+                app.cycle_smoothing();
+                app.smoothing
+            })
+            .collect();
+        assert_eq!(windows, vec![3, 5, 1, 3]);
+    }
+
+    #[test]
+    fn summarizes_multiple_gpus() {
+        let gpu = |util, mem_used_mb| GpuStats {
+            util,
+            mem_used_mb,
+            mem_total_mb: mem_used_mb.map(|_| 8192),
+            ..GpuStats::default()
+        };
+        let mut snapshot = StatsSnapshot::default();
+
+        snapshot.set_gpus(&[gpu(40.0, Some(1024)), gpu(60.0, Some(2048))]);
+        assert_eq!(snapshot.gpu_util, Some(50.0));
+        assert_eq!(snapshot.gpu_mem_used_mb, Some(3072));
+        assert_eq!(snapshot.gpu_mem_total_mb, Some(16384));
+
+        snapshot.set_gpus(&[gpu(40.0, Some(1024)), gpu(60.0, None)]);
+        assert_eq!(snapshot.gpu_mem_used_mb, None);
+
+        snapshot.set_gpus(&[]);
+        assert_eq!(snapshot.gpu_util, None);
+        assert_eq!(snapshot.gpu_mem_total_mb, None);
+        assert!(snapshot.gpus.is_empty());
+    }
+
+    #[test]
+    fn history_tracks_swap_used() {
+        let mut history = History::new(2);
+        let start = Instant::now();
+        for used in [0, 128, 512] {
+            let snapshot = StatsSnapshot {
+                swap_used_mb: Some(used),
+                swap_total_mb: Some(7_000),
+                ..StatsSnapshot::default()
+            };
+            history.push_at(&snapshot, CpuAggregate::Mean, start);
+        }
+
+        assert_eq!(history.swap_used.values, [128, 512]);
+    }
+
+    #[test]
+    fn anomaly_flags_outlier_in_stable_series() {
+        let mut detector = AnomalyDetector::new(4.0, 30, 60);
+        let at = Instant::now();
+        for mw in [9000.0, 9050.0, 8980.0, 9020.0, 9010.0, 8990.0, 9030.0] {
+            assert_eq!(detector.observe(SparkMetric::Power, mw, at), None);
+        }
+
+        let anomaly = detector
+            .observe(SparkMetric::Power, 15000.0, at)
+            .expect("outlier flagged");
+        assert!(anomaly.z_score > 4.0);
+        assert_eq!(detector.observe(SparkMetric::Power, 9000.0, at), None);
+
+        let flags = detector.flags(SparkMetric::Power).expect("power flags");
+        assert_eq!(flags.values, [0, 0, 0, 0, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn anomaly_log_keeps_every_metric_flagged_in_one_sample() {
+        let mut detector = AnomalyDetector::new(4.0, 30, 60);
+        let at = Instant::now();
+        let snapshot = |cpu: f32, ram_mb: u64| StatsSnapshot {
+            cpu_cores: vec![cpu],
+            ram_used_mb: Some(ram_mb),
+            ..StatsSnapshot::default()
+        };
+        let power = PowerSummary::default();
+        for (cpu, ram_mb) in [(20.0, 4000), (21.0, 4010), (19.0, 3990), (20.0, 4005), (21.0, 4000)]
+        {
+            let stable = snapshot(cpu, ram_mb);
+            assert!(detector.observe_snapshot(&stable, CpuAggregate::Mean, &power, at).is_empty());
+        }
+
+        let spike = snapshot(95.0, 7000);
+        assert_eq!(detector.observe_snapshot(&spike, CpuAggregate::Mean, &power, at).len(), 2);
+        let logged: Vec<_> = detector.log().iter().map(|anomaly| anomaly.metric).collect();
+        assert_eq!(logged, [SparkMetric::Cpu, SparkMetric::Ram]);
+
+        // With a low threshold every swing is flagged, but only the latest are kept.
+        let mut jumpy = AnomalyDetector::new(0.5, 30, 60);
+        for _ in 0..ANOMALY_LOG_LEN {
+            jumpy.observe(SparkMetric::Power, 1.0, at);
+            jumpy.observe(SparkMetric::Power, 1e9, at);
         }
+        assert_eq!(jumpy.log().len(), ANOMALY_LOG_LEN);
     }
 }
@@ -0,0 +1,186 @@
+pub use imp::SnapshotSocket;
+
+/// Clients beyond this many are turned away until one disconnects.
+pub const MAX_CLIENTS: usize = 8;
+
+#[cfg(unix)]
+mod imp {
+    use std::fs;
+    use std::io::Write;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use anyhow::{bail, Context, Result};
+
+    use super::MAX_CLIENTS;
+    use crate::headless::snapshot_json;
+    use crate::model::StatsSnapshot;
+
+    /// How long a write to one client may block before it is disconnected.
+    const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// Streams every snapshot as one line of JSON (the `--json` format) to
+    /// each client connected to a Unix socket. Clients are written to from a
+    /// background thread, so a slow one never stalls the caller. The socket
+    /// file is removed on drop.
+    pub struct SnapshotSocket {
+        path: PathBuf,
+        lines: Sender<String>,
+        /// Lets tests wait for the accept thread.
+        #[cfg(test)]
+        pub(super) clients: Arc<Mutex<Vec<UnixStream>>>,
+    }
+
+    impl SnapshotSocket {
+        pub fn spawn(path: &Path) -> Result<Self> {
+            // A socket left behind by a crashed run is replaced; a live one or
+            // any other file is not.
+            if let Ok(meta) = fs::symlink_metadata(path) {
+                if !meta.file_type().is_socket() {
+                    bail!("`{}` exists and is not a socket", path.display());
+                }
+                if UnixStream::connect(path).is_ok() {
+                    bail!("`{}` is already in use", path.display());
+                }
+                fs::remove_file(path).with_context(|| {
+                    format!("failed to remove stale socket `{}`", path.display())
+                })?;
+            }
+            let listener = UnixListener::bind(path)
+                .with_context(|| format!("failed to listen on `{}`", path.display()))?;
+            let clients = Arc::new(Mutex::new(Vec::new()));
+
+            let accepted = Arc::clone(&clients);
+            thread::spawn(move || {
+                for stream in listener.incoming().map_while(Result::ok) {
+                    let Ok(mut clients) = accepted.lock() else {
+                        return;
+                    };
+                    // A client that stops reading is dropped once a write
+                    // times out.
+                    if clients.len() < MAX_CLIENTS
+                        && stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok()
+                    {
+                        clients.push(stream);
+                    }
+                }
+            });
+
+            let (lines, rx) = mpsc::channel::<String>();
+            let writing = Arc::clone(&clients);
+            thread::spawn(move || {
+                for line in rx {
+                    let Ok(mut clients) = writing.lock() else {
+                        return;
+                    };
+                    clients.retain_mut(|client| writeln!(client, "{}", line).is_ok());
+                }
+            });
+
+            Ok(Self {
+                path: path.to_path_buf(),
+                lines,
+                #[cfg(test)]
+                clients,
+            })
+        }
+
+        /// Queues `snapshot` for every client; those that went away are
+        /// forgotten when it is written.
+        pub fn publish(&self, snapshot: &StatsSnapshot) {
+            if let Ok(line) = snapshot_json(snapshot) {
+                let _ = self.lines.send(line);
+            }
+        }
+    }
+
+    impl Drop for SnapshotSocket {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Unix sockets are not available here, so `--socket` is rejected.
+#[cfg(not(unix))]
+mod imp {
+    use std::path::Path;
+
+    use anyhow::Result;
+
+    use crate::model::StatsSnapshot;
+
+    pub struct SnapshotSocket;
+
+    impl SnapshotSocket {
+        pub fn spawn(_path: &Path) -> Result<Self> {
+            anyhow::bail!("--socket needs a platform with Unix domain sockets")
+        }
+
+        pub fn publish(&self, _snapshot: &StatsSnapshot) {}
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+    use std::time::{Duration, Instant};
+
+    use super::{SnapshotSocket, MAX_CLIENTS};
+    use crate::model::StatsSnapshot;
+
+    fn read_line(client: &UnixStream) -> String {
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        line
+    }
+
+    /// Waits for the accept thread to hold `count` clients.
+    fn wait_for_clients(socket: &SnapshotSocket, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while socket.clients.lock().unwrap().len() != count {
+            assert!(Instant::now() < deadline, "expected {} clients", count);
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn streams_json_lines_and_cleans_up() {
+        let path = std::env::temp_dir().join(format!("jmon-test-{}.sock", std::process::id()));
+        let socket = SnapshotSocket::spawn(&path).unwrap();
+        let mut clients: Vec<_> = (0..=MAX_CLIENTS)
+            .map(|_| UnixStream::connect(&path).unwrap())
+            .collect();
+        // The client over the limit is accepted last and closed right away.
+        let turned_away = clients.pop().unwrap();
+        assert_eq!(read_line(&turned_away), "");
+        wait_for_clients(&socket, MAX_CLIENTS);
+        drop(clients.remove(1));
+
+        let snapshot = StatsSnapshot {
+            ram_used_mb: Some(1024),
+            ..StatsSnapshot::default()
+        };
+        socket.publish(&snapshot);
+        // The last client is written to after the closed one was dropped.
+        let value: serde_json::Value =
+            serde_json::from_str(&read_line(clients.last().unwrap())).unwrap();
+        assert_eq!(value["ram_used_mb"], 1024);
+        assert_eq!(socket.clients.lock().unwrap().len(), MAX_CLIENTS - 1);
+
+        let client = UnixStream::connect(&path).unwrap();
+        wait_for_clients(&socket, MAX_CLIENTS);
+        socket.publish(&snapshot);
+        let value: serde_json::Value = serde_json::from_str(&read_line(&client)).unwrap();
+        assert_eq!(value["ram_used_mb"], 1024);
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{AppState, Pane};
+
+/// UI choices saved on exit and restored on the next start. Values use the
+/// CLI spellings so they are applied like flags, below any given flag.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SavedState {
+    pub interval: u64,
+    pub hide: Vec<String>,
+    pub temp_unit: String,
+    pub power_unit: String,
+    pub palette: String,
+}
+
+impl SavedState {
+    /// `$XDG_STATE_HOME/jmon/state.json`, falling back to `~/.local/state`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+            })?;
+        Some(base.join("jmon").join("state.json"))
+    }
+
+    pub fn from_app(app: &AppState) -> Self {
+        Self {
+            interval: app.interval_ms,
+            hide: Pane::ALL
+                .into_iter()
+                .filter(|pane| !app.panes.shows(*pane))
+                .map(value_name)
+                .collect(),
+            temp_unit: value_name(app.temp_unit),
+            power_unit: value_name(app.power_unit),
+            palette: value_name(app.color_scheme),
+        }
+    }
+
+    /// A missing or unreadable state file just means nothing is restored.
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create `{}`", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+            .with_context(|| format!("failed to save UI state to `{}`", path.display()))
+    }
+
+    /// The state as `(arg id, value)` pairs, like [`crate::config::Config::args`].
+    /// Fields left empty by an older or hand-edited file are skipped.
+    pub fn args(&self) -> Vec<(&'static str, String)> {
+        let mut args = vec![("hide", self.hide.join(","))];
+        if self.interval > 0 {
+            args.push(("interval", self.interval.to_string()));
+        }
+        for (id, value) in [
+            ("temp_unit", &self.temp_unit),
+            ("power_unit", &self.power_unit),
+            ("palette", &self.palette),
+        ] {
+            if !value.is_empty() {
+                args.push((id, value.clone()));
+            }
+        }
+        args
+    }
+}
+
+/// Option defaults seeded below the command line from the config file's
+/// `(arg id, value)` pairs and the restored state. Config values win, so an
+/// edit to `config.toml` takes effect even after a run saved other choices.
+pub fn seed_defaults(
+    config: Vec<(&'static str, String)>,
+    restored: Option<&SavedState>,
+) -> HashMap<&'static str, String> {
+    let mut seeded: HashMap<_, _> =
+        restored.map(SavedState::args).unwrap_or_default().into_iter().collect();
+    seeded.extend(config);
+    seeded
+}
+
+fn value_name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seed_defaults, SavedState};
+    use crate::model::{AppState, PowerUnit};
+
+    #[test]
+    fn captures_toggles_in_cli_spelling() {
+        let mut app = AppState::new(String::new(), String::new(), 750, 10);
+        app.panes.swap = false;
+        app.power_unit = PowerUnit::Watts;
+        let state = SavedState::from_app(&app);
+
+        assert_eq!(state.hide, vec!["swap".to_string()]);
+        assert_eq!(state.power_unit, "w");
+        let args = state.args();
+        assert!(args.contains(&("interval", "750".to_string())));
+        assert!(args.contains(&("temp_unit", "celsius".to_string())));
+        assert!(args.contains(&("palette", "default".to_string())));
+    }
+
+    #[test]
+    fn config_values_win_over_restored_state() {
+        let state = SavedState {
+            interval: 750,
+            palette: "mono".to_string(),
+            ..SavedState::default()
+        };
+        let config = vec![("interval", "500".to_string())];
+
+        let seeded = seed_defaults(config, Some(&state));
+        assert_eq!(seeded["interval"], "500");
+        assert_eq!(seeded["palette"], "mono");
+        assert_eq!(seed_defaults(Vec::new(), None), Default::default());
+    }
+}
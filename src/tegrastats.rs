@@ -1,85 +1,450 @@
-use std::io::{BufRead, BufReader};
-use std::process::{Child, Command, Stdio};
-use std::sync::mpsc::{self, Receiver};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process::{Child, ChildStderr, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
 use regex::Regex;
 
-use crate::model::{PowerRail, StatsSnapshot, TempReading};
+use crate::model::{FanReading, IntervalStyle, PowerRail, StatsSnapshot, TempReading};
+
+/// How often a tegrastats child that exits is respawned.
+#[derive(Clone, Copy, Debug)]
+pub struct RestartPolicy {
+    pub max_restarts: u32,
+    /// Delay before the first restart; doubles on each further attempt.
+    pub backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Lifecycle changes of the tegrastats child, reported next to the snapshots.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceStatus {
+    /// The child died right after starting without printing a reading, e.g.
+    /// because it needs root. Carries its stderr, or its exit status when it
+    /// printed nothing. Sent just before the matching `Exited` or `GaveUp`.
+    StartupFailed(String),
+    /// The child exited and restart `attempt` of `max` is pending.
+    Exited { attempt: u32, max: u32 },
+    Restarted,
+    /// The child kept exiting; no further restarts will be tried.
+    GaveUp,
+}
 
 pub struct TegrastatsRunner {
     rx: Receiver<StatsSnapshot>,
-    child: Child,
+    status_rx: Receiver<SourceStatus>,
+    child: Arc<Mutex<Option<Child>>>,
+    stop: Arc<AtomicBool>,
+    recorder: Arc<Mutex<Option<SessionRecorder>>>,
+}
+
+/// How often recorded lines are flushed to disk.
+const RECORD_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tees raw tegrastats lines to a file (`--record`). Data lines without a
+/// timestamp get their arrival time prefixed in tegrastats' own format, so
+/// `--replay` reads the file back with the original pacing.
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    flushed_at: Instant,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create recording `{}`", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            flushed_at: Instant::now(),
+        })
+    }
+
+    /// Writes `line`, stamping it if it parsed to a `snapshot` without a time.
+    fn record(&mut self, line: &str, snapshot: Option<&StatsSnapshot>) {
+        let written = match snapshot {
+            Some(snapshot) if snapshot.timestamp.is_none() => writeln!(
+                self.writer,
+                "{} {}",
+                Local::now().format(TIMESTAMP_FORMAT),
+                line
+            ),
+            _ => writeln!(self.writer, "{}", line),
+        };
+        if written.is_ok() && self.flushed_at.elapsed() >= RECORD_FLUSH_INTERVAL {
+            let _ = self.writer.flush();
+            self.flushed_at = Instant::now();
+        }
+    }
 }
 
 impl TegrastatsRunner {
-    pub fn spawn(path: &str, interval_ms: u64) -> Result<Self> {
-        let mut child = Command::new(path)
-            .arg("--interval")
-            .arg(interval_ms.to_string())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .with_context(|| format!("failed to start tegrastats at `{}`", path))?;
-
-        let stdout = child
-            .stdout
-            .take()
-            .context("tegrastats stdout was not available")?;
+    /// Runs `path` with `extra_args` followed by the interval in `style`,
+    /// teeing its lines to `recorder` if given.
+    pub fn spawn(
+        path: &str,
+        extra_args: &[String],
+        interval_ms: u64,
+        style: IntervalStyle,
+        recorder: Option<SessionRecorder>,
+    ) -> Result<Self> {
+        let mut args = extra_args.to_vec();
+        match style {
+            IntervalStyle::Flag => args.extend(["--interval".to_string(), interval_ms.to_string()]),
+            IntervalStyle::Positional => args.push(interval_ms.to_string()),
+            IntervalStyle::None => {}
+        }
+        let path = path.to_string();
+        let command_line = [path.as_str()]
+            .into_iter()
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self::spawn_with(
+            move || {
+                Command::new(&path)
+                    .args(&args)
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .with_context(|| format!("failed to start tegrastats as `{}`", command_line))
+            },
+            RestartPolicy::default(),
+            recorder,
+        )
+    }
+
+    /// Runs the child produced by `spawn_child`, calling it again whenever the
+    /// child's output ends, as allowed by `policy`. A child that produced at
+    /// least one snapshot resets the attempt count. If the child pipes its
+    /// stderr, that is reported when it dies at startup. Every line the
+    /// children print is teed to `recorder`, from the first one on.
+    pub fn spawn_with<F>(
+        mut spawn_child: F,
+        policy: RestartPolicy,
+        recorder: Option<SessionRecorder>,
+    ) -> Result<Self>
+    where
+        F: FnMut() -> Result<Child> + Send + 'static,
+    {
+        let mut first = spawn_child()?;
+        let mut stdout = Some(
+            first
+                .stdout
+                .take()
+                .context("tegrastats stdout was not available")?,
+        );
+        let mut stderr = first.stderr.take().map(collect_stderr);
 
         let (tx, rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+        let child = Arc::new(Mutex::new(Some(first)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let child_thread = Arc::clone(&child);
+        let stop_thread = Arc::clone(&stop);
+        let recorder = Arc::new(Mutex::new(recorder));
+        let recorder_thread = Arc::clone(&recorder);
         let parser = TegrastatsParser::new();
 
         thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines().flatten() {
-                if let Some(snapshot) = parser.parse_line(&line) {
-                    let _ = tx.send(snapshot);
+            let mut attempt = 0;
+            while let Some(output) = stdout.take() {
+                let started = Instant::now();
+                let mut sent_any = false;
+                for line in BufReader::new(output).lines().map_while(Result::ok) {
+                    let snapshot = parser.parse_line(&line);
+                    // Recorded before it is sent, so a reading seen is also on disk.
+                    if let Some(recorder) = lock(&recorder_thread).as_mut() {
+                        recorder.record(&line, snapshot.as_ref());
+                    }
+                    if let Some(snapshot) = snapshot {
+                        if tx.send(snapshot).is_err() {
+                            return;
+                        }
+                        sent_any = true;
+                    }
+                }
+                let exit_status = lock(&child_thread)
+                    .take()
+                    .and_then(|mut exited| exited.wait().ok());
+                if sent_any {
+                    attempt = 0;
+                } else if started.elapsed() < STARTUP_GRACE {
+                    let stderr = stderr.take().and_then(|rx| rx.recv_timeout(STDERR_WAIT).ok());
+                    let message = match stderr.as_deref().map(str::trim) {
+                        Some(text) if !text.is_empty() => Some(text.to_string()),
+                        _ => exit_status
+                            .filter(|status| !status.success())
+                            .map(|status| format!("exited at startup ({})", status)),
+                    };
+                    if let Some(message) = message {
+                        let _ = status_tx.send(SourceStatus::StartupFailed(message));
+                    }
+                }
+
+                while stdout.is_none() && !stop_thread.load(Ordering::Relaxed) {
+                    if attempt == policy.max_restarts {
+                        let _ = status_tx.send(SourceStatus::GaveUp);
+                        return;
+                    }
+                    attempt += 1;
+                    let _ = status_tx.send(SourceStatus::Exited {
+                        attempt,
+                        max: policy.max_restarts,
+                    });
+                    thread::sleep(policy.backoff * 2u32.saturating_pow(attempt - 1));
+                    let Ok(mut next) = spawn_child() else {
+                        continue;
+                    };
+                    let mut slot = lock(&child_thread);
+                    if stop_thread.load(Ordering::Relaxed) {
+                        let _ = next.kill();
+                        let _ = next.wait();
+                        return;
+                    }
+                    stdout = next.stdout.take();
+                    stderr = next.stderr.take().map(collect_stderr);
+                    *slot = Some(next);
+                    let _ = status_tx.send(SourceStatus::Restarted);
+                }
+            }
+        });
+
+        Ok(Self {
+            rx,
+            status_rx,
+            child,
+            stop,
+            recorder,
+        })
+    }
+
+    /// Replays a captured tegrastats log instead of running tegrastats.
+    pub fn replay(path: &str, interval_ms: u64) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("failed to open tegrastats log `{}`", path))?;
+        Ok(Self::from_reader(BufReader::new(file), interval_ms))
+    }
+
+    /// Sends one snapshot per `interval_ms` from `reader`, starting over at EOF.
+    /// When consecutive lines carry timestamps a second or more apart, that gap
+    /// is used instead, so logs reproduce their original timing. Stops if a
+    /// full pass yields no snapshot, so a log without usable lines does not spin.
+    pub fn from_reader<R>(mut reader: R, interval_ms: u64) -> Self
+    where
+        R: BufRead + Seek + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = Arc::clone(&stop);
+        let parser = TegrastatsParser::new();
+
+        thread::spawn(move || {
+            while !stop_thread.load(Ordering::Relaxed) {
+                let mut sent_any = false;
+                let mut previous = None;
+                let mut line = String::new();
+                while !stop_thread.load(Ordering::Relaxed) {
+                    line.clear();
+                    match reader.read_line(&mut line) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                    match send_line(&parser, line.trim_end(), &tx) {
+                        LineOutcome::Sent(timestamp) => {
+                            sent_any = true;
+                            let interval = Duration::from_millis(interval_ms);
+                            thread::sleep(replay_delay(previous, timestamp, interval));
+                            previous = timestamp;
+                        }
+                        LineOutcome::Skipped => {}
+                        LineOutcome::Disconnected => return,
+                    }
+                }
+                if !sent_any || reader.seek(SeekFrom::Start(0)).is_err() {
+                    return;
                 }
             }
         });
 
-        Ok(Self { rx, child })
+        Self {
+            rx,
+            status_rx: mpsc::channel().1,
+            child: Arc::new(Mutex::new(None)),
+            stop,
+            recorder: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stops recording and hands the recorder back, e.g. to move it to the
+    /// runner that replaces this one.
+    pub fn take_recorder(&self) -> Option<SessionRecorder> {
+        lock(&self.recorder).take()
+    }
+
+    /// Tees the lines read from now on to `recorder`, e.g. one taken from
+    /// the runner this one replaces.
+    pub fn set_recorder(&self, recorder: Option<SessionRecorder>) {
+        *lock(&self.recorder) = recorder;
     }
 
     pub fn try_recv(&self) -> Option<StatsSnapshot> {
         self.rx.try_recv().ok()
     }
 
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<StatsSnapshot> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    pub fn try_status(&self) -> Option<SourceStatus> {
+        self.status_rx.try_recv().ok()
+    }
+
     pub fn shutdown(&mut self) {
-        let _ = self.child.kill();
-        let _ = self.child.wait();
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = lock(&self.child).take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(mut recorder) = self.take_recorder() {
+            let _ = recorder.writer.flush();
+        }
+    }
+}
+
+/// A child that exits within this long without a reading is treated as
+/// failing to start rather than as a crash after running fine.
+const STARTUP_GRACE: Duration = Duration::from_secs(3);
+/// How long to wait for an exited child's stderr to be drained.
+const STDERR_WAIT: Duration = Duration::from_millis(200);
+
+/// Reads `stderr` to the end on a helper thread so a chatty child cannot block
+/// on a full pipe; the text arrives on the returned channel once it closes.
+fn collect_stderr(mut stderr: ChildStderr) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        let _ = tx.send(text);
+    });
+    rx
+}
+
+/// The slot stays usable even if a thread panicked while holding it.
+fn lock<T>(slot: &Mutex<T>) -> MutexGuard<'_, T> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Longest pause between replayed lines, so a gap in a log does not stall it.
+const MAX_REPLAY_DELAY: Duration = Duration::from_secs(60);
+
+/// How long to wait after replaying a line stamped `current`, given the
+/// previous line's stamp. Timestamps only have whole seconds, so lines within
+/// the same second fall back to `interval`.
+fn replay_delay(
+    previous: Option<NaiveDateTime>,
+    current: Option<NaiveDateTime>,
+    interval: Duration,
+) -> Duration {
+    let gap = previous
+        .zip(current)
+        .and_then(|(previous, current)| (current - previous).to_std().ok())
+        .filter(|gap| *gap >= Duration::from_secs(1));
+    gap.map_or(interval, |gap| gap.min(MAX_REPLAY_DELAY))
+}
+
+enum LineOutcome {
+    /// Carries the line's timestamp, if it had one.
+    Sent(Option<NaiveDateTime>),
+    /// The line held no tegrastats data.
+    Skipped,
+    /// The receiving side is gone; the reader thread should stop.
+    Disconnected,
+}
+
+/// Parses `line` and forwards the snapshot, if any.
+fn send_line(parser: &TegrastatsParser, line: &str, tx: &Sender<StatsSnapshot>) -> LineOutcome {
+    let Some(snapshot) = parser.parse_line(line) else {
+        return LineOutcome::Skipped;
+    };
+    let timestamp = snapshot.timestamp;
+    if tx.send(snapshot).is_ok() {
+        LineOutcome::Sent(timestamp)
+    } else {
+        LineOutcome::Disconnected
     }
 }
 
+/// The `MM-DD-YYYY HH:MM:SS` prefix tegrastats prints on each line.
+const TIMESTAMP_FORMAT: &str = "%m-%d-%Y %H:%M:%S";
+
 pub struct TegrastatsParser {
+    /// ANSI CSI sequences (e.g. `\x1b[0m`) that wrappers may inject.
+    ansi_re: Regex,
+    timestamp_re: Regex,
     ram_re: Regex,
     swap_re: Regex,
+    lfb_re: Regex,
     cpu_re: Regex,
     emc_re: Regex,
+    /// `GR3D_FREQ 55%`, optionally followed by a clock.
+    gr3d_re: Regex,
     temp_re: Regex,
+    /// `VDD_GPU 1200mW/1100mW`, or `GPU 1200/1100` as JetPack 4 prints it.
     power_re: Regex,
+    /// `FAN 52%` / `fan [52%]` duty cycle.
+    fan_pwm_re: Regex,
+    /// `RPM 2400` or `2400RPM`.
+    fan_rpm_re: Regex,
 }
 
 impl TegrastatsParser {
     pub fn new() -> Self {
         Self {
+            ansi_re: Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]").unwrap(),
+            timestamp_re: Regex::new(r"^\s*(?P<ts>\d{2}-\d{2}-\d{4} \d{2}:\d{2}:\d{2})").unwrap(),
             ram_re: Regex::new(r"RAM\s+(?P<used>\d+)/(?P<total>\d+)MB").unwrap(),
             swap_re: Regex::new(r"SWAP\s+(?P<used>\d+)/(?P<total>\d+)MB").unwrap(),
-            cpu_re: Regex::new(r"CPU\s+\[(?P<list>[^\]]+)]").unwrap(),
+            lfb_re: Regex::new(r"lfb\s+(?P<count>\d+)x(?P<size>\d+)MB").unwrap(),
+            cpu_re: Regex::new(r"CPU\s*\[(?P<list>[^\]]+)]").unwrap(),
             emc_re: Regex::new(r"EMC_FREQ\s+(?P<util>\d+)%").unwrap(),
-            temp_re: Regex::new(r"(?P<name>[A-Za-z0-9_]+)@(?P<temp>\d+(?:\.\d+)?)C").unwrap(),
+            gr3d_re: Regex::new(r"GR3D_FREQ\s+(?P<util>\d+(?:\.\d+)?)%").unwrap(),
+            temp_re: Regex::new(r"(?P<name>[A-Za-z0-9_]+)@(?P<temp>-?\d+(?:\.\d+)?)C").unwrap(),
             power_re: Regex::new(
-                r"(?P<name>[A-Z0-9_]+)\s+(?P<current>\d+)mW/(?P<avg>\d+)mW",
+                r"(?P<name>[A-Z0-9_]+)\s+(?P<current>\d+)(?:mW)?/(?P<avg>\d+)(?:mW)?\b",
             )
             .unwrap(),
+            fan_pwm_re: Regex::new(r"(?i)\bfan\s*\[?\s*(?P<pwm>\d+(?:\.\d+)?)%").unwrap(),
+            fan_rpm_re: Regex::new(r"(?i)\bRPM\s*(?P<before>\d+)|\b(?P<after>\d+)\s*RPM\b")
+                .unwrap(),
         }
     }
 
     pub fn parse_line(&self, line: &str) -> Option<StatsSnapshot> {
+        let line = self.ansi_re.replace_all(line.trim_end_matches('\r'), "");
+        let line = line.as_ref();
         let mut snapshot = StatsSnapshot::default();
 
+        if let Some(caps) = self.timestamp_re.captures(line) {
+            snapshot.timestamp = caps
+                .name("ts")
+                .and_then(|ts| NaiveDateTime::parse_from_str(ts.as_str(), TIMESTAMP_FORMAT).ok());
+        }
+
         if let Some(caps) = self.ram_re.captures(line) {
             snapshot.ram_used_mb = caps.name("used").and_then(|v| v.as_str().parse().ok());
             snapshot.ram_total_mb = caps
@@ -94,10 +459,19 @@ impl TegrastatsParser {
                 .and_then(|v| v.as_str().parse().ok());
         }
 
-        if let Some(caps) = self.cpu_re.captures(line) {
-            if let Some(list) = caps.name("list") {
-                snapshot.cpu_cores = parse_cpu_list(list.as_str());
-            }
+        if let Some(caps) = self.lfb_re.captures(line) {
+            snapshot.lfb_blocks = caps.name("count").and_then(|v| v.as_str().parse().ok());
+            snapshot.lfb_block_mb = caps.name("size").and_then(|v| v.as_str().parse().ok());
+        }
+
+        if let Some(caps) = self.cpu_re.captures(line)
+            && let Some(list) = caps.name("list")
+        {
+            let entries = parse_cpu_list(list.as_str());
+            snapshot.cpu_cores = entries.iter().map(|entry| entry.util).collect();
+            snapshot.cpu_freqs_mhz = entries.iter().map(|entry| entry.freq_mhz).collect();
+            snapshot.cpu_online = entries.iter().map(|entry| entry.online).collect();
+            snapshot.cpu_unparseable = snapshot.cpu_cores.is_empty();
         }
 
         if let Some(caps) = self.emc_re.captures(line) {
@@ -106,14 +480,20 @@ impl TegrastatsParser {
                 .and_then(|v| v.as_str().parse::<f32>().ok());
         }
 
+        if let Some(caps) = self.gr3d_re.captures(line) {
+            snapshot.gr3d_util = caps
+                .name("util")
+                .and_then(|v| v.as_str().parse::<f32>().ok());
+        }
+
         for caps in self.temp_re.captures_iter(line) {
-            if let (Some(name), Some(temp)) = (caps.name("name"), caps.name("temp")) {
-                if let Ok(value_c) = temp.as_str().parse::<f32>() {
-                    snapshot.temps.push(TempReading {
-                        name: name.as_str().to_string(),
-                        value_c,
-                    });
-                }
+            if let (Some(name), Some(temp)) = (caps.name("name"), caps.name("temp"))
+                && let Ok(value_c) = temp.as_str().parse::<f32>()
+            {
+                snapshot.temps.push(TempReading {
+                    name: name.as_str().to_string(),
+                    value_c,
+                });
             }
         }
 
@@ -122,20 +502,34 @@ impl TegrastatsParser {
                 caps.name("name"),
                 caps.name("current"),
                 caps.name("avg"),
-            ) {
-                if let (Ok(current_mw), Ok(average_mw)) =
-                    (current.as_str().parse::<u64>(), avg.as_str().parse::<u64>())
-                {
-                    snapshot.power_rails.push(PowerRail {
-                        name: name.as_str().to_string(),
-                        current_mw,
-                        average_mw,
-                    });
-                }
+            ) && let (Ok(current_mw), Ok(average_mw)) =
+                (current.as_str().parse::<u64>(), avg.as_str().parse::<u64>())
+            {
+                snapshot.power_rails.push(PowerRail {
+                    name: name.as_str().to_string(),
+                    current_mw,
+                    average_mw,
+                });
             }
         }
 
+        let pwm_percent = self
+            .fan_pwm_re
+            .captures(line)
+            .and_then(|caps| caps.name("pwm")?.as_str().parse::<f32>().ok());
+        let rpm = self.fan_rpm_re.captures(line).and_then(|caps| {
+            caps.name("before")
+                .or_else(|| caps.name("after"))?
+                .as_str()
+                .parse::<u32>()
+                .ok()
+        });
+        if rpm.is_some() || pwm_percent.is_some() {
+            snapshot.fan = Some(FanReading { rpm, pwm_percent });
+        }
+
         let has_data = !snapshot.cpu_cores.is_empty()
+            || snapshot.cpu_unparseable
             || snapshot.ram_used_mb.is_some()
             || snapshot.swap_used_mb.is_some()
             || snapshot.emc_util.is_some()
@@ -150,22 +544,119 @@ impl TegrastatsParser {
     }
 }
 
-fn parse_cpu_list(list: &str) -> Vec<f32> {
-    list.split(',')
+/// One entry of the tegrastats CPU list: `92%@1566` or `off`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CpuEntry {
+    util: f32,
+    freq_mhz: Option<u32>,
+    online: bool,
+}
+
+/// Parses `12%@1190` style entries. Builds differ in decimals (`12.5%`),
+/// padding with spaces or tabs (`[ 12.5 % @ 1190 ]`) and offline cores
+/// (`off`); unreadable entries are skipped.
+///
+/// Offline cores count as 0% with no frequency, but a list with no parseable
+/// utilization at all (e.g. `off,,,`) yields no cores rather than a
+/// misleading all-zero CPU.
+fn parse_cpu_list(list: &str) -> Vec<CpuEntry> {
+    let mut any_reading = false;
+    let entries = list
+        .split(',')
         .filter_map(|entry| {
             let trimmed = entry.trim();
             if trimmed.eq_ignore_ascii_case("off") {
-                return Some(0.0);
+                return Some(CpuEntry {
+                    util: 0.0,
+                    freq_mhz: None,
+                    online: false,
+                });
             }
-            let percent_part = trimmed.split('%').next().unwrap_or("");
-            percent_part.trim().parse::<f32>().ok()
+            let (percent_part, freq_part) = match trimmed.split_once('@') {
+                Some((percent, freq)) => (percent, Some(freq)),
+                None => (trimmed, None),
+            };
+            let percent_part = percent_part.split('%').next().unwrap_or("");
+            let util = percent_part.trim().parse::<f32>().ok().filter(|util| util.is_finite())?;
+            any_reading = true;
+            Some(CpuEntry {
+                util,
+                freq_mhz: freq_part.and_then(|freq| {
+                    let mhz = freq.trim().parse::<f32>().ok().filter(|mhz| mhz.is_finite())?;
+                    Some(mhz.round() as u32)
+                }),
+                online: true,
+            })
         })
-        .collect()
+        .collect();
+    if any_reading { entries } else { Vec::new() }
 }
 
+/// The fake board, so tests can feed its layouts through the parser.
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "bin/fake_tegrastats.rs"]
+mod fake_tegrastats;
+
 #[cfg(test)]
 mod tests {
-    use super::TegrastatsParser;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use std::process::{Command, Stdio};
+
+    use chrono::NaiveDateTime;
+
+    use super::{
+        parse_cpu_list, replay_delay, IntervalStyle, RestartPolicy, SessionRecorder, SourceStatus,
+        TegrastatsParser, TegrastatsRunner,
+    };
+    use crate::model::{CpuAggregate, FanReading, GpuStats, TempRange};
+
+    #[test]
+    fn parses_optional_fan_fields() {
+        let parser = TegrastatsParser::new();
+        let line = "RAM 2000/3964MB CPU [5%@1479,3%@1479] FAN [42%] RPM 2380 tj@41C";
+        let fan = parser.parse_line(line).and_then(|snapshot| snapshot.fan);
+        assert_eq!(fan, Some(FanReading { rpm: Some(2380), pwm_percent: Some(42.0) }));
+
+        let line = "RAM 2000/3964MB fan 60% tj@41C";
+        let fan = parser.parse_line(line).and_then(|snapshot| snapshot.fan);
+        assert_eq!(fan, Some(FanReading { rpm: None, pwm_percent: Some(60.0) }));
+
+        let line = "RAM 2000/3964MB CPU [5%@1479] tj@41C";
+        assert_eq!(parser.parse_line(line).and_then(|snapshot| snapshot.fan), None);
+    }
+
+    #[test]
+    fn gr3d_load_backs_gpu_util_without_nvidia_smi() {
+        let parser = TegrastatsParser::new();
+        let mut snapshot = parser
+            .parse_line("RAM 2000/3964MB EMC_FREQ 3%@1600 GR3D_FREQ 37%@[1300,1300]")
+            .expect("parse snapshot");
+        assert_eq!(snapshot.gr3d_util, Some(37.0));
+
+        snapshot.set_gpus(&[GpuStats {
+            util: 80.0,
+            ..GpuStats::default()
+        }]);
+        assert_eq!(snapshot.gpu_util, Some(80.0));
+        snapshot.set_gpus(&[]);
+        assert_eq!(snapshot.gpu_util, Some(37.0));
+    }
+
+    #[test]
+    fn lfb_token_is_optional() {
+        let parser = TegrastatsParser::new();
+        let snapshot = parser
+            .parse_line("RAM 2000/3964MB (lfb 79x4MB) SWAP 0/1982MB (cached 0MB)")
+            .expect("parse snapshot");
+        assert_eq!((snapshot.lfb_blocks, snapshot.lfb_block_mb), (Some(79), Some(4)));
+        assert_eq!(snapshot.swap_total_mb, Some(1982));
+
+        let snapshot = parser.parse_line("RAM 2000/3964MB CPU [5%@1479]").expect("parse snapshot");
+        assert_eq!((snapshot.lfb_blocks, snapshot.lfb_mb()), (None, None));
+    }
 
     #[test]
     fn parses_sample_line() {
@@ -175,7 +666,11 @@ mod tests {
 
         assert_eq!(snapshot.ram_used_mb, Some(17842));
         assert_eq!(snapshot.ram_total_mb, Some(125772));
+        assert_eq!(snapshot.lfb_mb(), Some(316));
+        assert_eq!(snapshot.swap_total_mb, None);
         assert_eq!(snapshot.cpu_cores.len(), 14);
+        assert_eq!(snapshot.cpu_freqs_mhz.len(), 14);
+        assert_eq!(snapshot.cpu_freqs_mhz[2], Some(1566));
         assert!(snapshot.power_rails.iter().any(|rail| rail.name == "VIN"));
         let vin = snapshot
             .power_rails
@@ -183,5 +678,314 @@ mod tests {
             .find(|rail| rail.name == "VIN")
             .expect("VIN rail");
         assert_eq!(vin.current_mw, 16802);
+        let timestamp = snapshot.timestamp.expect("timestamp");
+        assert_eq!(timestamp.to_string(), "2026-01-20 22:46:22");
+    }
+
+    #[test]
+    fn parses_every_fake_tegrastats_profile() {
+        use super::fake_tegrastats::{FakeState, Profile, Scenario};
+
+        let parser = TegrastatsParser::new();
+        for name in Profile::NAMES {
+            let profile = Profile::parse(name).expect("known profile");
+            let line = FakeState::new(Scenario::Wave, profile).next_line(1000);
+            let snapshot = parser.parse_line(&line).expect("parse snapshot");
+
+            assert_eq!(snapshot.cpu_cores.len(), profile.cpu_cores(), "{}", name);
+            let temps: Vec<&str> = snapshot.temps.iter().map(|temp| temp.name.as_str()).collect();
+            let sensors: Vec<&str> = profile.sensors().iter().map(|(name, _)| *name).collect();
+            assert_eq!(temps, sensors, "{}", name);
+            let rails: Vec<&str> =
+                snapshot.power_rails.iter().map(|rail| rail.name.as_str()).collect();
+            let expected: Vec<&str> =
+                profile.rails(0.0, 0.0, 0.0, 0.0).iter().map(|(name, _)| *name).collect();
+            assert_eq!(rails, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn timestamp_is_optional() {
+        let parser = TegrastatsParser::new();
+        let snapshot = parser.parse_line("RAM 100/200MB").expect("parse snapshot");
+        assert_eq!(snapshot.timestamp, None);
+        assert!(parser.parse_line("01-20-2026 22:46:22").is_none());
+    }
+
+    #[test]
+    fn replay_paces_by_timestamp_gaps() {
+        let parse = |ts| NaiveDateTime::parse_from_str(ts, super::TIMESTAMP_FORMAT).ok();
+        let interval = Duration::from_millis(250);
+        let first = parse("01-20-2026 22:46:22");
+        let same_second = parse("01-20-2026 22:46:22");
+        let later = parse("01-20-2026 22:46:25");
+        let much_later = parse("01-20-2026 23:46:25");
+
+        assert_eq!(replay_delay(first, same_second, interval), interval);
+        assert_eq!(replay_delay(first, later, interval), Duration::from_secs(3));
+        assert_eq!(replay_delay(later, first, interval), interval);
+        assert_eq!(replay_delay(None, later, interval), interval);
+        assert_eq!(replay_delay(later, much_later, interval), super::MAX_REPLAY_DELAY);
+    }
+
+    #[test]
+    fn ignores_ansi_codes_and_trailing_carriage_return() {
+        let parser = TegrastatsParser::new();
+        let line = "\x1b[1;32mRAM 1000/8000MB\x1b[0m CPU [\x1b[33m5%@1000,\x1b[0m15%@1000] \
+                    cpu@40.5C VIN 5000mW/4000mW\r";
+        let snapshot = parser.parse_line(line).expect("parse snapshot");
+
+        assert_eq!(snapshot.ram_used_mb, Some(1000));
+        assert_eq!(snapshot.ram_total_mb, Some(8000));
+        assert_eq!(snapshot.cpu_cores, vec![5.0, 15.0]);
+        assert_eq!(snapshot.power_rails[0].average_mw, 4000);
+    }
+
+    #[test]
+    fn keeps_disconnected_sensor_values_for_validation() {
+        let parser = TegrastatsParser::new();
+        let line = "RAM 1000/8000MB CPU [5%@1000] cpu@40.5C cv0@-256C gpu@511C";
+        let snapshot = parser.parse_line(line).expect("parse snapshot");
+
+        let plausible = TempRange::default();
+        let values: Vec<(f32, bool)> = snapshot
+            .temps
+            .iter()
+            .map(|temp| (temp.value_c, plausible.contains(temp.value_c)))
+            .collect();
+        assert_eq!(values, vec![(40.5, true), (-256.0, false), (511.0, false)]);
+        assert!(!snapshot.crit_breached(plausible));
+    }
+
+    #[test]
+    fn malformed_cpu_field_yields_no_cores() {
+        let parser = TegrastatsParser::new();
+        let snapshot = parser
+            .parse_line("RAM 1000/8000MB CPU [off,,,] cpu@40.5C")
+            .expect("parse snapshot");
+
+        assert!(snapshot.cpu_cores.is_empty());
+        assert!(snapshot.cpu_unparseable);
+        assert_eq!(snapshot.cpu_total(CpuAggregate::Mean), None);
+        assert_eq!(snapshot.cpu_total(CpuAggregate::ActiveMean), None);
+
+        let snapshot = parser.parse_line("RAM 1000/8000MB").expect("parse snapshot");
+        assert!(!snapshot.cpu_unparseable);
+    }
+
+    #[test]
+    fn parses_core_frequencies() {
+        let entries = parse_cpu_list("off,0%@972,92%@1566,15%");
+        let freqs: Vec<_> = entries.iter().map(|entry| entry.freq_mhz).collect();
+        let online: Vec<_> = entries.iter().map(|entry| entry.online).collect();
+
+        assert_eq!(freqs, vec![None, Some(972), Some(1566), None]);
+        assert_eq!(online, vec![false, true, true, true]);
+        assert_eq!(entries[2].util, 92.0);
+    }
+
+    #[test]
+    fn parses_decimal_and_padded_core_entries() {
+        let entries = parse_cpu_list(" 12.5%@1190,\t3 % @ 729 ,\tOFF ,7.25%\t@\t1984.0, 40%");
+        let values: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.util, entry.freq_mhz, entry.online))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                (12.5, Some(1190), true),
+                (3.0, Some(729), true),
+                (0.0, None, false),
+                (7.25, Some(1984), true),
+                (40.0, None, true),
+            ]
+        );
+        assert!(parse_cpu_list("nan%@1190").is_empty());
+
+        let snapshot = TegrastatsParser::new()
+            .parse_line("RAM 1000/8000MB CPU\t[ 12.5%@1190,\toff ] cpu@40.5C")
+            .expect("parse snapshot");
+        assert_eq!(snapshot.cpu_cores, vec![12.5, 0.0]);
+        assert_eq!(snapshot.cpu_online, vec![true, false]);
+    }
+
+    #[test]
+    fn replays_lines_in_order_and_loops() {
+        let log = "RAM 1000/8000MB CPU [10%@972]\n\
+                   not a tegrastats line\n\
+                   RAM 2000/8000MB CPU [20%@972]\n";
+        let mut runner = TegrastatsRunner::from_reader(Cursor::new(log), 1);
+        let timeout = Duration::from_secs(1);
+        let ram = |runner: &TegrastatsRunner| {
+            runner.recv_timeout(timeout).expect("replayed snapshot").ram_used_mb
+        };
+
+        assert_eq!(ram(&runner), Some(1000));
+        assert_eq!(ram(&runner), Some(2000));
+        assert_eq!(ram(&runner), Some(1000));
+        runner.shutdown();
+    }
+
+    #[test]
+    fn passes_extra_args_then_the_interval_in_its_style() {
+        // `sh -c` binds the first argument after the script to `$0`.
+        let args = [
+            "-c".to_string(),
+            "[ \"$0 $1 $2\" = 'extra --interval 250' ] && echo 'RAM 1000/8000MB'".to_string(),
+            "extra".to_string(),
+        ];
+        let mut runner =
+            TegrastatsRunner::spawn("sh", &args, 250, IntervalStyle::Flag, None).expect("spawn");
+        let snapshot = runner.recv_timeout(Duration::from_secs(2)).expect("reading");
+        assert_eq!(snapshot.ram_used_mb, Some(1000));
+        runner.shutdown();
+
+        let positional = [
+            "-c".to_string(),
+            "[ \"$0\" = 250 ] && [ -z \"$1\" ] && echo 'RAM 2000/8000MB'".to_string(),
+        ];
+        let mut runner =
+            TegrastatsRunner::spawn("sh", &positional, 250, IntervalStyle::Positional, None)
+                .expect("spawn");
+        let snapshot = runner.recv_timeout(Duration::from_secs(2)).expect("reading");
+        assert_eq!(snapshot.ram_used_mb, Some(2000));
+        runner.shutdown();
+
+        let missing = "/nonexistent/tegrastats";
+        let err = TegrastatsRunner::spawn(missing, &[], 250, IntervalStyle::None, None)
+            .err()
+            .expect("missing binary");
+        assert!(format!("{:#}", err).contains("`/nonexistent/tegrastats`"));
+    }
+
+    #[test]
+    fn records_raw_lines_in_a_replayable_form() {
+        let path = std::env::temp_dir().join(format!("jmon-test-{}.log", std::process::id()));
+        let policy = RestartPolicy {
+            max_restarts: 0,
+            backoff: Duration::from_millis(1),
+        };
+        let mut runner = TegrastatsRunner::spawn_with(
+            || {
+                let script = "echo 'RAM 1000/8000MB'; echo 'warning: noise'; \
+                              echo '01-20-2026 22:46:22 RAM 2000/8000MB'";
+                Ok(Command::new("sh")
+                    .arg("-c")
+                    .arg(script)
+                    .stdout(Stdio::piped())
+                    .spawn()?)
+            },
+            policy,
+            Some(SessionRecorder::create(&path).unwrap()),
+        )
+        .expect("spawn");
+
+        let timeout = Duration::from_secs(2);
+        assert!(runner.recv_timeout(timeout).is_some());
+        assert!(runner.recv_timeout(timeout).is_some());
+        runner.shutdown();
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = recorded.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(TegrastatsParser::new().parse_line(lines[0]).unwrap().timestamp.is_some());
+        assert!(lines[0].ends_with(" RAM 1000/8000MB"));
+        assert_eq!(lines[1..], ["warning: noise", "01-20-2026 22:46:22 RAM 2000/8000MB"]);
+
+        let mut replay = TegrastatsRunner::replay(path.to_str().unwrap(), 1).unwrap();
+        let snapshot = replay.recv_timeout(timeout).expect("replayed snapshot");
+        assert_eq!(snapshot.ram_used_mb, Some(1000));
+        replay.shutdown();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn respawns_exited_child_until_giving_up() {
+        let mut spawns = 0;
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            backoff: Duration::from_millis(1),
+        };
+        let mut runner = TegrastatsRunner::spawn_with(
+            move || {
+                spawns += 1;
+                // Only the first child prints a reading, so the restarts never reset.
+                let script = if spawns == 1 { "echo 'RAM 1000/8000MB'" } else { "exit 0" };
+                Ok(Command::new("sh")
+                    .arg("-c")
+                    .arg(script)
+                    .stdout(Stdio::piped())
+                    .spawn()?)
+            },
+            policy,
+            None,
+        )
+        .expect("spawn");
+
+        let timeout = Duration::from_secs(2);
+        let snapshot = runner.recv_timeout(timeout).expect("first child reading");
+        assert_eq!(snapshot.ram_used_mb, Some(1000));
+
+        let mut statuses = Vec::new();
+        let deadline = std::time::Instant::now() + timeout;
+        while statuses.last() != Some(&SourceStatus::GaveUp) && std::time::Instant::now() < deadline
+        {
+            match runner.try_status() {
+                Some(status) => statuses.push(status),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                SourceStatus::Exited { attempt: 1, max: 2 },
+                SourceStatus::Restarted,
+                SourceStatus::Exited { attempt: 2, max: 2 },
+                SourceStatus::Restarted,
+                SourceStatus::GaveUp,
+            ]
+        );
+        runner.shutdown();
+    }
+
+    #[test]
+    fn reports_stderr_of_child_that_dies_at_startup() {
+        let policy = RestartPolicy {
+            max_restarts: 0,
+            backoff: Duration::from_millis(1),
+        };
+        let mut runner = TegrastatsRunner::spawn_with(
+            || {
+                Ok(Command::new("sh")
+                    .arg("-c")
+                    .arg("echo 'tegrastats: must be run as root' >&2; exit 1")
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()?)
+            },
+            policy,
+            None,
+        )
+        .expect("spawn");
+
+        let mut statuses = Vec::new();
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while statuses.last() != Some(&SourceStatus::GaveUp) && std::time::Instant::now() < deadline
+        {
+            match runner.try_status() {
+                Some(status) => statuses.push(status),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                SourceStatus::StartupFailed("tegrastats: must be run as root".to_string()),
+                SourceStatus::GaveUp,
+            ]
+        );
+        runner.shutdown();
     }
 }
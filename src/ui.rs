@@ -1,37 +1,102 @@
+use std::cmp::Ordering;
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 use chrono::Local;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::symbols;
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap};
 use ratatui::Frame;
 
-use crate::model::{AppState, HoverTarget, StatsSnapshot, UiButton, UiButtons};
+use crate::keys::{KEY_BINDINGS, MOUSE_HINTS};
+use crate::model::{
+    battery_runtime, percentile, temp_filter_keeps, AppState, ColorDepth, ColorScheme, CpuAggregate,
+    FanReading, HeatBounds, HoverTarget, Pane, PowerSummary, Rgb, SourceField, SourcesEditor,
+    SparkMetric, SparkScale, StatsSnapshot, TempRange, TempSort, TempUnit, TimedSeries, UiArea,
+    UiButton, UiButtons, VIN_AVERAGE_WINDOW,
+};
+
+/// Smallest terminal the header, footer and five-panel body fit in without
+/// borders drawing over each other.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 20;
 
 pub fn draw(frame: &mut Frame, app: &mut AppState) {
     let size = frame.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        // Nothing clickable is drawn, so stale header buttons must not match.
+        app.buttons = UiButtons::default();
+        render_too_small(frame, size);
+        return;
+    }
     let sections = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .constraints([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)])
         .split(size);
 
     render_header(frame, sections[0], app);
     render_body(frame, sections[1], app);
+    render_footer(frame, sections[2], app);
 
     if app.show_help {
-        render_help(frame, size);
+        render_help(frame, size, app);
     }
+
+    if let Some(editor) = app.sources_editor.as_ref() {
+        render_sources_editor(frame, size, editor, app.ascii);
+    }
+}
+
+fn render_too_small(frame: &mut Frame, area: Rect) {
+    let message = format!(
+        "terminal too small (need {}x{}, have {}x{})",
+        MIN_WIDTH, MIN_HEIGHT, area.width, area.height
+    );
+    // Start mid-screen and wrap, so narrow terminals still show all of it.
+    let rows = Rect {
+        y: area.y + area.height / 2,
+        height: area.height - area.height / 2,
+        ..area
+    };
+    frame.render_widget(
+        Paragraph::new(Line::styled(message, Style::default().fg(Color::Yellow)))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        rows,
+    );
 }
 
 fn render_header(frame: &mut Frame, area: Rect, app: &mut AppState) {
     app.buttons = UiButtons::default();
 
-    let left_line = Line::from(vec![
+    let mut left_spans = vec![
         Span::styled("jmon", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::raw("  q:quit  h:help  r:reset"),
-    ]);
+    ];
+    if app.stability.is_stable() {
+        left_spans.push(Span::raw("  "));
+        left_spans.push(Span::styled(
+            "STABLE",
+            Style::default().fg(Color::Black).bg(Color::Green).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.throttle_suspected() {
+        left_spans.push(Span::raw("  "));
+        left_spans.push(Span::styled(
+            "THROTTLE?",
+            Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.is_paused() {
+        left_spans.push(Span::raw("  "));
+        left_spans.push(Span::styled(
+            "PAUSED",
+            Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let left_line = Line::from(left_spans);
 
     let sections = Layout::default()
         .direction(Direction::Horizontal)
@@ -45,7 +110,11 @@ fn render_header(frame: &mut Frame, area: Rect, app: &mut AppState) {
     let header = Paragraph::new(left_line).alignment(Alignment::Left);
     frame.render_widget(header, sections[0]);
 
-    let time_string = Local::now().format("%I:%M:%S %p").to_string();
+    // Prefer the device-reported sample time, e.g. when replaying a log.
+    let time_string = match app.latest.as_ref().and_then(|snapshot| snapshot.timestamp) {
+        Some(timestamp) => timestamp.format("%I:%M:%S %p").to_string(),
+        None => Local::now().format("%I:%M:%S %p").to_string(),
+    };
     let time_line = Paragraph::new(Line::from(time_string)).alignment(Alignment::Center);
     frame.render_widget(time_line, sections[1]);
 
@@ -56,9 +125,32 @@ fn render_interval_controls(frame: &mut Frame, area: Rect, app: &mut AppState) {
     let label = "interval";
     let minus = "[-]";
     let plus = "[+]";
-    let interval_text = format!("{}ms", app.interval_ms);
-    let control_len = (label.len() + 1 + minus.len() + 1 + interval_text.len() + 1 + plus.len())
-        as u16;
+    let interval_text = match &app.interval_input {
+        Some(input) => format!("{}_ms", input),
+        None => format_interval(app.interval_ms),
+    };
+    let interval_style = if app.interval_input.is_some() {
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    let effective_text = app
+        .effective_interval()
+        .map(|effective| format!(" effective: ~{}ms", effective.as_millis()))
+        .unwrap_or_default();
+    let effective_style = if app.sample_rate_lagging() {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    let control_len = (label.len()
+        + 1
+        + minus.len()
+        + 1
+        + interval_text.len()
+        + 1
+        + plus.len()
+        + effective_text.len()) as u16;
 
     let sections = Layout::default()
         .direction(Direction::Horizontal)
@@ -83,8 +175,11 @@ fn render_interval_controls(frame: &mut Frame, area: Rect, app: &mut AppState) {
     let line = Line::from(vec![
         Span::raw(format!("{} ", label)),
         Span::styled(minus, minus_style),
-        Span::raw(format!(" {} ", interval_text)),
+        Span::raw(" "),
+        Span::styled(interval_text.clone(), interval_style),
+        Span::raw(" "),
         Span::styled(plus, plus_style),
+        Span::styled(effective_text, effective_style),
     ]);
     let paragraph = Paragraph::new(line).alignment(Alignment::Left);
     frame.render_widget(paragraph, sections[1]);
@@ -109,18 +204,100 @@ fn render_interval_controls(frame: &mut Frame, area: Rect, app: &mut AppState) {
         });
     }
 
-    if let Some(error) = &app.error {
+    if let Some(readout) = cursor_readout(app) {
+        let readout_line = Paragraph::new(Line::from(Span::styled(
+            readout,
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )))
+        .alignment(Alignment::Right);
+        frame.render_widget(readout_line, sections[0]);
+    } else if let Some(toast) = app.active_toast() {
+        let toast_line = Paragraph::new(Line::from(Span::styled(
+            toast.to_string(),
+            Style::default().fg(Color::Green),
+        )))
+        .alignment(Alignment::Right);
+        frame.render_widget(toast_line, sections[0]);
+    } else if let Some(notice) = &app.notice {
+        let notice_line = Paragraph::new(Line::from(Span::styled(
+            format!("notice: {}", notice),
+            Style::default().fg(Color::Yellow),
+        )))
+        .alignment(Alignment::Right);
+        frame.render_widget(notice_line, sections[0]);
+    }
+}
+
+/// Active modes on the left and the latest error, with the time it was
+/// raised, on the right.
+fn render_footer(frame: &mut Frame, area: Rect, app: &AppState) {
+    let mut modes = vec![
+        format!("interval {}", format_interval(app.interval_ms)),
+        format!("temps {}", app.temp_unit.suffix()),
+        format!("power {}", app.power_unit.suffix()),
+    ];
+    if app.is_paused() {
+        modes.push("paused".to_string());
+    }
+    if app.ascii {
+        modes.push("ascii".to_string());
+    }
+    let modes = modes.join("  ");
+
+    let sections = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(modes.len() as u16 + 2), Constraint::Min(0)])
+        .split(area);
+    frame.render_widget(
+        Paragraph::new(Line::styled(modes, Style::default().fg(Color::Gray))),
+        sections[0],
+    );
+
+    if let Some((error, at)) = app.visible_error() {
         let error_line = Paragraph::new(Line::from(Span::styled(
-            format!("error: {}", error),
+            format!("{} error: {}", at.format("%H:%M:%S"), error),
             Style::default().fg(Color::Red),
         )))
         .alignment(Alignment::Right);
-        frame.render_widget(error_line, sections[0]);
+        frame.render_widget(error_line, sections[1]);
+    }
+}
+
+fn format_interval(interval_ms: u64) -> String {
+    if interval_ms >= 1000 && interval_ms.is_multiple_of(1000) {
+        format!("{}s", interval_ms / 1000)
+    } else {
+        format!("{}ms", interval_ms)
     }
 }
 
-fn render_body(frame: &mut Frame, area: Rect, app: &AppState) {
-    let has_left = app.panes.cpu || app.panes.ram;
+fn render_body(frame: &mut Frame, area: Rect, app: &mut AppState) {
+    if app.table_view {
+        render_table_view(frame, area, app);
+        return;
+    }
+
+    if let Some(pane) = app.focused_pane {
+        app.buttons.panes.push((pane, ui_area(area)));
+        match pane {
+            Pane::Cpu => render_cpu_panel(frame, area, app),
+            Pane::Ram if app.ram_detail => render_ram_detail_panel(frame, area, app),
+            Pane::Ram => render_ram_panel(frame, area, app),
+            Pane::Swap => render_swap_panel(frame, area, app),
+            Pane::Gpu => render_gpu_panel(frame, area, app),
+            Pane::Temps => app.temps_scroll = render_temps_panel(frame, area, app),
+            Pane::Power => app.power_scroll = render_power_panel(frame, area, app),
+        }
+        return;
+    }
+
+    if app.ram_detail && app.panes.ram {
+        app.buttons.panes.push((Pane::Ram, ui_area(area)));
+        render_ram_detail_panel(frame, area, app);
+        return;
+    }
+
+    let has_left = app.panes.cpu || app.panes.ram || app.panes.swap;
     let has_right = app.panes.gpu || app.panes.temps || app.panes.power;
 
     if !has_left && !has_right {
@@ -148,23 +325,138 @@ fn render_body(frame: &mut Frame, area: Rect, app: &AppState) {
     }
 }
 
-fn render_left_column(frame: &mut Frame, area: Rect, app: &AppState) {
-    match (app.panes.cpu, app.panes.ram) {
-        (true, true) => {
-            let left = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
-                .split(area);
-            render_cpu_panel(frame, left[0], app);
-            render_ram_panel(frame, left[1], app);
+fn render_table_view(frame: &mut Frame, area: Rect, app: &AppState) {
+    let block = panel_block(app.ascii).title("Metrics");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let Some(snapshot) = app.latest.as_ref() else {
+        frame.render_widget(Paragraph::new("Waiting for tegrastats..."), inner);
+        return;
+    };
+
+    let rows = table_rows(snapshot, app);
+    let visible = inner.height as usize;
+    let offset = app.table_scroll.min(rows.len().saturating_sub(visible));
+    let key_width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0) as u16;
+
+    let table = Table::new(
+        rows.into_iter()
+            .skip(offset)
+            .map(|(key, value)| {
+                Row::new(vec![
+                    Cell::from(key).style(Style::default().fg(Color::Gray)),
+                    Cell::from(value),
+                ])
+            }),
+        [Constraint::Length(key_width), Constraint::Min(0)],
+    )
+    .column_spacing(2);
+    frame.render_widget(table, inner);
+}
+
+pub fn table_row_count(app: &AppState) -> usize {
+    app.latest
+        .as_ref()
+        .map_or(0, |snapshot| table_rows(snapshot, app).len())
+}
+
+fn table_rows(snapshot: &StatsSnapshot, app: &AppState) -> Vec<(String, String)> {
+    let percent = |value: Option<f32>| {
+        value
+            .map(|value| format!("{:.0}%", value))
+            .unwrap_or_else(|| "N/A".to_string())
+    };
+    let used_total = |used: Option<u64>, total: Option<u64>| match (used, total) {
+        (Some(used), Some(total)) => format!("{}/{}MB", used, total),
+        _ => "N/A".to_string(),
+    };
+
+    let cpu_total = if snapshot.cpu_unparseable {
+        "unparseable".to_string()
+    } else {
+        percent(snapshot.cpu_total(app.cpu_aggregate))
+    };
+    let mut rows = vec![("CPU total".to_string(), cpu_total)];
+    for (idx, util) in snapshot.cpu_cores.iter().enumerate() {
+        let value = if snapshot.core_online(idx) {
+            percent(Some(*util))
+        } else {
+            "offline".to_string()
+        };
+        rows.push((format!("CPU C{:02}", idx), value));
+    }
+    rows.push((
+        "RAM".to_string(),
+        used_total(snapshot.ram_used_mb, snapshot.ram_total_mb),
+    ));
+    rows.push((
+        "Swap".to_string(),
+        used_total(snapshot.swap_used_mb, snapshot.swap_total_mb),
+    ));
+    rows.push(("GPU".to_string(), percent(snapshot.gpu_util)));
+    if snapshot.gpu_mem_total_mb.is_some() {
+        rows.push((
+            "GPU mem".to_string(),
+            used_total(snapshot.gpu_mem_used_mb, snapshot.gpu_mem_total_mb),
+        ));
+    }
+    rows.push(("EMC".to_string(), percent(snapshot.emc_util)));
+    for temp in &snapshot.temps {
+        rows.push((
+            format!("Temp {}", app.sensor_label(&temp.name)),
+            if app.temp_range.contains(temp.value_c) {
+                app.temp_unit.format(temp.value_c)
+            } else {
+                "?".to_string()
+            },
+        ));
+    }
+    if let Some(total) = snapshot.total_power_mw(&app.history.power_summary) {
+        rows.push(("Power total".to_string(), app.power_unit.format(total)));
+    }
+    for rail in &snapshot.power_rails {
+        rows.push((
+            format!("Power {}", app.sensor_label(&rail.name)),
+            format!(
+                "{} / {} avg",
+                app.power_unit.format(rail.current_mw),
+                app.power_unit.format(rail.average_mw)
+            ),
+        ));
+    }
+    rows
+}
+
+fn render_left_column(frame: &mut Frame, area: Rect, app: &mut AppState) {
+    let panels: Vec<(PaneKind, u32)> = [
+        (PaneKind::Cpu, app.panes.cpu, 65),
+        (PaneKind::Ram, app.panes.ram, 35),
+        (PaneKind::Swap, app.panes.swap, 20),
+    ]
+    .into_iter()
+    .filter(|(_, visible, _)| *visible)
+    .map(|(pane, _, weight)| (pane, weight))
+    .collect();
+
+    let total: u32 = panels.iter().map(|(_, weight)| weight).sum();
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(panels.iter().map(|(_, weight)| Constraint::Ratio(*weight, total)))
+        .split(area);
+
+    for ((pane, _), section) in panels.into_iter().zip(sections.iter()) {
+        app.buttons.panes.extend(pane.pane().map(|pane| (pane, ui_area(*section))));
+        match pane {
+            PaneKind::Cpu => render_cpu_panel(frame, *section, app),
+            PaneKind::Ram => render_ram_panel(frame, *section, app),
+            PaneKind::Swap => render_swap_panel(frame, *section, app),
+            _ => {}
         }
-        (true, false) => render_cpu_panel(frame, area, app),
-        (false, true) => render_ram_panel(frame, area, app),
-        (false, false) => {}
     }
 }
 
-fn render_right_column(frame: &mut Frame, area: Rect, app: &AppState) {
+fn render_right_column(frame: &mut Frame, area: Rect, app: &mut AppState) {
     let mut panels = Vec::new();
     if app.panes.gpu {
         panels.push(PaneKind::Gpu);
@@ -175,21 +467,19 @@ fn render_right_column(frame: &mut Frame, area: Rect, app: &AppState) {
     if app.panes.power {
         panels.push(PaneKind::Power);
     }
+    if app
+        .latest
+        .as_ref()
+        .is_some_and(|snapshot| !snapshot.extra.is_empty())
+    {
+        panels.push(PaneKind::Extra);
+    }
 
     if panels.is_empty() {
         return;
     }
 
-    let constraints = match panels.len() {
-        1 => vec![Constraint::Min(0)],
-        2 => vec![Constraint::Percentage(50), Constraint::Percentage(50)],
-        _ => vec![
-            Constraint::Percentage(35),
-            Constraint::Percentage(25),
-            Constraint::Percentage(40),
-        ],
-    };
-
+    let constraints = right_column_constraints(panels.len());
     let sections = Layout::default()
         .direction(Direction::Vertical)
         .constraints(constraints)
@@ -197,61 +487,268 @@ fn render_right_column(frame: &mut Frame, area: Rect, app: &AppState) {
 
     for (idx, pane) in panels.into_iter().enumerate() {
         if let Some(section) = sections.get(idx) {
+            app.buttons.panes.extend(pane.pane().map(|pane| (pane, ui_area(*section))));
             match pane {
                 PaneKind::Gpu => render_gpu_panel(frame, *section, app),
-                PaneKind::Temps => render_temps_panel(frame, *section, app),
-                PaneKind::Power => render_power_panel(frame, *section, app),
+                PaneKind::Temps => app.temps_scroll = render_temps_panel(frame, *section, app),
+                PaneKind::Power => app.power_scroll = render_power_panel(frame, *section, app),
+                PaneKind::Extra => render_extra_panel(frame, *section, app),
+                _ => {}
             }
         }
     }
 }
 
+/// One vertical constraint per visible right-column pane.
+fn right_column_constraints(visible: usize) -> Vec<Constraint> {
+    match visible {
+        0 => Vec::new(),
+        1 => vec![Constraint::Min(0)],
+        2 => vec![Constraint::Percentage(50), Constraint::Percentage(50)],
+        3 => vec![
+            Constraint::Percentage(35),
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+        ],
+        _ => vec![
+            Constraint::Percentage(28),
+            Constraint::Percentage(20),
+            Constraint::Percentage(32),
+            Constraint::Percentage(20),
+        ],
+    }
+}
+
+/// Height of a panel's fixed sparkline: three rows, or half the panel when zoomed.
+fn spark_constraint(app: &AppState, pane: Pane) -> Constraint {
+    if app.focused_pane == Some(pane) {
+        Constraint::Percentage(50)
+    } else {
+        Constraint::Length(3)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum PaneKind {
+    Cpu,
+    Ram,
+    Swap,
     Gpu,
     Temps,
     Power,
+    Extra,
+}
+
+impl PaneKind {
+    /// The selectable pane this is; the Extra panel is not one.
+    fn pane(self) -> Option<Pane> {
+        match self {
+            PaneKind::Cpu => Some(Pane::Cpu),
+            PaneKind::Ram => Some(Pane::Ram),
+            PaneKind::Swap => Some(Pane::Swap),
+            PaneKind::Gpu => Some(Pane::Gpu),
+            PaneKind::Temps => Some(Pane::Temps),
+            PaneKind::Power => Some(Pane::Power),
+            PaneKind::Extra => None,
+        }
+    }
+}
+
+fn ui_area(rect: Rect) -> UiArea {
+    UiArea {
+        x: rect.x,
+        y: rect.y,
+        width: rect.width,
+        height: rect.height,
+    }
 }
 
 fn render_cpu_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let palette = Palette::from_app(app);
     let title = pane_title(
         1,
         "CPU",
         app.latest
             .as_ref()
-            .and_then(StatsSnapshot::cpu_total)
-            .map(|total| format!("{:.0}%", total)),
-        Color::Green,
+            .and_then(|snapshot| snapshot.cpu_total(app.cpu_aggregate))
+            .map(|total| match app.cpu_aggregate {
+                CpuAggregate::Mean => format!("{:.0}%", total),
+                CpuAggregate::Max => format!("max {:.0}%", total),
+                CpuAggregate::ActiveMean => format!("active {:.0}%", total),
+            }),
+        palette.accent(palette.hues.cpu),
     );
 
-    let block = Block::default().title(title).borders(Borders::ALL);
+    let block = pane_block(app, Pane::Cpu).title(with_smoothing(title, app));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let footer_height = u16::from(inner.height >= 7);
     let sections = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .constraints([
+            Constraint::Min(3),
+            spark_constraint(app, Pane::Cpu),
+            Constraint::Length(footer_height),
+        ])
         .split(inner);
 
-    let core_lines = match app.latest.as_ref() {
-        Some(snapshot) if !snapshot.cpu_cores.is_empty() => snapshot
-            .cpu_cores
-            .iter()
-            .enumerate()
-            .map(|(idx, util)| core_bar_line(idx, *util, sections[0].width, SparkRgb::cpu()))
-            .collect(),
-        Some(_) => vec![Line::from("No CPU data")],
-        None => vec![Line::from("Waiting for tegrastats...")],
-    };
+    let has_cores = app
+        .latest
+        .as_ref()
+        .is_some_and(|snapshot| !snapshot.cpu_cores.is_empty());
+    if app.cpu_heatmap && has_cores {
+        render_cpu_heatmap(frame, sections[0], app, palette);
+    } else if let Some(snapshot) = app.latest.as_ref().filter(|_| has_cores)
+        && app.cpu_bars_per_row <= 1
+    {
+        render_core_columns(frame, sections[0], snapshot, app, palette);
+    } else {
+        let core_lines = match app.latest.as_ref() {
+            Some(snapshot) if !snapshot.cpu_cores.is_empty() && app.cpu_bars_per_row > 1 => {
+                compact_core_lines(
+                    &snapshot.cpu_cores,
+                    &snapshot.cpu_online,
+                    app.cpu_bars_per_row,
+                    sections[0].width,
+                    palette,
+                    palette.hues.cpu,
+                )
+            }
+            Some(snapshot) if snapshot.cpu_unparseable => {
+                vec![Line::from("CPU data unparseable")]
+            }
+            Some(_) => vec![Line::from("No CPU data")],
+            None => vec![Line::from("Waiting for tegrastats...")],
+        };
+
+        let core_list = Paragraph::new(core_lines).alignment(Alignment::Left);
+        frame.render_widget(core_list, sections[0]);
+    }
 
-    let core_list = Paragraph::new(core_lines).alignment(Alignment::Left);
-    frame.render_widget(core_list, sections[0]);
+    let (spark_area, axis_area) = split_time_axis(sections[1]);
+    let cpu_spark = graph_sparkline(
+        app,
+        &app.history.cpu_total,
+        spark_samples(palette, spark_area.width),
+    );
+    let cpu_color = spark_color(app, SparkMetric::Cpu, palette.hues.cpu);
+    render_sparkline(frame, spark_area, &cpu_spark, palette, cpu_color, Some(100));
+    mark_anomalies(frame, spark_area, app, SparkMetric::Cpu, palette);
+    mark_cursor(frame, spark_area, app);
+    render_time_axis(frame, axis_area, app);
+    let cpu_visible = visible_samples(
+        app,
+        &app.history.cpu_total,
+        spark_samples(palette, spark_area.width),
+    );
+    render_util_footer(frame, sections[2], app, &cpu_visible);
+}
+
+/// Narrowest column a full `C00 [bar] 100%` line still reads well in.
+const MIN_CORE_COLUMN_WIDTH: u16 = 24;
+
+/// One bar per core, split into side-by-side columns filled top to bottom
+/// when the cores don't fit the height and the panel is wide enough.
+fn render_core_columns(
+    frame: &mut Frame,
+    area: Rect,
+    snapshot: &StatsSnapshot,
+    app: &AppState,
+    palette: Palette,
+) {
+    let freq_width = if app.show_cpu_freq { " @1566MHz".len() as u16 } else { 0 };
+    let columns = core_columns(
+        snapshot.cpu_cores.len(),
+        area.height,
+        area.width,
+        MIN_CORE_COLUMN_WIDTH + freq_width,
+    );
+    let per_column = snapshot.cpu_cores.len().div_ceil(columns);
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+        .split(area);
+
+    for (column, column_area) in areas.iter().enumerate() {
+        let first = column * per_column;
+        let lines: Vec<Line> = (first..(first + per_column).min(snapshot.cpu_cores.len()))
+            .map(|idx| {
+                let util = Some(snapshot.cpu_cores[idx]).filter(|_| snapshot.core_online(idx));
+                let freq_mhz = snapshot.cpu_freqs_mhz.get(idx).copied().flatten();
+                let freq_mhz = freq_mhz.filter(|_| app.show_cpu_freq);
+                core_bar_line(idx, util, freq_mhz, column_area.width, palette, palette.hues.cpu)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), *column_area);
+    }
+}
+
+/// How many columns `cores` bars need to fit in `height` rows, limited to
+/// as many `min_width` columns as fit across `width`.
+fn core_columns(cores: usize, height: u16, width: u16, min_width: u16) -> usize {
+    let needed = cores.div_ceil((height as usize).max(1)).max(1);
+    let fit = (width / min_width.max(1)).max(1) as usize;
+    needed.min(fit)
+}
+
+/// Rows are cores and columns are samples (newest on the right). When there are
+/// more cores than rows, neighbouring cores share a row showing their busiest value.
+fn render_cpu_heatmap(frame: &mut Frame, area: Rect, app: &AppState, palette: Palette) {
+    let cores = &app.history.cpu_cores;
+    if area.is_empty() || cores.is_empty() {
+        return;
+    }
+
+    let rows = heatmap_rows(cores.len(), area.height as usize);
+    let label_width = 8u16.min(area.width);
+    let grid_width = area.width.saturating_sub(label_width);
+    let buffer = frame.buffer_mut();
+
+    for (row, group) in rows.iter().enumerate() {
+        let y = area.top() + row as u16;
+        let label = if group.len() == 1 {
+            format!("C{:02}", group.start)
+        } else {
+            format!("C{:02}-{:02}", group.start, group.end - 1)
+        };
+        buffer.set_stringn(
+            area.left(),
+            y,
+            label,
+            label_width as usize,
+            Style::default().fg(Color::Gray),
+        );
+
+        let series: Vec<Vec<u64>> = cores[group.clone()]
+            .iter()
+            .map(|core| series_sparkline(app, core, grid_width))
+            .collect();
+        for col in 0..grid_width as usize {
+            let util = series.iter().map(|values| values[col]).max().unwrap_or(0);
+            let color = scaled_color(palette, palette.hues.cpu, util as f64);
+            buffer
+                .get_mut(area.left() + label_width + col as u16, y)
+                .set_symbol(palette.glyph(8))
+                .set_style(Style::default().fg(color));
+        }
+    }
+}
 
-    let cpu_spark = sparkline_data(&app.history.cpu_total, sections[1].width);
-    render_sparkline(frame, sections[1], &cpu_spark, SparkRgb::cpu(), Some(100));
+/// Splits `cores` into at most `height` contiguous, evenly sized row groups.
+fn heatmap_rows(cores: usize, height: usize) -> Vec<std::ops::Range<usize>> {
+    if cores == 0 || height == 0 {
+        return Vec::new();
+    }
+    let per_row = cores.div_ceil(height);
+    (0..cores)
+        .step_by(per_row)
+        .map(|start| start..(start + per_row).min(cores))
+        .collect()
 }
 
 fn render_ram_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let palette = Palette::from_app(app);
     let title = pane_title(
         2,
         "RAM",
@@ -259,10 +756,10 @@ fn render_ram_panel(frame: &mut Frame, area: Rect, app: &AppState) {
             .as_ref()
             .and_then(StatsSnapshot::ram_percent)
             .map(|percent| format!("{:.0}%", percent)),
-        Color::Yellow,
+        palette.accent(palette.hues.ram),
     );
 
-    let block = Block::default().title(title).borders(Borders::ALL);
+    let block = pane_block(app, Pane::Ram).title(with_smoothing(title, app));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
@@ -271,158 +768,732 @@ fn render_ram_panel(frame: &mut Frame, area: Rect, app: &AppState) {
         .constraints([Constraint::Length(2), Constraint::Min(3)])
         .split(inner);
 
-    let line = match app.latest.as_ref() {
-        Some(snapshot) => memory_bar_line(snapshot, sections[0].width, SparkRgb::ram()),
-        None => Line::from("Waiting for tegrastats..."),
+    let lines = match app.latest.as_ref() {
+        Some(snapshot) => {
+            let width = sections[0].width;
+            let mut lines =
+                vec![memory_bar_line(snapshot, width, palette, palette.hues.ram, app.ram_free)];
+            lines.extend(lfb_line(snapshot, app.ascii));
+            lines
+        }
+        None => vec![Line::from("Waiting for tegrastats...")],
+    };
+    frame.render_widget(Paragraph::new(lines), sections[0]);
+
+    let show_lfb = app.show_lfb && !app.history.lfb_mb.values.is_empty();
+    let spark_area = if show_lfb {
+        let spark_sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(sections[1]);
+        render_lfb_sparkline(frame, spark_sections[1], app, palette);
+        spark_sections[0]
+    } else {
+        sections[1]
     };
-    frame.render_widget(Paragraph::new(line), sections[0]);
 
-    let ram_spark = sparkline_data(&app.history.ram_used, sections[1].width);
+    let (spark_area, footer_area) = split_stats_footer(spark_area);
+    let ram_spark = graph_sparkline(
+        app,
+        &app.history.ram_used,
+        spark_samples(palette, spark_area.width),
+    );
     let ram_max = app.latest.as_ref().and_then(|snapshot| snapshot.ram_total_mb);
-    render_sparkline(frame, sections[1], &ram_spark, SparkRgb::ram(), ram_max);
+    let ram_color = spark_color(app, SparkMetric::Ram, palette.hues.ram);
+    render_sparkline(frame, spark_area, &ram_spark, palette, ram_color, ram_max);
+    mark_anomalies(frame, spark_area, app, SparkMetric::Ram, palette);
+    mark_cursor(frame, spark_area, app);
+    if let Some(footer_area) = footer_area {
+        let ram_visible = visible_samples(
+            app,
+            &app.history.ram_used,
+            spark_samples(palette, spark_area.width),
+        );
+        render_stats_footer(frame, footer_area, &ram_visible, |value| format!("{:.0}MB", value));
+    }
 }
 
-fn render_gpu_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+/// Fragmentation summary, e.g. `LFB: 79×4MB (316MB)`, when the line has it.
+fn lfb_line(snapshot: &StatsSnapshot, ascii: bool) -> Option<Line<'static>> {
+    let (Some(blocks), Some(size), Some(total)) =
+        (snapshot.lfb_blocks, snapshot.lfb_block_mb, snapshot.lfb_mb())
+    else {
+        return None;
+    };
+    let times = if ascii { 'x' } else { '\u{d7}' };
+    Some(Line::from(vec![
+        Span::styled("LFB:", Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {}{}{}MB ({}MB)", blocks, times, size, total)),
+    ]))
+}
+
+/// Input draw readout, e.g. `VIN 12.3W avg 11.8W ~2h15m left`, with the
+/// estimate only when `--battery-wh` is set.
+fn vin_line(app: &AppState, snapshot: &StatsSnapshot) -> Line<'static> {
+    let label = Span::styled("VIN", Style::default().fg(Color::Gray));
+    let Some(current) = snapshot.total_power_mw(&PowerSummary::InputRail) else {
+        let missing = if app.ascii { " -" } else { " \u{2014}" };
+        return Line::from(vec![label, Span::raw(missing)]);
+    };
+    let average = app.history.vin_mw.recent_mean(VIN_AVERAGE_WINDOW).unwrap_or(current);
+    let mut spans = vec![
+        label,
+        Span::raw(format!(" {}", app.power_unit.format(current))),
+        Span::styled(
+            format!(" avg {}", app.power_unit.format(average)),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if let Some(runtime) = app
+        .battery_wh
+        .and_then(|capacity| battery_runtime(capacity, average))
+    {
+        spans.push(Span::raw(format!(" ~{} left", format_runtime(runtime))));
+    }
+    Line::from(spans)
+}
+
+fn render_lfb_sparkline(frame: &mut Frame, area: Rect, app: &AppState, palette: Palette) {
+    let label = match app.latest.as_ref().and_then(StatsSnapshot::lfb_mb) {
+        Some(lfb_mb) => format!("largest free block {}MB", lfb_mb),
+        None => "largest free block".to_string(),
+    };
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(area);
+    frame.render_widget(
+        Paragraph::new(Line::styled(label, Style::default().fg(Color::Gray))),
+        sections[0],
+    );
+
+    let lfb_spark = graph_sparkline(
+        app,
+        &app.history.lfb_mb,
+        spark_samples(palette, sections[1].width),
+    );
+    let lfb_max = app.history.lfb_mb.values.iter().copied().max();
+    let lfb_color = SparkColor::Intensity(palette.hues.lfb);
+    render_sparkline(frame, sections[1], &lfb_spark, palette, lfb_color, lfb_max);
+}
+
+fn render_swap_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let palette = Palette::from_app(app);
+    let swap = app
+        .latest
+        .as_ref()
+        .and_then(|snapshot| snapshot.swap_used_mb.zip(snapshot.swap_total_mb))
+        .filter(|(_, total)| *total > 0);
     let title = pane_title(
-        3,
-        "GPU",
-        app.latest
-            .as_ref()
-            .and_then(|snap| snap.gpu_util)
-            .map(|util| format!("{:.0}%", util)),
-        Color::Cyan,
+        6,
+        "Swap",
+        swap.map(|(used, total)| format!("{:.0}%", used as f64 / total as f64 * 100.0)),
+        palette.accent(palette.hues.swap),
     );
 
-    let block = Block::default().title(title).borders(Borders::ALL);
+    let block = pane_block(app, Pane::Swap).title(with_smoothing(title, app));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
     let sections = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(2), Constraint::Length(3)])
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
         .split(inner);
 
-    let mut lines = Vec::new();
-    if let Some(snapshot) = app.latest.as_ref() {
-        if let Some(util) = snapshot.gpu_util {
-            lines.push(bar_line("GPU", util, sections[0].width, SparkRgb::gpu()));
-        } else {
-            lines.push(Line::from("GPU: N/A"));
-        }
-
-        if let Some(emc) = snapshot.emc_util {
-            lines.push(bar_line("EMC", emc, sections[0].width, SparkRgb::emc()));
+    let line = match (app.latest.as_ref(), swap) {
+        (Some(_), Some((used, total))) => {
+            mb_bar_line("Swap", used, total, sections[0].width, palette, palette.hues.swap)
         }
-    } else {
-        lines.push(Line::from("Waiting for tegrastats..."));
-    }
-
-    frame.render_widget(Paragraph::new(lines), sections[0]);
+        (Some(_), None) => Line::from("Swap: none"),
+        (None, _) => Line::from("Waiting for tegrastats..."),
+    };
+    frame.render_widget(Paragraph::new(line), sections[0]);
 
-    let gpu_spark = sparkline_data(&app.history.gpu_util, sections[1].width);
-    render_sparkline(frame, sections[1], &gpu_spark, SparkRgb::gpu(), Some(100));
+    let swap_spark = graph_sparkline(
+        app,
+        &app.history.swap_used,
+        spark_samples(palette, sections[1].width),
+    );
+    let swap_max = swap.map(|(_, total)| total);
+    let swap_color = SparkColor::Intensity(palette.hues.swap);
+    render_sparkline(frame, sections[1], &swap_spark, palette, swap_color, swap_max);
 }
 
-fn render_power_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+fn render_ram_detail_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let palette = Palette::from_app(app);
     let title = pane_title(
-        5,
-        "Power",
+        2,
+        "RAM detail",
         app.latest
             .as_ref()
-            .and_then(StatsSnapshot::total_power_mw)
-            .map(|total| format!("{}mW", total)),
-        Color::LightRed,
+            .and_then(StatsSnapshot::ram_percent)
+            .map(|percent| format!("{:.0}%", percent)),
+        palette.accent(palette.hues.ram),
     );
 
-    let block = Block::default().title(title).borders(Borders::ALL);
+    let block = pane_block(app, Pane::Ram).title(with_smoothing(title, app));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
     let sections = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(3), Constraint::Length(3)])
+        .constraints([Constraint::Length(5), Constraint::Min(3)])
         .split(inner);
 
-    let total_line = match app.latest.as_ref().and_then(StatsSnapshot::total_power_mw) {
-        Some(total) => {
-            let max_power = app
-                .history
-                .power_total
-                .iter()
-                .copied()
-                .max()
-                .unwrap_or(total)
-                .max(1);
-            let percent = (total as f64 / max_power as f64) * 100.0;
-            power_bar_line(total, percent, sections[0].width, SparkRgb::power())
+    let width = sections[0].width;
+    let lines = match app.latest.as_ref() {
+        Some(snapshot) => {
+            let mut lines = Vec::new();
+            match (snapshot.ram_used_mb, snapshot.ram_total_mb) {
+                (Some(used), Some(total)) if total > 0 => {
+                    lines.push(mb_bar_line("Used", used, total, width, palette, palette.hues.ram));
+                    lines.push(mb_bar_line(
+                        "Free",
+                        total.saturating_sub(used),
+                        total,
+                        width,
+                        palette,
+                        palette.hues.ram,
+                    ));
+                }
+                _ => lines.push(Line::from("RAM data unavailable")),
+            }
+            match (snapshot.swap_used_mb, snapshot.swap_total_mb) {
+                (Some(used), Some(total)) if total > 0 => {
+                    lines.push(mb_bar_line("Swap", used, total, width, palette, palette.hues.ram));
+                }
+                _ => lines.push(Line::from("Swap: none")),
+            }
+            match (snapshot.lfb_blocks, snapshot.lfb_block_mb, snapshot.lfb_mb()) {
+                (Some(blocks), Some(size), Some(total)) => lines.push(Line::from(format!(
+                    "LFB  {}x{}MB ({}MB largest free block)",
+                    blocks, size, total
+                ))),
+                _ => lines.push(Line::from("LFB: N/A")),
+            }
+            lines
         }
-        None => Line::from("Waiting for tegrastats..."),
+        None => vec![Line::from("Waiting for tegrastats...")],
     };
-    frame.render_widget(Paragraph::new(total_line), sections[0]);
+    frame.render_widget(Paragraph::new(lines), sections[0]);
 
-    let rail_lines = match app.latest.as_ref() {
-        Some(snapshot) if !snapshot.power_rails.is_empty() => snapshot
-            .power_rails
-            .iter()
-            .map(|rail| {
-                Line::from(format!(
-                    "{:<16} {:>6}mW / {:>6}mW",
-                    rail.name, rail.current_mw, rail.average_mw
-                ))
-            })
-            .collect(),
-        Some(_) => vec![Line::from("No power rails")],
-        None => vec![Line::from("Waiting for tegrastats...")],
+    let show_lfb = app.show_lfb && !app.history.lfb_mb.values.is_empty();
+    let spark_area = if show_lfb {
+        let spark_sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(sections[1]);
+        render_lfb_sparkline(frame, spark_sections[1], app, palette);
+        spark_sections[0]
+    } else {
+        sections[1]
     };
-    frame.render_widget(Paragraph::new(rail_lines), sections[1]);
 
-    let power_spark = sparkline_data(&app.history.power_total, sections[2].width);
-    render_sparkline(frame, sections[2], &power_spark, SparkRgb::power(), None);
+    let (spark_area, footer_area) = split_stats_footer(spark_area);
+    let ram_spark = graph_sparkline(
+        app,
+        &app.history.ram_used,
+        spark_samples(palette, spark_area.width),
+    );
+    let ram_max = app.latest.as_ref().and_then(|snapshot| snapshot.ram_total_mb);
+    let ram_color = spark_color(app, SparkMetric::Ram, palette.hues.ram);
+    render_sparkline(frame, spark_area, &ram_spark, palette, ram_color, ram_max);
+    mark_anomalies(frame, spark_area, app, SparkMetric::Ram, palette);
+    mark_cursor(frame, spark_area, app);
+    if let Some(footer_area) = footer_area {
+        let ram_visible = visible_samples(
+            app,
+            &app.history.ram_used,
+            spark_samples(palette, spark_area.width),
+        );
+        render_stats_footer(frame, footer_area, &ram_visible, |value| format!("{:.0}MB", value));
+    }
 }
 
-fn render_temps_panel(frame: &mut Frame, area: Rect, app: &AppState) {
-    let block = Block::default()
-        .title(pane_title(4, "Temps", None, Color::LightBlue))
-        .borders(Borders::ALL);
+fn render_gpu_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let palette = Palette::from_app(app);
+    let title = pane_title(
+        3,
+        "GPU",
+        app.latest
+            .as_ref()
+            .and_then(|snap| snap.gpu_util)
+            .map(|util| format!("{:.0}%", util)),
+        palette.accent(palette.hues.gpu),
+    );
+
+    let block = pane_block(app, Pane::Gpu).title(with_smoothing(title, app));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let lines = match app.latest.as_ref() {
-        Some(snapshot) if !snapshot.temps.is_empty() => snapshot
-            .temps
-            .iter()
-            .map(|temp| temp_line(&temp.name, temp.value_c))
-            .collect(),
-        Some(_) => vec![Line::from("No temps")],
-        None => vec![Line::from("Waiting for tegrastats...")],
-    };
-
-    frame.render_widget(Paragraph::new(lines), inner);
+    let emc_height = if app.history.emc_util.values.is_empty() { 0 } else { 2 };
+    let footer_height = u16::from(inner.height >= 6 + emc_height);
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(2),
+            spark_constraint(app, Pane::Gpu),
+            Constraint::Length(footer_height),
+            Constraint::Length(emc_height),
+        ])
+        .split(inner);
+
+    let mut lines = Vec::new();
+    if let Some(snapshot) = app.latest.as_ref() {
+        if snapshot.gpus.len() > 1 {
+            // Labels are padded so every GPU's bar lines up with the average's.
+            let width = sections[0].width;
+            for (index, gpu) in snapshot.gpus.iter().enumerate() {
+                let label = format!("GPU{:<2}", index);
+                lines.push(bar_line(&label, gpu.util, width, palette, palette.hues.gpu));
+            }
+        }
+        if let Some(util) = snapshot.gpu_util {
+            let label = match snapshot.gpus.len() {
+                0 => "GR3D",
+                1 => "GPU",
+                _ => "avg  ",
+            };
+            lines.push(bar_line(label, util, sections[0].width, palette, palette.hues.gpu));
+        } else if !app.gpu_unresponsive {
+            lines.push(Line::from("GPU: N/A"));
+        }
+        if app.gpu_unresponsive {
+            lines.push(Line::styled(
+                "GPU: nvidia-smi unresponsive",
+                Style::default().fg(Color::Red),
+            ));
+        }
+
+        if let (Some(used), Some(total)) = (snapshot.gpu_mem_used_mb, snapshot.gpu_mem_total_mb)
+            && total > 0
+        {
+            let width = sections[0].width;
+            lines.push(mb_bar_line("VRAM", used, total, width, palette, palette.hues.gpu));
+        }
+
+        for (index, gpu) in snapshot.gpus.iter().enumerate() {
+            let mut readings = Vec::new();
+            // Like the Temps list, an implausible reading shows as a dimmed `?`.
+            readings.extend(gpu.temp_c.map(|temp| {
+                if app.temp_range.contains(temp) {
+                    Span::raw(app.temp_unit.format(temp))
+                } else {
+                    Span::styled(
+                        "?",
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+                    )
+                }
+            }));
+            readings.extend(gpu.power_mw.map(|power| Span::raw(app.power_unit.format(power))));
+            if !readings.is_empty() {
+                let mut spans = vec![Span::styled(
+                    smi_label(snapshot, index),
+                    Style::default().fg(Color::Gray),
+                )];
+                for (position, reading) in readings.into_iter().enumerate() {
+                    spans.push(Span::raw(if position == 0 { " " } else { "  " }));
+                    spans.push(reading);
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        if let Some(emc) = snapshot.emc_util {
+            lines.push(bar_line("EMC", emc, sections[0].width, palette, palette.hues.emc));
+        }
+    } else {
+        lines.push(Line::from("Waiting for tegrastats..."));
+    }
+
+    frame.render_widget(Paragraph::new(lines), sections[0]);
+
+    let (spark_area, axis_area) = split_time_axis(sections[1]);
+    let gpu_spark = graph_sparkline(
+        app,
+        &app.history.gpu_util,
+        spark_samples(palette, spark_area.width),
+    );
+    let gpu_color = spark_color(app, SparkMetric::Gpu, palette.hues.gpu);
+    render_sparkline(frame, spark_area, &gpu_spark, palette, gpu_color, Some(100));
+    mark_anomalies(frame, spark_area, app, SparkMetric::Gpu, palette);
+    mark_cursor(frame, spark_area, app);
+    render_time_axis(frame, axis_area, app);
+    let gpu_visible = visible_samples(
+        app,
+        &app.history.gpu_util,
+        spark_samples(palette, spark_area.width),
+    );
+    render_util_footer(frame, sections[2], app, &gpu_visible);
+
+    if emc_height > 0 {
+        let emc_spark = graph_sparkline(
+            app,
+            &app.history.emc_util,
+            spark_samples(palette, sections[3].width),
+        );
+        let emc_color = SparkColor::Intensity(palette.hues.emc);
+        render_sparkline(frame, sections[3], &emc_spark, palette, emc_color, Some(100));
+    }
 }
 
-fn render_help(frame: &mut Frame, area: Rect) {
-    let help_area = centered_rect(60, 40, area);
-    let block = Block::default().title("Help").borders(Borders::ALL);
+/// Returns the rail list's scroll offset, clamped to what it shows.
+fn render_power_panel(frame: &mut Frame, area: Rect, app: &AppState) -> usize {
+    let palette = Palette::from_app(app);
+    let total = app
+        .latest
+        .as_ref()
+        .and_then(|snapshot| snapshot.total_power_mw(&app.history.power_summary));
+    let title_value = match (total, app.power_max_fixed) {
+        (Some(total), Some(ceiling)) => Some(format!(
+            "{} / {} max",
+            app.power_unit.format(total),
+            app.power_unit.format(ceiling)
+        )),
+        (Some(total), None) => Some(app.power_unit.format(total)),
+        (None, _) => None,
+    };
+    let mut name = match app.history.power_summary {
+        PowerSummary::SumComponents => "Power".to_string(),
+        _ => format!("Power ({})", app.sensor_label(app.history.power_summary.label())),
+    };
+    if app.power_scale == SparkScale::Log {
+        name.push_str(" [log]");
+    }
+    let title = pane_title(5, &name, title_value, palette.accent(palette.hues.power));
+
+    let block = pane_block(app, Pane::Power).title(with_smoothing(title, app));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let footer_height = u16::from(inner.height >= 9);
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(3),
+            spark_constraint(app, Pane::Power),
+            Constraint::Length(footer_height),
+        ])
+        .split(inner);
+
+    let total_line = match total {
+        Some(total) => {
+            let max_power = app
+                .power_max_fixed
+                .or_else(|| app.history.power_total.values.iter().copied().max())
+                .unwrap_or(total)
+                .max(1);
+            let percent = (total as f64 / max_power as f64) * 100.0;
+            let suffix = app.power_unit.format(total);
+            power_bar_line(&suffix, percent, sections[0].width, palette, palette.hues.power)
+        }
+        None => Line::from("Waiting for tegrastats..."),
+    };
+    let mut header = vec![total_line];
+    if let Some(snapshot) = app.latest.as_ref() {
+        header.push(vin_line(app, snapshot));
+    }
+    frame.render_widget(Paragraph::new(header), sections[0]);
+
+    let rail_lines = match app.latest.as_ref() {
+        Some(snapshot) => {
+            let mut lines: Vec<Line> = snapshot
+                .power_rails
+                .iter()
+                .map(|rail| {
+                    let color = rail_color(app, palette, &rail.name);
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:<16}", app.sensor_label(&rail.name)),
+                            Style::default().fg(color),
+                        ),
+                        Span::styled(
+                            format!(" {:>8}", app.power_unit.format(rail.current_mw)),
+                            Style::default().fg(color),
+                        ),
+                        Span::raw(format!(" / {:>8}", app.power_unit.format(rail.average_mw))),
+                    ])
+                })
+                .collect();
+            // nvidia-smi draw is not a tegrastats rail, so it stays out of the total.
+            for (index, gpu) in snapshot.gpus.iter().enumerate() {
+                if let Some(power) = gpu.power_mw {
+                    let label = smi_label(snapshot, index);
+                    lines.push(Line::from(format!(
+                        "{:<16} {:>8}",
+                        label,
+                        app.power_unit.format(power)
+                    )));
+                }
+            }
+            if lines.is_empty() {
+                lines.push(Line::from("No power rails"));
+            }
+            lines
+        }
+        None => vec![Line::from("Waiting for tegrastats...")],
+    };
+    let (rail_lines, scroll) =
+        scroll_lines(rail_lines, sections[1].height, app.power_scroll, app.ascii);
+    frame.render_widget(Paragraph::new(rail_lines), sections[1]);
+
+    let power_spark = graph_sparkline(
+        app,
+        &app.history.power_total,
+        spark_samples(palette, sections[2].width),
+    );
+    render_scaled_sparkline(
+        frame,
+        sections[2],
+        &power_spark,
+        palette,
+        spark_color(app, SparkMetric::Power, palette.hues.power),
+        app.power_max_fixed.or(app.power_max.get()),
+        app.power_scale,
+    );
+    mark_anomalies(frame, sections[2], app, SparkMetric::Power, palette);
+    mark_cursor(frame, sections[2], app);
+    let power_visible = visible_samples(
+        app,
+        &app.history.power_total,
+        spark_samples(palette, sections[2].width),
+    );
+    render_stats_footer(frame, sections[3], &power_visible, |value| {
+        app.power_unit.format(value.round() as u64)
+    });
+    scroll
+}
+
+/// Returns the list's scroll offset, clamped to what it shows.
+fn render_temps_panel(frame: &mut Frame, area: Rect, app: &AppState) -> usize {
+    let palette = Palette::from_app(app);
+    let mut title_value = format!("({})", app.temp_unit.suffix());
+    if let Some(sort) = app.temp_sort.label() {
+        title_value.push_str(&format!(" {}", sort));
+    }
+    match &app.temp_filter_input {
+        Some(input) => title_value.push_str(&format!(" /{}_", input)),
+        None if !app.temp_filter.is_empty() => {
+            title_value.push_str(&format!(" /{}", app.temp_filter));
+        }
+        None => {}
+    }
+    let mut block = pane_block(app, Pane::Temps).title(pane_title(
+        4,
+        "Temps",
+        Some(title_value),
+        palette.accent(palette.hues.cool),
+    ));
+    if app.throttle_suspected() && app.blink_on() {
+        block = block.border_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+    }
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines = match app.latest.as_ref() {
+        Some(snapshot) if !snapshot.temps.is_empty() || smi_temps(snapshot).next().is_some() => {
+            let rows = temp_rows(app, snapshot);
+            let fan_rows = usize::from(snapshot.fan.is_some());
+            let show_trail = inner.height as usize >= rows.len() + fan_rows;
+            let mut lines: Vec<Line> = rows
+                .iter()
+                .map(|row| {
+                    let value_c = Some(row.value_c).filter(|value| app.temp_range.contains(*value));
+                    let mut line = temp_line(&row.label, value_c, app.temp_unit, palette);
+                    let trail_width = (inner.width as usize).saturating_sub(line.width() + 1);
+                    if show_trail && let Some(series) = row.series {
+                        let trail = temp_trail(series, app.temp_range, trail_width, palette);
+                        if !trail.is_empty() {
+                            line.spans.push(Span::raw(" "));
+                            line.spans.extend(trail);
+                        }
+                    }
+                    line
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push(Line::from("No temps match the filter"));
+            }
+            lines.extend(snapshot.fan.map(fan_line));
+            lines
+        }
+        Some(_) => vec![Line::from("No temps")],
+        None => vec![Line::from("Waiting for tegrastats...")],
+    };
+
+    let (lines, scroll) = scroll_lines(lines, inner.height, app.temps_scroll, app.ascii);
+    frame.render_widget(Paragraph::new(lines), inner);
+    scroll
+}
+
+fn render_extra_panel(frame: &mut Frame, area: Rect, app: &AppState) {
+    let block = panel_block(app.ascii).title("Extra");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let palette = Palette::from_app(app);
+
+    let Some(snapshot) = app.latest.as_ref() else {
+        return;
+    };
+    let mut metrics: Vec<_> = snapshot.extra.iter().collect();
+    metrics.sort_by(|a, b| a.0.cmp(b.0));
+    let label_width = metrics.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    let lines = metrics
+        .into_iter()
+        .map(|(key, value)| extra_bar_line(key, *value, label_width, inner.width, palette))
+        .collect::<Vec<_>>();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Extra metrics have no known unit, so the bar assumes a 0-100 scale and
+/// the raw value is printed alongside.
+fn extra_bar_line(
+    key: &str,
+    value: f64,
+    label_width: usize,
+    width: u16,
+    palette: Palette,
+) -> Line<'static> {
+    let label = format!("{:<label_width$}", key);
+    let value_text = format!("{:>7.1}", value);
+    let bar_width = width
+        .saturating_sub(label.len() as u16 + value_text.len() as u16 + 4)
+        as usize;
+    let percent = value.clamp(0.0, 100.0);
+    let bar = make_bar(percent, bar_width);
+    let color = scaled_color(palette, palette.hues.cpu, percent);
+
+    Line::from(vec![
+        Span::styled(label, Style::default().fg(Color::Gray)),
+        Span::raw(" "),
+        Span::styled(format!("[{}]", bar), Style::default().fg(color)),
+        Span::raw(" "),
+        Span::raw(value_text),
+    ])
+}
+
+fn render_help(frame: &mut Frame, area: Rect, app: &mut AppState) {
+    let mut lines: Vec<Line> = KEY_BINDINGS
+        .iter()
+        .map(|binding| Line::from(format!("{:<9}{}", binding.label, binding.description)))
+        .collect();
+    lines.push(Line::from(""));
+    lines.extend(
+        MOUSE_HINTS
+            .iter()
+            .map(|(gesture, description)| Line::from(format!("{:<9}{}", gesture, description))),
+    );
+    let footer_style = Style::default().fg(Color::DarkGray);
+    let anomalies = app.anomalies.log();
+    if !anomalies.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from("Recent anomalies (newest first)"));
+        lines.extend(anomalies.iter().rev().map(|anomaly| {
+            let at = anomaly.at.saturating_duration_since(app.started_at);
+            Line::styled(
+                format!(
+                    "{:<9}{:?} {:.0} (z={:.1})",
+                    format_uptime(at),
+                    anomaly.metric,
+                    anomaly.value,
+                    anomaly.z_score
+                ),
+                footer_style,
+            )
+        }));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::styled(
+        format!(
+            "jmon {} (built {})",
+            env!("CARGO_PKG_VERSION"),
+            env!("JMON_BUILD_DATE")
+        ),
+        footer_style,
+    ));
+    lines.push(Line::styled(
+        format!("session uptime {}", format_uptime(app.started_at.elapsed())),
+        footer_style,
+    ));
+
+    let help_area = centered_rect_with_height(60, lines.len() as u16 + 2, area);
+    let visible = help_area.height.saturating_sub(2) as usize;
+    app.help_scroll = app.help_scroll.min(lines.len().saturating_sub(visible));
+    let title = if lines.len() > visible { "Help (Up/Down scrolls)" } else { "Help" };
+    let block = panel_block(app.ascii).title(title);
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Left)
+        .block(block)
+        .scroll((app.help_scroll as u16, 0));
+    frame.render_widget(Clear, help_area);
+    frame.render_widget(paragraph, help_area);
+}
+
+fn render_sources_editor(frame: &mut Frame, area: Rect, editor: &SourcesEditor, ascii: bool) {
+    let editor_area = centered_rect(60, 30, area);
+    let block = panel_block(ascii).title("Sources");
+    let field_line = |label: &str, value: &str, active: bool| {
+        let (marker, style) = if active {
+            ("> ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        } else {
+            ("  ", Style::default().fg(Color::Gray))
+        };
+        let cursor = if active { "_" } else { "" };
+        Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:<11}", label), style),
+            Span::raw(format!("{}{}", value, cursor)),
+        ])
+    };
     let lines = vec![
-        Line::from("q / Esc  quit"),
-        Line::from("h        toggle help"),
-        Line::from("r        reset history"),
-        Line::from("+/-      change tegrastats interval"),
+        field_line(
+            "tegrastats",
+            &editor.tegrastats,
+            editor.field == SourceField::Tegrastats,
+        ),
+        field_line(
+            "nvidia-smi",
+            &editor.nvidia_smi,
+            editor.field == SourceField::NvidiaSmi,
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Tab switch field  Enter apply  Esc cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
     ];
     let paragraph = Paragraph::new(lines).alignment(Alignment::Left).block(block);
-    frame.render_widget(Clear, help_area);
-    frame.render_widget(paragraph, help_area);
+    frame.render_widget(Clear, editor_area);
+    frame.render_widget(paragraph, editor_area);
 }
 
-fn core_bar_line(index: usize, percent: f32, width: u16, target: SparkRgb) -> Line<'static> {
+/// `percent` is `None` for an offline core, which renders as a dimmed
+/// `C05 offline` row instead of an empty bar.
+fn core_bar_line(
+    index: usize,
+    percent: Option<f32>,
+    freq_mhz: Option<u32>,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+) -> Line<'static> {
     let label = format!("C{:02}", index);
+    let Some(percent) = percent else {
+        return Line::styled(
+            format!("{} offline", label),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+        );
+    };
     let percent_text = format!("{:>3.0}%", percent);
-    let bar_width = width
-        .saturating_sub(label.len() as u16 + percent_text.len() as u16 + 4)
-        as usize;
+    let freq_text = freq_mhz.map(|mhz| format!(" @{}MHz", mhz)).unwrap_or_default();
+    let bar_width = width.saturating_sub(
+        label.len() as u16 + percent_text.len() as u16 + freq_text.len() as u16 + 4,
+    ) as usize;
     let bar = make_bar(percent as f64, bar_width);
-    let color = scaled_color(target, percent as f64);
-    let percent_color = heat_color(percent as f64, 0.0, 50.0, 100.0);
+    let color = scaled_color(palette, target, percent as f64);
+    let percent_color = heat_color(palette, percent as f64, palette.percent_heat);
 
     Line::from(vec![
         Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
@@ -430,10 +1501,69 @@ fn core_bar_line(index: usize, percent: f32, width: u16, target: SparkRgb) -> Li
         Span::styled(format!("[{}]", bar), Style::default().fg(color)),
         Span::raw(" "),
         Span::styled(percent_text, Style::default().fg(percent_color)),
+        Span::styled(freq_text, Style::default().fg(Color::Gray)),
     ])
 }
 
-fn memory_bar_line(snapshot: &StatsSnapshot, width: u16, target: SparkRgb) -> Line<'static> {
+/// Packs `per_row` compact `C00[###-]` bars onto each line, splitting the width evenly.
+fn compact_core_lines(
+    cores: &[f32],
+    online: &[bool],
+    per_row: usize,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+) -> Vec<Line<'static>> {
+    let (cell_width, rows) = core_grid(cores.len(), per_row, width);
+    rows.into_iter()
+        .map(|row| {
+            let spans = row
+                .flat_map(|idx| {
+                    let percent = cores[idx] as f64;
+                    let label = format!("C{:02}", idx);
+                    // Label, brackets, and a trailing gap between cells.
+                    let bar_width = cell_width.saturating_sub(label.len() + 3);
+                    let color = if online.get(idx).copied().unwrap_or(true) {
+                        scaled_color(palette, target, percent)
+                    } else {
+                        Color::DarkGray
+                    };
+                    let label_style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+                    [
+                        Span::styled(label, label_style),
+                        Span::styled(
+                            format!("[{}]", make_bar(percent, bar_width)),
+                            Style::default().fg(color),
+                        ),
+                        Span::raw(" "),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Returns the per-bar cell width and the core index range for each row.
+fn core_grid(count: usize, per_row: usize, width: u16) -> (usize, Vec<std::ops::Range<usize>>) {
+    let per_row = per_row.max(1);
+    let cell_width = width as usize / per_row;
+    let rows = (0..count)
+        .step_by(per_row)
+        .map(|start| start..(start + per_row).min(count))
+        .collect();
+    (cell_width, rows)
+}
+
+/// With `show_free` the bar fills with available memory instead of used, but
+/// stays colored by the used percent.
+fn memory_bar_line(
+    snapshot: &StatsSnapshot,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+    show_free: bool,
+) -> Line<'static> {
     let (used, total, percent) = match (snapshot.ram_used_mb, snapshot.ram_total_mb) {
         (Some(used), Some(total)) if total > 0 => {
             let percent = (used as f64 / total as f64) * 100.0;
@@ -443,12 +1573,44 @@ fn memory_bar_line(snapshot: &StatsSnapshot, width: u16, target: SparkRgb) -> Li
     };
 
     let label = "RAM";
-    let suffix = format!("{}/{}MB", used, total);
+    let gb = |mb: u64| mb as f64 / 1024.0;
+    let (suffix, fill) = if show_free {
+        let free = total.saturating_sub(used);
+        (format!("{:.1} GB free", gb(free)), 100.0 - percent)
+    } else {
+        (format!("{:.1}/{:.1} GB used", gb(used), gb(total)), percent)
+    };
+    let bar_width = width
+        .saturating_sub(label.len() as u16 + suffix.len() as u16 + 5)
+        as usize;
+    let bar = make_bar(fill, bar_width);
+    let color = scaled_color(palette, target, percent);
+
+    Line::from(vec![
+        Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        Span::styled(format!("[{}]", bar), Style::default().fg(color)),
+        Span::raw(" "),
+        Span::raw(suffix),
+    ])
+}
+
+fn mb_bar_line(
+    label: &str,
+    value_mb: u64,
+    total_mb: u64,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+) -> Line<'static> {
+    let percent = (value_mb as f64 / total_mb.max(1) as f64) * 100.0;
+    let label = format!("{:<4}", label);
+    let suffix = format!("{}/{}MB", value_mb, total_mb);
     let bar_width = width
         .saturating_sub(label.len() as u16 + suffix.len() as u16 + 5)
         as usize;
     let bar = make_bar(percent, bar_width);
-    let color = scaled_color(target, percent);
+    let color = scaled_color(palette, target, percent);
 
     Line::from(vec![
         Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
@@ -459,14 +1621,20 @@ fn memory_bar_line(snapshot: &StatsSnapshot, width: u16, target: SparkRgb) -> Li
     ])
 }
 
-fn power_bar_line(total_mw: u64, percent: f64, width: u16, target: SparkRgb) -> Line<'static> {
+fn power_bar_line(
+    total: &str,
+    percent: f64,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+) -> Line<'static> {
     let label = "TOTAL";
-    let suffix = format!("{}mW", total_mw);
+    let suffix = total.to_string();
     let bar_width = width
         .saturating_sub(label.len() as u16 + suffix.len() as u16 + 5)
         as usize;
     let bar = make_bar(percent, bar_width);
-    let color = scaled_color(target, percent);
+    let color = scaled_color(palette, target, percent);
 
     Line::from(vec![
         Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
@@ -477,12 +1645,124 @@ fn power_bar_line(total_mw: u64, percent: f64, width: u16, target: SparkRgb) ->
     ])
 }
 
-fn temp_line(name: &str, value_c: f32) -> Line<'static> {
+/// Fits `lines` into `height` rows from `scroll` on, returning them with
+/// the offset clamped to what they can show. When they do not all fit, the
+/// last row says how many lines are hidden below (or, scrolled to the end,
+/// above).
+fn scroll_lines(
+    lines: Vec<Line<'_>>,
+    height: u16,
+    scroll: usize,
+    ascii: bool,
+) -> (Vec<Line<'_>>, usize) {
+    let height = height as usize;
+    if height == 0 || lines.len() <= height {
+        return (lines, 0);
+    }
+    let visible = height - 1;
+    let offset = scroll.min(lines.len() - visible);
+    let below = lines.len() - offset - visible;
+    let marker = if below > 0 {
+        format!("{} more{}", below, if ascii { "..." } else { "\u{2026}" })
+    } else {
+        format!("{} above", offset)
+    };
+    let mut shown: Vec<Line> = lines.into_iter().skip(offset).take(visible).collect();
+    shown.push(Line::styled(marker, Style::default().fg(Color::DarkGray)));
+    (shown, offset)
+}
+
+/// One sensor in the Temps list.
+struct TempRow<'a> {
+    label: String,
+    /// History for the inline trail; nvidia-smi readings keep none.
+    series: Option<&'a TimedSeries>,
+    value_c: f32,
+}
+
+/// The tegrastats and nvidia-smi sensors the Temps list shows, after the
+/// filter and in the chosen order. The snapshot itself is left as read.
+fn temp_rows<'a>(app: &'a AppState, snapshot: &StatsSnapshot) -> Vec<TempRow<'a>> {
+    let tegrastats = snapshot.temps.iter().map(|temp| TempRow {
+        label: app.sensor_label(&temp.name).to_string(),
+        series: app.history.temps.get(&temp.name),
+        value_c: temp.value_c,
+    });
+    let smi = smi_temps(snapshot).map(|(index, value_c)| TempRow {
+        label: smi_label(snapshot, index),
+        series: None,
+        value_c,
+    });
+    let mut rows: Vec<TempRow> = tegrastats
+        .chain(smi)
+        .filter(|row| temp_filter_keeps(&app.temp_filter, &row.label, row.value_c, app.temp_unit))
+        .collect();
+    match app.temp_sort {
+        TempSort::Reported => {}
+        TempSort::Name => rows.sort_by_key(|row| row.label.to_lowercase()),
+        TempSort::Hottest => {
+            let plausible = |row: &TempRow| {
+                Some(row.value_c).filter(|value| app.temp_range.contains(*value))
+            };
+            rows.sort_by(|a, b| plausible(b).partial_cmp(&plausible(a)).unwrap_or(Ordering::Equal));
+        }
+    }
+    rows
+}
+
+/// Marks readings that come from nvidia-smi rather than tegrastats.
+fn smi_label(snapshot: &StatsSnapshot, index: usize) -> String {
+    if snapshot.gpus.len() > 1 {
+        format!("GPU{} nvidia-smi", index)
+    } else {
+        "GPU nvidia-smi".to_string()
+    }
+}
+
+/// `(gpu index, Celsius)` for every GPU whose nvidia-smi reports a temperature.
+fn smi_temps(snapshot: &StatsSnapshot) -> impl Iterator<Item = (usize, f32)> + '_ {
+    snapshot
+        .gpus
+        .iter()
+        .enumerate()
+        .filter_map(|(index, gpu)| gpu.temp_c.map(|temp| (index, temp)))
+}
+
+/// `Fan 42% 2380RPM`, leaving out whichever reading the board lacks.
+fn fan_line(fan: FanReading) -> Line<'static> {
+    let mut spans = vec![Span::styled("Fan", Style::default().fg(Color::Gray))];
+    if let Some(pwm) = fan.pwm_percent {
+        spans.push(Span::raw(format!(" {:.0}%", pwm)));
+    }
+    if let Some(rpm) = fan.rpm {
+        spans.push(Span::raw(format!(" {}RPM", rpm)));
+    }
+    Line::from(spans)
+}
+
+/// `None` marks an implausible reading, drawn dimmed as `?` rather than heat-colored.
+/// Colors by the Celsius reading so the heat thresholds don't depend on `unit`.
+fn temp_line(
+    name: &str,
+    value_c: Option<f32>,
+    unit: TempUnit,
+    palette: Palette,
+) -> Line<'static> {
     let label = name.to_string();
-    let value = format!("{:>5.1}C", value_c);
     let label_style = Style::default().fg(Color::Gray);
-    let value_color = heat_color(value_c as f64, 30.0, 60.0, 85.0);
-    let value_style = Style::default().fg(value_color).add_modifier(Modifier::BOLD);
+    let (value, value_style) = match value_c {
+        Some(value_c) => {
+            let color = heat_color(palette, value_c as f64, palette.temp_heat);
+            (
+                format!("{:>6}", unit.format(value_c)),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )
+        }
+        None => (
+            format!("{:>6}", "?"),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+        ),
+    };
 
     Line::from(vec![
         Span::styled(label, label_style),
@@ -491,15 +1771,55 @@ fn temp_line(name: &str, value_c: f32) -> Line<'static> {
     ])
 }
 
-fn bar_line(label: &str, percent: f32, width: u16, target: SparkRgb) -> Line<'static> {
+/// Inline trail of a sensor's recent readings, scaled to its own min/max so
+/// small drifts stay visible. Implausible readings are left out.
+fn temp_trail(
+    series: &TimedSeries,
+    plausible: TempRange,
+    width: usize,
+    palette: Palette,
+) -> Vec<Span<'static>> {
+    let values: Vec<u64> = series
+        .values
+        .iter()
+        .copied()
+        .filter(|value| plausible.contains(*value as f32))
+        .collect();
+    let values = &values[values.len().saturating_sub(width)..];
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let levels = 8;
+
+    values
+        .iter()
+        .map(|value| {
+            let level = if max > min {
+                (value - min) * (levels - 1) / (max - min)
+            } else {
+                levels / 2
+            };
+            let color = heat_color(palette, *value as f64, palette.temp_heat);
+            Span::styled(palette.glyph(level + 1), Style::default().fg(color))
+        })
+        .collect()
+}
+
+fn bar_line(
+    label: &str,
+    percent: f32,
+    width: u16,
+    palette: Palette,
+    target: SparkRgb,
+) -> Line<'static> {
     let label = label.to_string();
     let percent_text = format!("{:>3.0}%", percent);
     let bar_width = width
         .saturating_sub(label.len() as u16 + percent_text.len() as u16 + 4)
         as usize;
     let bar = make_bar(percent as f64, bar_width);
-    let color = scaled_color(target, percent as f64);
-    let percent_color = heat_color(percent as f64, 0.0, 50.0, 100.0);
+    let color = scaled_color(palette, target, percent as f64);
+    let percent_color = heat_color(palette, percent as f64, palette.percent_heat);
 
     Line::from(vec![
         Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
@@ -530,6 +1850,37 @@ fn pane_title(index: u8, name: &str, value: Option<String>, accent: Color) -> Li
     Line::from(spans)
 }
 
+/// Panel border drawn with `+`, `-` and `|` for terminals without box-drawing glyphs.
+const ASCII_BORDER: symbols::border::Set = symbols::border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+fn panel_block<'a>(ascii: bool) -> Block<'a> {
+    let block = Block::default().borders(Borders::ALL);
+    if ascii {
+        block.border_set(ASCII_BORDER)
+    } else {
+        block
+    }
+}
+
+/// A dashboard panel's block, with its border highlighted when selected.
+fn pane_block<'a>(app: &AppState, pane: Pane) -> Block<'a> {
+    let block = panel_block(app.ascii);
+    if app.selected_pane == Some(pane) {
+        block.border_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+    } else {
+        block
+    }
+}
+
 fn make_bar(percent: f64, width: usize) -> String {
     if width == 0 {
         return String::new();
@@ -539,6 +1890,264 @@ fn make_bar(percent: f64, width: usize) -> String {
     format!("{}{}", "#".repeat(filled), "-".repeat(empty))
 }
 
+/// Sparkline columns for `series`, ending at the scrollback cursor while paused.
+fn series_sparkline(app: &AppState, series: &TimedSeries, width: u16) -> Vec<u64> {
+    let cursor = app.cursor_time();
+    if app.align_history {
+        aligned_sparkline_data(
+            series,
+            width,
+            Duration::from_millis(app.interval_ms.max(1)),
+            cursor.or(app.paused_at).unwrap_or_else(Instant::now),
+        )
+    } else if let Some(cursor) = cursor {
+        let kept = series.times.iter().take_while(|at| **at <= cursor).count();
+        let values: VecDeque<u64> = series.values.iter().take(kept).copied().collect();
+        sparkline_data(&values, width)
+    } else {
+        sparkline_data(&series.values, width)
+    }
+}
+
+/// [`series_sparkline`] after the moving average picked with the smoothing
+/// key; only graphs use it, so exports and stats keep the raw samples.
+fn graph_sparkline(app: &AppState, series: &TimedSeries, width: u16) -> Vec<u64> {
+    if app.smoothing > 1 {
+        series_sparkline(app, &series.smoothed(app.smoothing), width)
+    } else {
+        series_sparkline(app, series, width)
+    }
+}
+
+/// Appends the smoothing window to a graph pane's title while it is on.
+fn with_smoothing(mut title: Line<'static>, app: &AppState) -> Line<'static> {
+    if app.smoothing > 1 {
+        title.spans.push(Span::styled(
+            format!(" [avg {}]", app.smoothing),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    title
+}
+
+/// Takes the bottom row of a sparkline area for its time axis, if the area is
+/// tall enough to spare it.
+fn split_time_axis(area: Rect) -> (Rect, Option<Rect>) {
+    if area.height < 3 {
+        return (area, None);
+    }
+    let spark = Rect {
+        height: area.height - 1,
+        ..area
+    };
+    let axis = Rect {
+        y: area.bottom() - 1,
+        height: 1,
+        ..area
+    };
+    (spark, Some(axis))
+}
+
+/// The real samples a `width`-column sparkline of `series` currently shows,
+/// oldest first and without the zero padding.
+fn visible_samples(app: &AppState, series: &TimedSeries, width: u16) -> VecDeque<u64> {
+    let cursor = app.cursor_time();
+    let shown = series
+        .iter_timed()
+        .filter(|(at, _)| cursor.is_none_or(|cursor| *at <= cursor));
+    if app.align_history {
+        let now = cursor.or(app.paused_at).unwrap_or_else(Instant::now);
+        let span = Duration::from_millis(app.interval_ms.max(1)) * u32::from(width);
+        shown
+            .filter(|(at, _)| now.saturating_duration_since(*at) < span)
+            .map(|(_, value)| value)
+            .collect()
+    } else {
+        let mut values: VecDeque<u64> = shown.map(|(_, value)| value).collect();
+        values.drain(..values.len().saturating_sub(width as usize));
+        values
+    }
+}
+
+/// Min, mean and max of `values`; all zero when there are none.
+fn series_stats(values: &VecDeque<u64>) -> (u64, f64, u64) {
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return (0, 0.0, 0);
+    };
+    let avg = values.iter().sum::<u64>() as f64 / values.len() as f64;
+    (*min, avg, *max)
+}
+
+/// Takes the bottom row of a sparkline area for its stats footer, as long as
+/// two rows of graph are left.
+fn split_stats_footer(area: Rect) -> (Rect, Option<Rect>) {
+    if area.height < 3 {
+        return (area, None);
+    }
+    let spark = Rect {
+        height: area.height - 1,
+        ..area
+    };
+    let footer = Rect {
+        y: area.bottom() - 1,
+        height: 1,
+        ..area
+    };
+    (spark, Some(footer))
+}
+
+/// One dim `min / avg / max` line; nothing is drawn until there are samples.
+fn render_stats_footer(
+    frame: &mut Frame,
+    area: Rect,
+    values: &VecDeque<u64>,
+    format: impl Fn(f64) -> String,
+) {
+    if area.is_empty() || values.is_empty() {
+        return;
+    }
+    let (min, avg, max) = series_stats(values);
+    let text = format!(
+        "min {} / avg {} / max {}",
+        format(min as f64),
+        format(avg),
+        format(max as f64)
+    );
+    frame.render_widget(
+        Paragraph::new(Line::styled(text, Style::default().fg(Color::DarkGray))),
+        area,
+    );
+}
+
+/// The CPU and GPU footer: min/avg/max, or p50/p95/p99 after `u` to show
+/// how often the load peaks.
+fn render_util_footer(frame: &mut Frame, area: Rect, app: &AppState, values: &VecDeque<u64>) {
+    if !app.show_percentiles {
+        render_stats_footer(frame, area, values, |value| format!("{:.0}%", value));
+        return;
+    }
+    if area.is_empty() || values.is_empty() {
+        return;
+    }
+    let text = format!(
+        "p50 {}% / p95 {}% / p99 {}%",
+        percentile(values, 50.0),
+        percentile(values, 95.0),
+        percentile(values, 99.0)
+    );
+    frame.render_widget(
+        Paragraph::new(Line::styled(text, Style::default().fg(Color::DarkGray))),
+        area,
+    );
+}
+
+fn render_time_axis(frame: &mut Frame, area: Option<Rect>, app: &AppState) {
+    let Some(area) = area else {
+        return;
+    };
+    let end_age = match (app.paused_at, app.cursor_time()) {
+        (Some(paused_at), Some(cursor)) => paused_at.saturating_duration_since(cursor),
+        _ => Duration::ZERO,
+    };
+    let per_cell = spark_samples(Palette::from_app(app), 1) as u32;
+    let interval = Duration::from_millis(app.interval_ms.max(1)) * per_cell;
+    let axis = time_axis_line(area.width, interval, end_age);
+    let style = Style::default().fg(Color::DarkGray);
+    frame.render_widget(Paragraph::new(Line::styled(axis, style)), area);
+}
+
+/// Tick labels for a sparkline with one column per `interval` whose rightmost
+/// column is `end_age` old, e.g. `-60s          -30s           now`.
+fn time_axis_line(width: u16, interval: Duration, end_age: Duration) -> String {
+    let width = width as usize;
+    if width == 0 {
+        return String::new();
+    }
+    let label = |column: usize| {
+        let age = end_age + interval * (width - 1 - column) as u32;
+        match age.as_secs() {
+            0 => "now".to_string(),
+            secs if secs < 120 => format!("-{}s", secs),
+            secs => format!("-{}m", secs / 60),
+        }
+    };
+    let mut axis = vec![' '; width];
+    let mut place = |text: String, start: usize| {
+        for (offset, ch) in text.chars().enumerate() {
+            if let Some(slot) = axis.get_mut(start + offset) {
+                *slot = ch;
+            }
+        }
+    };
+
+    if width < 12 {
+        let right = label(width.saturating_sub(1));
+        place(right.clone(), width.saturating_sub(right.len()));
+    } else {
+        let right = label(width - 1);
+        let middle = label(width / 2);
+        place(label(0), 0);
+        place(middle.clone(), width / 2 - middle.len() / 2);
+        place(right.clone(), width - right.len());
+    }
+    axis.into_iter().collect()
+}
+
+/// Highlights the rightmost column, which holds the sample under the
+/// scrollback cursor while paused.
+fn mark_cursor(frame: &mut Frame, area: Rect, app: &AppState) {
+    if area.is_empty() || app.cursor_time().is_none() {
+        return;
+    }
+    let buffer = frame.buffer_mut();
+    for y in area.top()..area.bottom() {
+        let cell = buffer.get_mut(area.right() - 1, y);
+        cell.set_style(cell.style().add_modifier(Modifier::REVERSED));
+    }
+}
+
+/// Age, wall-clock time and main values of the sample under the cursor.
+fn cursor_readout(app: &AppState) -> Option<String> {
+    let at = app.cursor_time()?;
+    let age = app.paused_at?.saturating_duration_since(at);
+    let wall = Local::now() - chrono::Duration::from_std(at.elapsed()).ok()?;
+    let history = &app.history;
+    let mut parts = vec![format!("-{}s {}", age.as_secs(), wall.format("%H:%M:%S"))];
+    if let Some(cpu) = history.cpu_total.value_at(at) {
+        parts.push(format!("CPU {}%", cpu));
+    }
+    if let Some(ram) = history.ram_used.value_at(at) {
+        parts.push(format!("RAM {}MB", ram));
+    }
+    if let Some(gpu) = history.gpu_util.value_at(at) {
+        parts.push(format!("GPU {}%", gpu));
+    }
+    if let Some(power) = history.power_total.value_at(at) {
+        parts.push(app.power_unit.format(power));
+    }
+    Some(parts.join("  "))
+}
+
+/// Buckets samples into interval-sized slots ending at `now`, so every series
+/// shares one time axis. Slots without a sample are left empty.
+fn aligned_sparkline_data(
+    series: &TimedSeries,
+    width: u16,
+    interval: Duration,
+    now: Instant,
+) -> Vec<u64> {
+    let width = width as usize;
+    let mut values = vec![0; width];
+    for (at, value) in series.iter_timed() {
+        let age = now.saturating_duration_since(at);
+        let slot = (age.as_nanos() / interval.as_nanos().max(1)) as usize;
+        if slot < width {
+            values[width - 1 - slot] = value;
+        }
+    }
+    values
+}
+
 fn sparkline_data(data: &VecDeque<u64>, width: u16) -> Vec<u64> {
     let width = width as usize;
     if width == 0 {
@@ -560,42 +2169,104 @@ fn sparkline_data(data: &VecDeque<u64>, width: u16) -> Vec<u64> {
     values
 }
 
+/// How sparkline columns are colored.
+#[derive(Clone, Copy, Debug)]
+enum SparkColor {
+    /// Blend from the palette base toward the target as the column gets taller.
+    Intensity(SparkRgb),
+    /// Green/yellow/red by whether the sample is past the warn/crit percent of the scale.
+    Threshold { warn: f64, crit: f64 },
+}
+
+const SPARK_WARN_PERCENT: f64 = 50.0;
+const SPARK_CRIT_PERCENT: f64 = 85.0;
+
+fn spark_color(app: &AppState, metric: SparkMetric, target: SparkRgb) -> SparkColor {
+    if app.threshold_sparks.contains(&metric) {
+        SparkColor::Threshold {
+            warn: SPARK_WARN_PERCENT,
+            crit: SPARK_CRIT_PERCENT,
+        }
+    } else {
+        SparkColor::Intensity(target)
+    }
+}
+
+/// Recolors the sparkline columns whose sample was flagged as an anomaly.
+fn mark_anomalies(
+    frame: &mut Frame,
+    area: Rect,
+    app: &AppState,
+    metric: SparkMetric,
+    palette: Palette,
+) {
+    let Some(flags) = app.anomalies.flags(metric) else {
+        return;
+    };
+    let color = palette.quantize(palette.hues.anomaly.into());
+    let buffer = frame.buffer_mut();
+    let samples = series_sparkline(app, flags, spark_samples(palette, area.width));
+    let per_cell = spark_samples(palette, 1) as usize;
+    let columns = samples.chunks(per_cell).map(|cell| cell.iter().copied().max().unwrap_or(0));
+    for (i, _) in columns.enumerate().filter(|(_, flag)| *flag > 0) {
+        for y in area.top()..area.bottom() {
+            buffer.get_mut(area.left() + i as u16, y).set_fg(color);
+        }
+    }
+}
+
 fn render_sparkline(
     frame: &mut Frame,
     area: Rect,
     data: &[u64],
-    target: SparkRgb,
+    palette: Palette,
+    color_mode: SparkColor,
     max_override: Option<u64>,
+) {
+    let scale = SparkScale::Linear;
+    render_scaled_sparkline(frame, area, data, palette, color_mode, max_override, scale);
+}
+
+/// Decades below the max a log-scale sparkline shows; anything smaller,
+/// including zero, sits on this floor.
+const LOG_SCALE_DECADES: f64 = 3.0;
+
+/// Where `value` sits between the log floor and `max`, from 0 to 1.
+fn log_fraction(value: u64, max: u64) -> f64 {
+    let floor = max as f64 / 10f64.powf(LOG_SCALE_DECADES);
+    let value = (value as f64).clamp(floor, max as f64);
+    (value / floor).log10() / LOG_SCALE_DECADES
+}
+
+fn render_scaled_sparkline(
+    frame: &mut Frame,
+    area: Rect,
+    data: &[u64],
+    palette: Palette,
+    color_mode: SparkColor,
+    max_override: Option<u64>,
+    scale: SparkScale,
 ) {
     if area.is_empty() || data.is_empty() {
         return;
     }
 
     let max = max_override.unwrap_or_else(|| data.iter().copied().max().unwrap_or(1).max(1));
+    if palette.braille {
+        render_braille_sparkline(frame, area, data, palette, color_mode, max, scale);
+        return;
+    }
     let height = area.height as u64;
-    let bar_set = symbols::bar::NINE_LEVELS;
-    let base = SparkRgb::base();
 
     let buffer = frame.buffer_mut();
     let width = area.width as usize;
 
     for (i, value) in data.iter().take(width).enumerate() {
-        let mut scaled = value.saturating_mul(height * 8) / max;
-        let intensity = adjust_intensity(*value as f64 / max as f64);
-        let color = blend_color(base, target, intensity);
+        let (mut scaled, shown) = spark_level(*value, max, height * 8, scale);
+        let color = spark_cell_color(palette, color_mode, *value as f64 / max as f64, shown);
 
         for row in 0..area.height {
-            let symbol = match scaled {
-                0 => bar_set.empty,
-                1 => bar_set.one_eighth,
-                2 => bar_set.one_quarter,
-                3 => bar_set.three_eighths,
-                4 => bar_set.half,
-                5 => bar_set.five_eighths,
-                6 => bar_set.three_quarters,
-                7 => bar_set.seven_eighths,
-                _ => bar_set.full,
-            };
+            let symbol = palette.glyph(scaled);
             let x = area.left() + i as u16;
             let y = area.bottom().saturating_sub(1 + row);
             buffer
@@ -612,6 +2283,88 @@ fn render_sparkline(
     }
 }
 
+/// Like the block sparkline, but each cell holds two samples as 2x4 Braille
+/// dots, doubling the time resolution. `data` has two samples per column.
+fn render_braille_sparkline(
+    frame: &mut Frame,
+    area: Rect,
+    data: &[u64],
+    palette: Palette,
+    color_mode: SparkColor,
+    max: u64,
+    scale: SparkScale,
+) {
+    // Dot bits of the left and right Braille columns, bottom dot first.
+    const LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+    const RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+    let steps = area.height as u64 * 4;
+    let buffer = frame.buffer_mut();
+
+    for (i, pair) in data.chunks(2).take(area.width as usize).enumerate() {
+        let levels: Vec<(u64, f64)> =
+            pair.iter().map(|value| spark_level(*value, max, steps, scale)).collect();
+        let peak = pair.iter().copied().max().unwrap_or(0);
+        let shown = levels.iter().map(|(_, shown)| *shown).fold(0.0, f64::max);
+        let color = spark_cell_color(palette, color_mode, peak as f64 / max as f64, shown);
+
+        for row in 0..area.height as u64 {
+            let mut bits = 0;
+            for ((level, _), dots) in levels.iter().zip([LEFT, RIGHT]) {
+                let filled = level.saturating_sub(row * 4).min(4) as usize;
+                bits |= dots[..filled].iter().fold(0, |acc, dot| acc | dot);
+            }
+            let symbol = match bits {
+                0 => " ".to_string(),
+                bits => char::from_u32(0x2800 + bits).unwrap_or(' ').to_string(),
+            };
+            let x = area.left() + i as u16;
+            let y = area.bottom().saturating_sub(1 + row as u16);
+            buffer
+                .get_mut(x, y)
+                .set_symbol(&symbol)
+                .set_style(Style::default().fg(color));
+        }
+    }
+}
+
+/// Filled steps (of `steps`) for `value`, and how far up the scale that is.
+fn spark_level(value: u64, max: u64, steps: u64, scale: SparkScale) -> (u64, f64) {
+    match scale {
+        SparkScale::Linear => (value.saturating_mul(steps) / max, value as f64 / max as f64),
+        SparkScale::Log if value == 0 => (0, 0.0),
+        SparkScale::Log => {
+            let shown = log_fraction(value, max);
+            ((shown * steps as f64).round() as u64, shown)
+        }
+    }
+}
+
+/// Threshold colors go by the linear `fraction` of the scale; intensity
+/// follows what is `shown`, which differs on a log scale.
+fn spark_cell_color(palette: Palette, color_mode: SparkColor, fraction: f64, shown: f64) -> Color {
+    match color_mode {
+        SparkColor::Intensity(target) => {
+            palette.quantize(blend_color(palette.base, target, adjust_intensity(shown)))
+        }
+        SparkColor::Threshold { warn, crit } => {
+            let percent = fraction * 100.0;
+            let level = if percent >= crit {
+                palette.hues.hot
+            } else if percent >= warn {
+                palette.hues.warm
+            } else {
+                palette.hues.ok
+            };
+            palette.quantize(level.into())
+        }
+    }
+}
+
+/// How many samples a sparkline `width` cells wide shows.
+fn spark_samples(palette: Palette, width: u16) -> u16 {
+    if palette.braille { width.saturating_mul(2) } else { width }
+}
+
 #[derive(Clone, Copy, Debug)]
 struct SparkRgb {
     r: u8,
@@ -620,41 +2373,138 @@ struct SparkRgb {
 }
 
 impl SparkRgb {
-    const fn base() -> Self {
-        Self { r: 255, g: 255, b: 255 }
-    }
-
-    const fn cpu() -> Self {
-        Self { r: 40, g: 200, b: 120 }
-    }
-
-    const fn ram() -> Self {
-        Self { r: 230, g: 180, b: 30 }
-    }
-
-    const fn gpu() -> Self {
-        Self { r: 70, g: 200, b: 200 }
+    const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
     }
+}
 
-    const fn emc() -> Self {
-        Self { r: 90, g: 140, b: 230 }
-    }
+/// Per-metric target colors and heat-scale anchors of one `--palette`.
+#[derive(Debug)]
+struct Hues {
+    cpu: SparkRgb,
+    ram: SparkRgb,
+    gpu: SparkRgb,
+    emc: SparkRgb,
+    power: SparkRgb,
+    swap: SparkRgb,
+    lfb: SparkRgb,
+    /// Power rail label colors, picked by a hash of the rail name.
+    rails: &'static [SparkRgb],
+    anomaly: SparkRgb,
+    ok: SparkRgb,
+    cool: SparkRgb,
+    warm: SparkRgb,
+    hot: SparkRgb,
+}
 
-    const fn power() -> Self {
-        Self { r: 220, g: 90, b: 90 }
+const DEFAULT_HUES: Hues = Hues {
+    cpu: SparkRgb::new(40, 200, 120),
+    ram: SparkRgb::new(230, 180, 30),
+    gpu: SparkRgb::new(70, 200, 200),
+    emc: SparkRgb::new(90, 140, 230),
+    power: SparkRgb::new(220, 90, 90),
+    swap: SparkRgb::new(200, 110, 200),
+    lfb: SparkRgb::new(190, 120, 230),
+    rails: &[
+        SparkRgb::new(240, 120, 80),
+        SparkRgb::new(80, 200, 120),
+        SparkRgb::new(90, 160, 240),
+        SparkRgb::new(230, 200, 60),
+        SparkRgb::new(200, 110, 220),
+        SparkRgb::new(70, 210, 210),
+        SparkRgb::new(250, 150, 190),
+        SparkRgb::new(170, 200, 90),
+    ],
+    anomaly: SparkRgb::new(255, 0, 255),
+    ok: SparkRgb::new(80, 200, 80),
+    cool: SparkRgb::new(60, 150, 255),
+    warm: SparkRgb::new(255, 210, 0),
+    hot: SparkRgb::new(255, 90, 90),
+};
+
+/// Okabe-Ito colors; the heat scale runs blue to yellow to vermillion so no
+/// two steps differ only along the red-green axis.
+const DEUTERANOPIA_HUES: Hues = Hues {
+    cpu: SparkRgb::new(86, 180, 233),
+    ram: SparkRgb::new(230, 159, 0),
+    gpu: SparkRgb::new(240, 228, 66),
+    emc: SparkRgb::new(0, 114, 178),
+    power: SparkRgb::new(213, 94, 0),
+    swap: SparkRgb::new(204, 121, 167),
+    lfb: SparkRgb::new(170, 140, 230),
+    rails: &[
+        SparkRgb::new(230, 159, 0),
+        SparkRgb::new(86, 180, 233),
+        SparkRgb::new(0, 158, 115),
+        SparkRgb::new(240, 228, 66),
+        SparkRgb::new(0, 114, 178),
+        SparkRgb::new(213, 94, 0),
+        SparkRgb::new(204, 121, 167),
+    ],
+    anomaly: SparkRgb::new(255, 0, 255),
+    ok: SparkRgb::new(0, 114, 178),
+    cool: SparkRgb::new(86, 180, 233),
+    warm: SparkRgb::new(240, 228, 66),
+    hot: SparkRgb::new(213, 94, 0),
+};
+
+/// One blue hue everywhere; heat and load only change its brightness.
+const MONO_HUES: Hues = Hues {
+    cpu: SparkRgb::new(80, 170, 255),
+    ram: SparkRgb::new(80, 170, 255),
+    gpu: SparkRgb::new(80, 170, 255),
+    emc: SparkRgb::new(80, 170, 255),
+    power: SparkRgb::new(80, 170, 255),
+    swap: SparkRgb::new(80, 170, 255),
+    lfb: SparkRgb::new(80, 170, 255),
+    rails: &[SparkRgb::new(80, 170, 255)],
+    anomaly: SparkRgb::new(190, 230, 255),
+    ok: SparkRgb::new(40, 80, 130),
+    cool: SparkRgb::new(40, 80, 130),
+    warm: SparkRgb::new(80, 170, 255),
+    hot: SparkRgb::new(190, 230, 255),
+};
+
+impl Hues {
+    fn of(scheme: ColorScheme) -> &'static Self {
+        match scheme {
+            ColorScheme::Default => &DEFAULT_HUES,
+            ColorScheme::Deuteranopia => &DEUTERANOPIA_HUES,
+            ColorScheme::Mono => &MONO_HUES,
+        }
     }
+}
 
-    const fn cool() -> Self {
-        Self { r: 60, g: 150, b: 255 }
+impl From<Rgb> for SparkRgb {
+    fn from(rgb: Rgb) -> Self {
+        Self {
+            r: rgb.r,
+            g: rgb.g,
+            b: rgb.b,
+        }
     }
+}
 
-    const fn warm() -> Self {
-        Self { r: 255, g: 210, b: 0 }
+impl From<SparkRgb> for Color {
+    fn from(rgb: SparkRgb) -> Self {
+        Color::Rgb(rgb.r, rgb.g, rgb.b)
     }
+}
 
-    const fn hot() -> Self {
-        Self { r: 255, g: 90, b: 90 }
-    }
+/// A rail's color from `--rail-color`, or else one of the palette's rail
+/// hues picked by an FNV-1a hash of its name, so it stays the same across
+/// runs and boards.
+fn rail_color(app: &AppState, palette: Palette, name: &str) -> Color {
+    let rgb = match app.rail_colors.get(name) {
+        Some(rgb) => SparkRgb::from(*rgb),
+        None => {
+            let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+                (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+            });
+            palette.hues.rails[hash as usize % palette.hues.rails.len()]
+        }
+    };
+    palette.quantize(Color::Rgb(rgb.r, rgb.g, rgb.b))
 }
 
 fn blend_color(base: SparkRgb, target: SparkRgb, t: f64) -> Color {
@@ -675,9 +2525,9 @@ fn blend_rgb(start: SparkRgb, end: SparkRgb, t: f64) -> Color {
     Color::Rgb(r.round() as u8, g.round() as u8, b.round() as u8)
 }
 
-fn scaled_color(target: SparkRgb, percent: f64) -> Color {
+fn scaled_color(palette: Palette, target: SparkRgb, percent: f64) -> Color {
     let t = adjust_intensity(percent / 100.0);
-    blend_color(SparkRgb::base(), target, t)
+    palette.quantize(blend_color(palette.base, target, t))
 }
 
 fn adjust_intensity(t: f64) -> f64 {
@@ -689,15 +2539,186 @@ fn adjust_intensity(t: f64) -> f64 {
     }
 }
 
-fn heat_color(value: f64, low: f64, mid: f64, high: f64) -> Color {
+fn heat_color(palette: Palette, value: f64, bounds: HeatBounds) -> Color {
+    let HeatBounds { low, mid, high } = bounds;
     let value = value.clamp(low, high);
-    if value <= mid {
+    let color = if value <= mid {
         let t = if mid <= low { 0.0 } else { (value - low) / (mid - low) };
-        blend_rgb(SparkRgb::cool(), SparkRgb::warm(), t)
+        blend_rgb(palette.hues.cool, palette.hues.warm, t)
     } else {
         let t = if high <= mid { 1.0 } else { (value - mid) / (high - mid) };
-        blend_rgb(SparkRgb::warm(), SparkRgb::hot(), t)
+        blend_rgb(palette.hues.warm, palette.hues.hot, t)
+    };
+    palette.quantize(color)
+}
+
+/// Color and glyph settings shared by every bar, sparkline, and heat readout.
+#[derive(Clone, Copy, Debug)]
+struct Palette {
+    base: SparkRgb,
+    depth: ColorDepth,
+    hues: &'static Hues,
+    ascii: bool,
+    /// Two samples per sparkline cell as Braille dots; never set with `ascii`.
+    braille: bool,
+    temp_heat: HeatBounds,
+    percent_heat: HeatBounds,
+}
+
+impl Palette {
+    fn from_app(app: &AppState) -> Self {
+        Self {
+            base: SparkRgb::from(app.spark_base),
+            depth: app.color_depth,
+            hues: Hues::of(app.color_scheme),
+            ascii: app.ascii,
+            braille: app.braille && !app.ascii,
+            temp_heat: app.temp_heat,
+            percent_heat: app.percent_heat,
+        }
     }
+
+    /// One sparkline cell filled `eighths`/8 of the way (8 or more is full).
+    /// ASCII mode only has four steps, so it rounds to `. : | #`.
+    fn glyph(self, eighths: u64) -> &'static str {
+        if self.ascii {
+            return match eighths {
+                0 => " ",
+                1..=2 => ".",
+                3..=4 => ":",
+                5..=6 => "|",
+                _ => "#",
+            };
+        }
+        let bar_set = symbols::bar::NINE_LEVELS;
+        match eighths {
+            0 => bar_set.empty,
+            1 => bar_set.one_eighth,
+            2 => bar_set.one_quarter,
+            3 => bar_set.three_eighths,
+            4 => bar_set.half,
+            5 => bar_set.five_eighths,
+            6 => bar_set.three_quarters,
+            7 => bar_set.seven_eighths,
+            _ => bar_set.full,
+        }
+    }
+
+    /// A pane's title accent: `hue` pulled a quarter of the way toward the
+    /// theme's base, so it reads on light and dark backgrounds alike.
+    fn accent(self, hue: SparkRgb) -> Color {
+        self.quantize(blend_color(self.base, hue, 0.75))
+    }
+
+    /// Maps a computed RGB color down to what the terminal can display.
+    fn quantize(self, color: Color) -> Color {
+        match (self.depth, color) {
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_ansi256(r, g, b)),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => rgb_to_ansi16(r, g, b),
+            _ => color,
+        }
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (**level as i32 - v as i32).abs())
+            .map(|(idx, _)| idx as u8)
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = (
+        CUBE_LEVELS[ri as usize],
+        CUBE_LEVELS[gi as usize],
+        CUBE_LEVELS[bi as usize],
+    );
+
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = (avg.saturating_sub(8) / 10).min(23) as u8;
+    let gray_level = 8 + gray_index * 10;
+    let gray = (gray_level, gray_level, gray_level);
+
+    if color_distance((r, g, b), gray) < color_distance((r, g, b), cube) {
+        232 + gray_index
+    } else {
+        16 + 36 * ri + 6 * gi + bi
+    }
+}
+
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::Gray, (229, 229, 229)),
+        (Color::DarkGray, (127, 127, 127)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (92, 92, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| color_distance((r, g, b), *rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+fn color_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn format_uptime(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h{:02}m{:02}s", hours, minutes, seconds)
+    } else {
+        format!("{}m{:02}s", minutes, seconds)
+    }
+}
+
+/// Coarse hours and minutes for estimates, e.g. `2h15m` or `42m`.
+fn format_runtime(runtime: Duration) -> String {
+    let minutes = runtime.as_secs() / 60;
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h{:02}m", hours, minutes % 60),
+    }
+}
+
+/// Like [`centered_rect`] but with a fixed height (clamped to the area) so the
+/// popup always fits its content.
+fn centered_rect_with_height(percent_x: u16, height: u16, r: Rect) -> Rect {
+    let height = height.min(r.height);
+    let y = r.y + (r.height - height) / 2;
+    let row = Rect::new(r.x, y, r.width, height);
+    let horizontal = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(row);
+
+    horizontal[1]
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -721,3 +2742,552 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
     horizontal[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    use ratatui::backend::TestBackend;
+    use ratatui::style::Color;
+    use ratatui::text::Line;
+    use ratatui::Terminal;
+
+    use super::{
+        aligned_sparkline_data, core_columns, core_grid, draw, format_interval, format_uptime,
+        heatmap_rows, log_fraction, memory_bar_line, render_gpu_panel, render_power_panel,
+        render_sparkline, render_table_view, rgb_to_ansi256, right_column_constraints,
+        scaled_color, scroll_lines, series_stats, spark_samples, temp_line, temp_rows,
+        time_axis_line, rail_color, vin_line, Hues, Palette, SparkColor, SparkRgb, DEFAULT_HUES,
+    };
+    use crate::model::{
+        parse_heat_bounds, parse_rgb, AppState, ColorDepth, ColorScheme, CpuAggregate, GpuStats,
+        HeatBounds, PowerRail, Rgb, StatsSnapshot, TempReading, TempSort, TempUnit, TimedSeries,
+    };
+
+    fn palette(base: Rgb, depth: ColorDepth) -> Palette {
+        Palette {
+            base: SparkRgb::from(base),
+            depth,
+            hues: &DEFAULT_HUES,
+            ascii: false,
+            braille: false,
+            temp_heat: HeatBounds::TEMP,
+            percent_heat: HeatBounds::PERCENT,
+        }
+    }
+
+    #[test]
+    fn core_columns_only_split_when_needed_and_wide_enough() {
+        assert_eq!(core_columns(14, 20, 120, 24), 1);
+        assert_eq!(core_columns(14, 7, 120, 24), 2);
+        assert_eq!(core_columns(14, 3, 120, 24), 5);
+        assert_eq!(core_columns(14, 3, 60, 24), 2);
+        assert_eq!(core_columns(14, 3, 20, 24), 1);
+        assert_eq!(core_columns(0, 0, 0, 24), 1);
+    }
+
+    #[test]
+    fn series_stats_covers_min_avg_and_max() {
+        assert_eq!(series_stats(&VecDeque::from([10, 40, 25])), (10, 25.0, 40));
+        assert_eq!(series_stats(&VecDeque::new()), (0, 0.0, 0));
+    }
+
+    #[test]
+    fn log_fraction_spreads_small_values_and_floors_zero() {
+        assert_eq!(log_fraction(30_000, 30_000), 1.0);
+        assert!((log_fraction(300, 30_000) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(log_fraction(0, 30_000), 0.0);
+        assert_eq!(log_fraction(1, 1), 1.0);
+    }
+
+    #[test]
+    fn braille_sparkline_packs_two_samples_per_cell() {
+        let mut palette = palette(Rgb::WHITE, ColorDepth::TrueColor);
+        palette.braille = true;
+        let mut terminal = Terminal::new(TestBackend::new(2, 2)).unwrap();
+        let buffer = terminal
+            .draw(|frame| {
+                let area = frame.size();
+                let color = SparkColor::Intensity(palette.hues.ram);
+                render_sparkline(frame, area, &[0, 8, 4, 8], palette, color, Some(8));
+            })
+            .unwrap()
+            .buffer
+            .clone();
+        let text: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+
+        assert_eq!(text, "\u{28b8}\u{28b8}\u{28b8}\u{28ff}");
+        assert_eq!(spark_samples(palette, 30), 60);
+    }
+
+    #[test]
+    fn formats_whole_seconds() {
+        assert_eq!(format_interval(250), "250ms");
+        assert_eq!(format_interval(500), "500ms");
+        assert_eq!(format_interval(1000), "1s");
+        assert_eq!(format_interval(1500), "1500ms");
+        assert_eq!(format_interval(2000), "2s");
+    }
+
+    #[test]
+    fn base_override_changes_scaled_color() {
+        let target = DEFAULT_HUES.cpu;
+        let gray = parse_rgb("#303030").unwrap();
+        let white = scaled_color(palette(Rgb::WHITE, ColorDepth::TrueColor), target, 10.0);
+        let gray = scaled_color(palette(gray, ColorDepth::TrueColor), target, 10.0);
+        assert_ne!(white, gray);
+        assert_eq!(parse_rgb("48,48,48"), parse_rgb("#303030"));
+    }
+
+    #[test]
+    fn mono_palette_shares_one_hue_across_metrics() {
+        let with = |scheme| Palette {
+            hues: Hues::of(scheme),
+            ..palette(Rgb::WHITE, ColorDepth::TrueColor)
+        };
+        let mono = with(ColorScheme::Mono);
+        assert_eq!(
+            scaled_color(mono, mono.hues.cpu, 80.0),
+            scaled_color(mono, mono.hues.power, 80.0)
+        );
+        let default = with(ColorScheme::Default);
+        assert_ne!(
+            scaled_color(default, default.hues.cpu, 80.0),
+            scaled_color(default, default.hues.power, 80.0)
+        );
+        let deuteranopia = with(ColorScheme::Deuteranopia);
+        assert_ne!(
+            scaled_color(deuteranopia, deuteranopia.hues.hot, 100.0),
+            scaled_color(default, default.hues.hot, 100.0)
+        );
+    }
+
+    #[test]
+    fn maps_rgb_to_ansi256() {
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        assert_eq!(rgb_to_ansi256(95, 135, 175), 67);
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+
+        let quantized = palette(Rgb::WHITE, ColorDepth::Ansi256).quantize(Color::Rgb(255, 0, 0));
+        assert_eq!(quantized, Color::Indexed(196));
+        let untouched = palette(Rgb::WHITE, ColorDepth::TrueColor).quantize(Color::Rgb(1, 2, 3));
+        assert_eq!(untouched, Color::Rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn aligns_differently_spaced_series() {
+        let interval = Duration::from_millis(500);
+        let now = Instant::now() + Duration::from_secs(10);
+        let mut every = TimedSeries::default();
+        let mut sparse = TimedSeries::default();
+        for step in (0..6u32).rev() {
+            let at = now - interval * step;
+            every.push(16, 10 + step as u64, at);
+            if step % 2 == 0 {
+                sparse.push(16, 20 + step as u64, at);
+            }
+        }
+
+        assert_eq!(
+            aligned_sparkline_data(&every, 6, interval, now),
+            vec![15, 14, 13, 12, 11, 10]
+        );
+        assert_eq!(
+            aligned_sparkline_data(&sparse, 6, interval, now),
+            vec![0, 24, 0, 22, 0, 20]
+        );
+    }
+
+    #[test]
+    fn compresses_heatmap_rows() {
+        assert_eq!(heatmap_rows(4, 10), vec![0..1, 1..2, 2..3, 3..4]);
+        assert_eq!(heatmap_rows(14, 5), vec![0..3, 3..6, 6..9, 9..12, 12..14]);
+        assert!(heatmap_rows(0, 5).is_empty());
+    }
+
+    #[test]
+    fn formats_session_uptime() {
+        assert_eq!(format_uptime(Duration::from_secs(59)), "0m59s");
+        assert_eq!(format_uptime(Duration::from_secs(3723)), "1h02m03s");
+    }
+
+    #[test]
+    fn labels_time_axis_from_interval_and_width() {
+        let second = Duration::from_secs(1);
+        assert_eq!(time_axis_line(21, second, Duration::ZERO), "-20s    -10s      now");
+        assert_eq!(
+            time_axis_line(21, second * 6, Duration::from_secs(30)),
+            "-2m     -90s     -30s"
+        );
+        assert_eq!(time_axis_line(5, second, Duration::ZERO), "  now");
+    }
+
+    #[test]
+    fn divides_width_and_wraps_core_grid() {
+        let (cell_width, rows) = core_grid(10, 4, 80);
+        assert_eq!(cell_width, 20);
+        assert_eq!(rows, vec![0..4, 4..8, 8..10]);
+
+        let (cell_width, rows) = core_grid(3, 0, 30);
+        assert_eq!(cell_width, 30);
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn renders_table_view_for_snapshot() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        app.latest = Some(StatsSnapshot {
+            cpu_cores: vec![10.0, 30.0],
+            ram_used_mb: Some(1024),
+            ram_total_mb: Some(4096),
+            gpu_util: Some(55.0),
+            temps: vec![TempReading {
+                name: "tj".to_string(),
+                value_c: 41.5,
+            }],
+            power_rails: vec![PowerRail {
+                name: "VDD_GPU".to_string(),
+                current_mw: 1200,
+                average_mw: 1100,
+            }],
+            ..StatsSnapshot::default()
+        });
+
+        let mut terminal = Terminal::new(TestBackend::new(50, 16)).unwrap();
+        terminal
+            .draw(|frame| render_table_view(frame, frame.size(), &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect();
+
+        assert!(text.contains("CPU total"));
+        assert!(text.contains("20%"));
+        assert!(text.contains("1024/4096MB"));
+        assert!(text.contains("Temp tj"));
+        assert!(text.contains("41.5C"));
+        assert!(text.contains("1200mW / 1100mW avg"));
+    }
+
+    #[test]
+    fn renders_a_bar_per_gpu_plus_average() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let mut snapshot = StatsSnapshot::default();
+        let gpu = |util| GpuStats {
+            util,
+            ..GpuStats::default()
+        };
+        snapshot.set_gpus(&[gpu(20.0), gpu(80.0)]);
+        app.latest = Some(snapshot);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal
+            .draw(|frame| render_gpu_panel(frame, frame.size(), &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect();
+
+        assert!(text.contains("GPU0"));
+        assert!(text.contains("GPU1"));
+        assert!(text.contains("avg"));
+        assert!(text.contains(" 50%"));
+    }
+
+    #[test]
+    fn pane_accents_follow_the_palette_and_theme() {
+        let accent = |scheme, base| {
+            let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+            app.color_scheme = scheme;
+            app.spark_base = base;
+            let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+            terminal
+                .draw(|frame| render_gpu_panel(frame, frame.size(), &app))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let index = (0..buffer.area.width)
+                .map(|x| buffer.get(x, 0))
+                .find(|cell| cell.symbol() == "3")
+                .expect("pane index");
+            index.fg
+        };
+
+        let dark = accent(ColorScheme::Default, Rgb::WHITE);
+        assert_ne!(dark, accent(ColorScheme::Mono, Rgb::WHITE));
+        assert_ne!(dark, accent(ColorScheme::Default, Rgb::NEAR_BLACK));
+    }
+
+    #[test]
+    fn implausible_gpu_temp_renders_dimmed_question_mark() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let mut snapshot = StatsSnapshot::default();
+        snapshot.set_gpus(&[GpuStats {
+            util: 20.0,
+            temp_c: Some(511.0),
+            power_mw: Some(5000),
+            ..GpuStats::default()
+        }]);
+        app.latest = Some(snapshot);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal
+            .draw(|frame| render_gpu_panel(frame, frame.size(), &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let (text, cells) = (0..buffer.area.height)
+            .map(|y| {
+                let cells: Vec<_> = (0..buffer.area.width).map(|x| buffer.get(x, y)).collect();
+                (cells.iter().map(|cell| cell.symbol()).collect::<String>(), cells)
+            })
+            .find(|(text, _)| text.contains("GPU nvidia-smi"))
+            .expect("nvidia-smi readings row");
+        assert!(!text.contains("511"), "{}", text);
+        let mark = cells.iter().find(|cell| cell.symbol() == "?").expect("? mark");
+        assert_eq!(mark.fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn ascii_mode_keeps_the_same_layout() {
+        let render = |ascii: bool| {
+            let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+            app.ascii = ascii;
+            let start = Instant::now();
+            for (i, current_mw) in [800, 3000, 5000, 1200].into_iter().enumerate() {
+                let snapshot = StatsSnapshot {
+                    power_rails: vec![PowerRail {
+                        name: "VDD_GPU".to_string(),
+                        current_mw,
+                        average_mw: current_mw,
+                    }],
+                    ..StatsSnapshot::default()
+                };
+                let at = start + Duration::from_secs(i as u64);
+                app.history.push_at(&snapshot, CpuAggregate::Mean, at);
+                app.latest = Some(snapshot);
+            }
+            let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+            terminal
+                .draw(|frame| {
+                    render_power_panel(frame, frame.size(), &app);
+                })
+                .unwrap();
+            terminal.backend().buffer().clone()
+        };
+        let unicode = render(false);
+        let ascii = render(true);
+
+        assert!(ascii.content.iter().all(|cell| cell.symbol().is_ascii()));
+        assert!(!unicode.content.iter().all(|cell| cell.symbol().is_ascii()));
+        for (plain, fancy) in ascii.content.iter().zip(&unicode.content) {
+            assert_eq!(plain.symbol() == " ", fancy.symbol() == " ");
+        }
+    }
+
+    #[test]
+    fn rail_colors_are_stable_per_name_and_overridable() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let palette = palette(Rgb::WHITE, ColorDepth::TrueColor);
+        let gpu = rail_color(&app, palette, "VDD_GPU_SOC");
+        assert_eq!(gpu, rail_color(&app, palette, "VDD_GPU_SOC"));
+        let names = ["VDD_GPU_SOC", "VDD_CPU_CV", "VIN_SYS_5V0", "VDDQ_VDD2_1V8AO"];
+        let distinct: std::collections::HashSet<String> = names
+            .iter()
+            .map(|name| format!("{:?}", rail_color(&app, palette, name)))
+            .collect();
+        assert!(distinct.len() > 1);
+
+        app.rail_colors.insert("VDD_GPU_SOC".to_string(), parse_rgb("#102030").unwrap());
+        assert_eq!(rail_color(&app, palette, "VDD_GPU_SOC"), Color::Rgb(16, 32, 48));
+    }
+
+    #[test]
+    fn vin_line_averages_recent_draw_and_estimates_runtime() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let text = |line: Line| -> String {
+            line.spans.iter().map(|span| span.content.as_ref()).collect()
+        };
+        assert_eq!(text(vin_line(&app, &StatsSnapshot::default())), "VIN \u{2014}");
+
+        let start = Instant::now();
+        let mut snapshot = StatsSnapshot::default();
+        // The first sample falls outside the 60s window and is not averaged.
+        for (secs, current_mw) in [(0, 30_000), (90, 10_000), (120, 14_000)] {
+            snapshot = StatsSnapshot {
+                power_rails: vec![PowerRail {
+                    name: "VIN".to_string(),
+                    current_mw,
+                    average_mw: current_mw,
+                }],
+                ..StatsSnapshot::default()
+            };
+            let at = start + Duration::from_secs(secs);
+            app.history.push_at(&snapshot, CpuAggregate::Mean, at);
+        }
+        assert_eq!(text(vin_line(&app, &snapshot)), "VIN 14000mW avg 12000mW");
+
+        app.battery_wh = Some(30.0);
+        assert_eq!(text(vin_line(&app, &snapshot)), "VIN 14000mW avg 12000mW ~2h30m left");
+    }
+
+    #[test]
+    fn threshold_mode_colors_high_samples_red() {
+        let mut terminal = Terminal::new(TestBackend::new(3, 2)).unwrap();
+        let threshold = SparkColor::Threshold {
+            warn: 50.0,
+            crit: 85.0,
+        };
+        terminal
+            .draw(|frame| {
+                let palette = palette(Rgb::WHITE, ColorDepth::TrueColor);
+                render_sparkline(frame, frame.size(), &[10, 60, 95], palette, threshold, Some(100));
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        assert_eq!(buffer.get(0, 1).fg, Color::Rgb(80, 200, 80));
+        assert_eq!(buffer.get(1, 1).fg, Color::Rgb(255, 210, 0));
+        assert_eq!(buffer.get(2, 1).fg, Color::Rgb(255, 90, 90));
+    }
+
+    #[test]
+    fn custom_temp_heat_bounds_reach_hot_earlier() {
+        let default = palette(Rgb::WHITE, ColorDepth::TrueColor);
+        let early = Palette {
+            temp_heat: parse_heat_bounds("20:30:50").unwrap(),
+            ..default
+        };
+        let value_color = |palette| {
+            let line = temp_line("tj", Some(50.0), TempUnit::Celsius, palette);
+            line.spans.last().expect("value span").style.fg
+        };
+
+        assert_eq!(value_color(early), Some(Color::Rgb(255, 90, 90)));
+        assert_ne!(value_color(default), value_color(early));
+        assert!(parse_heat_bounds("60:50:90").is_err());
+    }
+
+    #[test]
+    fn temp_rows_filter_and_sort_without_touching_the_snapshot() {
+        let temp = |name: &str, value_c| TempReading {
+            name: name.to_string(),
+            value_c,
+        };
+        let snapshot = StatsSnapshot {
+            temps: vec![
+                temp("tj", 48.0),
+                temp("cpu", 45.0),
+                temp("gpu", -256.0),
+                temp("soc", 52.0),
+            ],
+            ..StatsSnapshot::default()
+        };
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let labels = |app: &AppState| {
+            temp_rows(app, &snapshot).into_iter().map(|row| row.label).collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels(&app), ["tj", "cpu", "gpu", "soc"]);
+        app.temp_sort = TempSort::Hottest;
+        assert_eq!(labels(&app), ["soc", "tj", "cpu", "gpu"]);
+        app.temp_sort = TempSort::Name;
+        app.temp_filter = "!PU".to_string();
+        assert_eq!(labels(&app), ["soc", "tj"]);
+        app.temp_filter = ">118".to_string();
+        app.temp_unit = TempUnit::Fahrenheit;
+        assert_eq!(labels(&app), ["soc", "tj"]);
+        app.temp_filter = "c".to_string();
+        assert_eq!(labels(&app), ["cpu", "soc"]);
+        assert_eq!(snapshot.temps.len(), 4);
+    }
+
+    #[test]
+    fn overflowing_lists_scroll_and_count_hidden_lines() {
+        let lines = || (0..10).map(|n| Line::from(n.to_string())).collect::<Vec<_>>();
+        let text = |lines: Vec<Line>| {
+            lines.iter().map(|line| line.spans[0].content.to_string()).collect::<Vec<_>>()
+        };
+
+        let (shown, offset) = scroll_lines(lines(), 4, 0, true);
+        assert_eq!(text(shown), ["0", "1", "2", "7 more..."]);
+        assert_eq!(offset, 0);
+        let (shown, offset) = scroll_lines(lines(), 4, 2, true);
+        assert_eq!(text(shown), ["2", "3", "4", "5 more..."]);
+        assert_eq!(offset, 2);
+        let (shown, offset) = scroll_lines(lines(), 4, 50, true);
+        assert_eq!(text(shown), ["7", "8", "9", "7 above"]);
+        assert_eq!(offset, 7);
+        let (shown, offset) = scroll_lines(lines(), 12, 7, true);
+        assert_eq!(shown.len(), 10);
+        assert_eq!(offset, 0);
+    }
+
+    #[test]
+    fn implausible_temp_renders_dimmed_question_mark() {
+        let palette = palette(Rgb::WHITE, ColorDepth::TrueColor);
+        let line = temp_line("gpu", None, TempUnit::Celsius, palette);
+        let value = line.spans.last().expect("value span");
+
+        assert_eq!(value.content.trim(), "?");
+        assert_eq!(value.style.fg, Some(Color::DarkGray));
+    }
+
+    #[test]
+    fn memory_bar_shows_used_or_free() {
+        let snapshot = StatsSnapshot {
+            ram_used_mb: Some(1024),
+            ram_total_mb: Some(4096),
+            ..StatsSnapshot::default()
+        };
+        let palette = palette(Rgb::WHITE, ColorDepth::TrueColor);
+        let text = |show_free| {
+            let line = memory_bar_line(&snapshot, 32, palette, palette.hues.ram, show_free);
+            let color = line.spans[0].style.fg;
+            let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+            (text, color)
+        };
+
+        let (used, used_color) = text(false);
+        let (free, free_color) = text(true);
+        assert_eq!(used, "RAM [##-------] 1.0/4.0 GB used");
+        assert_eq!(free, "RAM [##########---] 3.0 GB free");
+        assert_eq!(used_color, free_color);
+    }
+
+    #[test]
+    fn tiny_terminal_shows_only_the_size_message() {
+        let mut app = AppState::new(String::new(), String::new(), 1000, 10);
+        let mut terminal = Terminal::new(TestBackend::new(50, 5)).unwrap();
+        let buffer = terminal
+            .draw(|frame| draw(frame, &mut app))
+            .unwrap()
+            .buffer
+            .clone();
+        let text: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+
+        assert!(text.contains("terminal too small (need 60x20, have 50x5)"));
+        assert!(!text.contains("jmon"));
+        assert!(app.buttons.minus.is_none());
+    }
+
+    #[test]
+    fn right_column_has_one_constraint_per_visible_pane() {
+        for visible in 0..=4 {
+            assert_eq!(right_column_constraints(visible).len(), visible);
+        }
+    }
+}